@@ -0,0 +1,210 @@
+//!
+//! Parallel evaluation of many variants of a network (design optimization, Monte Carlo studies) :
+//! each candidate is a vector of parameter values applied to a copy of the base network.
+//! The candidates are solved on all cores, the structure of the head system of the base network
+//! is analysed once and shared by all the computations.
+//!
+use std::fmt::Display;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::network::{Network, ParameterError, ParameterKind, ParameterSet, Target};
+use crate::solver::{HydraulicResults, Method, Solver, SolverError, Topology};
+
+// the network, the solver and the results are shared by or sent to the worker threads.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Network>();
+    assert_send_sync::<Solver<'_>>();
+    assert_send_sync::<HydraulicResults>();
+    assert_send_sync::<BatchError>();
+};
+
+///
+/// Failure of the evaluation of a candidate.
+///
+#[derive(Debug, Clone)]
+pub enum BatchError {
+    ///
+    /// The candidate has not one value per parameter.
+    ///
+    DimensionMismatch {
+        expected: usize,
+        found: usize,
+    },
+
+    ///
+    /// A value of the candidate can not be applied (unknown element, invalid value).
+    ///
+    Parameter(ParameterError),
+    Solver(SolverError),
+}
+
+impl Display for BatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DimensionMismatch { expected, found } => {
+                write!(f, "{} values expected, {} found", expected, found)
+            }
+            Self::Parameter(error) => write!(f, "{}", error),
+            Self::Solver(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+impl From<ParameterError> for BatchError {
+    fn from(error: ParameterError) -> Self {
+        BatchError::Parameter(error)
+    }
+}
+
+impl From<SolverError> for BatchError {
+    fn from(error: SolverError) -> Self {
+        BatchError::Solver(error)
+    }
+}
+
+pub struct Batch<'a> {
+    network: &'a Network,
+    parameters: Vec<(Target, ParameterKind)>,
+    method: Method,
+    objective_error: Option<f64>,
+}
+
+impl<'a> Batch<'a> {
+    ///
+    /// Batch of variants of the network : the values of a candidate are those of the parameter kind
+    /// for the targets, in the same order. The network itself is not changed.
+    ///
+    pub fn new(network: &'a Network, kind: ParameterKind, targets: Vec<Target>) -> Self {
+        Batch {
+            network,
+            parameters: targets.into_iter().map(|target| (target, kind)).collect(),
+            method: Method::default(),
+            objective_error: None,
+        }
+    }
+
+    ///
+    /// Add parameters of another kind : their values follow those of the previous parameters.
+    ///
+    pub fn add_parameters(&mut self, kind: ParameterKind, targets: Vec<Target>) {
+        self.parameters
+            .extend(targets.into_iter().map(|target| (target, kind)));
+    }
+
+    pub fn parameters(&self) -> &[(Target, ParameterKind)] {
+        &self.parameters
+    }
+
+    ///
+    /// Method of the computations (see Solver::set_method).
+    ///
+    pub fn set_method(&mut self, method: Method) {
+        self.method = method;
+    }
+
+    ///
+    /// Accuracy overriding the network options (None : Options::accuracy).
+    ///
+    pub fn set_objective_error(&mut self, objective_error: Option<f64>) {
+        self.objective_error = objective_error;
+    }
+
+    ///
+    /// Solve the candidates in parallel. Returns the results or the error of each candidate,
+    /// in the order of the candidates.
+    ///
+    pub fn run(&self, candidates: &[Vec<f64>]) -> Vec<Result<HydraulicResults, BatchError>> {
+        let topology = Arc::new(Topology::analyse(self.network));
+        candidates
+            .par_iter()
+            .map(|values| self.evaluate(&topology, values))
+            .collect()
+    }
+
+    fn evaluate(
+        &self,
+        topology: &Arc<Topology>,
+        values: &[f64],
+    ) -> Result<HydraulicResults, BatchError> {
+        if values.len() != self.parameters.len() {
+            return Err(BatchError::DimensionMismatch {
+                expected: self.parameters.len(),
+                found: values.len(),
+            });
+        }
+
+        let set = self
+            .parameters
+            .iter()
+            .zip(values)
+            .fold(ParameterSet::new(), |set, ((target, kind), value)| {
+                set.set(*target, kind.with_value(*value))
+            });
+        let mut wdn = self.network.clone();
+        wdn.apply_parameters(&set)?;
+
+        let mut solver = Solver::with_topology(&wdn, self.objective_error, Arc::clone(topology));
+        solver.set_method(self.method);
+        Ok(solver.compute()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_the_candidates_as_sequential_solves_in_order() {
+        let file = concat!(env!("CARGO_MANIFEST_DIR"), "/src/bin/data/Hanoi.inp");
+        let network = Network::read_from_file(file).unwrap();
+        let pipes = network.pipes.as_ref().unwrap();
+        let targets: Vec<Target> = pipes.iter().map(|p| Target::Id(p.id)).collect();
+
+        let sizes = [304.8, 406.4, 508.0, 609.6, 762.0, 1016.0];
+        let mut candidates: Vec<Vec<f64>> = (0..16)
+            .map(|i| {
+                (0..pipes.len())
+                    .map(|k| sizes[(i * 7 + k * (i + 3)) % sizes.len()])
+                    .collect()
+            })
+            .collect();
+        candidates.insert(5, vec![304.8; 3]);
+
+        let batch = Batch::new(&network, ParameterKind::PipeDiameter, targets);
+        let results = batch.run(&candidates);
+        assert_eq!(results.len(), candidates.len());
+
+        for (i, (candidate, result)) in candidates.iter().zip(results.iter()).enumerate() {
+            if i == 5 {
+                assert!(matches!(
+                    result,
+                    Err(BatchError::DimensionMismatch {
+                        expected: 34,
+                        found: 3
+                    })
+                ));
+                continue;
+            }
+            let mut wdn = network.clone();
+            for (pipe, diameter) in wdn.pipes.as_mut().unwrap().iter_mut().zip(candidate) {
+                pipe.diameter = *diameter;
+            }
+            let sequential = Solver::new(&wdn, None).compute().unwrap();
+            let result = result.as_ref().unwrap();
+            for (a, b) in result.nodes.iter().zip(sequential.nodes.iter()) {
+                assert_eq!(a.id, b.id);
+                assert!(
+                    (a.head - b.head).abs() < 1e-9,
+                    "candidate {} node {}",
+                    i,
+                    a.id
+                );
+            }
+        }
+    }
+}
@@ -87,11 +87,17 @@ fn show_static_wdn() {
 
     println!("Flow unit = {:?}", WDN.options.flow_unit);
 
-    let mut tmp_net = WDN.clone();
+    let tmp_net = WDN.clone();
 
-    let mut solver = Solver::new(&mut tmp_net, None);
+    let mut solver = Solver::new(&tmp_net, None);
 
-    let _outnet = solver.compute();
+    let results = match solver.compute() {
+        Ok(results) => results,
+        Err(error) => {
+            println!("Computation failed : {}", error);
+            return;
+        }
+    };
     println!(
         "Iters = {:?}, (DQ, DH) = {:?}, Time-Analysis: {:?} MS",
         solver.get_final_iterations(),
@@ -104,7 +110,7 @@ fn show_static_wdn() {
             println!("Pipes = {:?}", pipes.len());
 
             for p in pipes.iter() {
-                println!("{}, Q: {:?}", p.id, p.flow,);
+                println!("{}, Q: {:?}", p.id, results.flow(p.id));
             }
         }
     };
@@ -115,7 +121,7 @@ fn show_static_wdn() {
             println!("Junctions = {:?}", nodes.len());
             nodes
                 .iter()
-                .for_each(|jn| println!("id: {}, Pressure: {:?}", jn.id, jn.pressure()));
+                .for_each(|jn| println!("id: {}, Pressure: {:?}", jn.id, results.pressure(jn.id)));
         }
     };
 }
@@ -194,18 +200,13 @@ fn read_wdn_file() {
 
 #[allow(dead_code)]
 fn test_network3() {
-    let mut net3: Network = network3();
-    let mut solver: Solver = Solver::new(&mut net3, None);
-
-    let _result = solver.compute();
+    let net3: Network = network3();
+    let mut solver: Solver = Solver::new(&net3, None);
 
-    let (erq, erh) = solver.get_final_errors().unwrap();
-    println!(
-        "Q_error : {:?}; \n H_error : {:?}; \n Iterations : {:?}",
-        erq,
-        erh,
-        solver.get_final_iterations()
-    );
+    match solver.compute() {
+        Ok(results) => println!("{}", results.report),
+        Err(error) => println!("Computation failed : {}", error),
+    }
 
     /*  match result {
         None => println!("No simulation results !!!"),
@@ -234,17 +235,13 @@ fn test_network3() {
 
 #[allow(dead_code)]
 fn test_network1_todini() {
-    let mut net: Network = network1_todini();
-    let mut solver: Solver = Solver::new(&mut net, None);
+    let net: Network = network1_todini();
+    let mut solver: Solver = Solver::new(&net, None);
 
-    let _result = solver.compute();
-    let (erq, erh) = solver.get_final_errors().unwrap();
-    println!(
-        "Q_error : {:?}; \n H_error : {:?}; \n Iterations : {:?}",
-        erq,
-        erh,
-        solver.get_final_iterations()
-    );
+    match solver.compute() {
+        Ok(results) => println!("{}", results.report),
+        Err(error) => println!("Computation failed : {}", error),
+    }
 
     /* match result {
         None => println!("No simulation results !!!"),
@@ -294,8 +291,8 @@ fn test_network1_todini() {
 
 #[allow(dead_code)]
 fn test_network2_todini() {
-    let mut net: Network = network2_todini();
-    let mut solver: Solver = Solver::new(&mut net, None);
+    let net: Network = network2_todini();
+    let mut solver: Solver = Solver::new(&net, None);
 
     // change the m value.
     solver.set_m_parameter(100.0f64);
@@ -303,18 +300,15 @@ fn test_network2_todini() {
     use std::time::Instant;
     let now = Instant::now();
 
-    let _result = solver.compute();
+    let result = solver.compute();
 
     let elapsed = now.elapsed();
     println!("\n Time duration (Elapsed) T : {:.2?} \n", elapsed);
 
-    let (erq, erh) = solver.get_final_errors().unwrap();
-    println!(
-        "Q_error : {:?}; \n H_error : {:?}; \n Iterations : {:?}",
-        erq,
-        erh,
-        solver.get_final_iterations()
-    );
+    match result {
+        Ok(results) => println!("{}", results.report),
+        Err(error) => println!("Computation failed : {}", error),
+    }
 
     /* match result {
         None => println!("No simulation results !!!"),
@@ -367,20 +361,16 @@ fn test_network2_todini() {
 
 #[allow(dead_code)]
 fn test_network4() {
-    let mut net: Network = network4();
-    let mut solver: Solver = Solver::new(&mut net, None);
+    let net: Network = network4();
+    let mut solver: Solver = Solver::new(&net, None);
 
     // change the m value.
     solver.set_m_parameter(100.0f64);
 
-    let _result = solver.compute();
-    let (erq, erh) = solver.get_final_errors().unwrap();
-    println!(
-        "Q_error: \n {:?} \n; H_error : \n {:?}; Iterations : {:?}",
-        erq,
-        erh,
-        solver.get_final_iterations()
-    );
+    match solver.compute() {
+        Ok(results) => println!("{}", results.report),
+        Err(error) => println!("Computation failed : {}", error),
+    }
 
     /* match result {
         None => println!("No simulation results !!!"),
@@ -469,25 +459,22 @@ fn test_modena_net() {
 
     match wdn {
         Err(eror) => println!("Cannot read the file because of : {:?}.", eror),
-        Ok(mut net) => {
+        Ok(net) => {
             use std::time::Instant;
             let now = Instant::now();
 
-            let mut solver: Solver = Solver::new(&mut net, None);
+            let mut solver: Solver = Solver::new(&net, None);
             solver.set_m_parameter(100.00);
 
-            let _result = solver.compute();
+            let result = solver.compute();
 
             let elapsed = now.elapsed();
             println!("\n Time duration (Elapsed) T : {:.2?} \n", elapsed);
 
-            let (erq, erh) = solver.get_final_errors().unwrap();
-            println!(
-                "Q_error: \n {:?} \n; H_error : \n {:?}; Iterations : {:?}",
-                erq,
-                erh,
-                solver.get_final_iterations()
-            );
+            match result {
+                Ok(results) => println!("{}", results.report),
+                Err(error) => println!("Computation failed : {}", error),
+            }
 
             /* match result {
                 None => println!("No results !!!"),
@@ -555,36 +542,33 @@ fn test_modena_net() {
 
 #[allow(dead_code)]
 fn test_2loop_network() {
-    let mut net: Network = network_2loop();
+    let net: Network = network_2loop();
 
-    let mut solver: Solver = Solver::new(&mut net, Some(0.00001));
+    let mut solver: Solver = Solver::new(&net, Some(0.00001));
     solver.set_m_parameter(1000.0);
 
-    let _result = solver.compute();
-
-    let (erq, erh) = solver.get_final_errors().unwrap();
-    println!(
-        "Q_error : {:?}; \n H_error : {:?}; \n Iterations : {:?}",
-        erq,
-        erh,
-        solver.get_final_iterations()
-    );
-
-    let _net2 = solver.compute();
+    let results = match solver.compute() {
+        Ok(results) => results,
+        Err(error) => {
+            println!("Computation failed : {}", error);
+            return;
+        }
+    };
+    println!("{}", results.report);
 
     println!("Flow unit (After) = {:?}", net.options.flow_unit);
 
     if let Some(nodes) = &net.junctions {
         println!("___________[JUNCTIONS]____________");
         for jn in nodes.iter() {
-            println!("id: {}, P={:?} m.", jn.id, jn.pressure());
+            println!("id: {}, P={:?} m.", jn.id, results.pressure(jn.id));
         }
     }
 
     if let Some(pipes) = &net.pipes {
         println!("___________[PIPES]____________");
         for pip in pipes.iter() {
-            println!("id: {}, Q={:?} m3/s.", pip.id, pip.flow);
+            println!("id: {}, Q={:?} m3/s.", pip.id, results.flow(pip.id));
         }
     }
 
@@ -0,0 +1,1164 @@
+//!
+//! Versioned JSON documents of the network model and of the solver results.
+//!
+//! The documents are the stable exchange contract (FFI, .NET and web clients) : they do not
+//! depend on the internal structures. Older document versions are migrated on reading.
+//!
+use std::fmt::Display;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::network::link::pipe::PipeBuilder;
+use crate::network::link::pump::PumpBuilder;
+use crate::network::link::valve::ValveBuilder;
+use crate::network::link::{LinkStatus, LinkType, ValveType};
+use crate::network::{
+    Control, DemandModel, EnergyOptions, HeadlossFormula, JunctionBuilder, Network, NetworkBuilder,
+    Options, Pattern, PipeLeakage, Position, ReservoirBuilder, Rule, TankBuilder, TimeOptions,
+    Unbalanced,
+};
+use crate::solver::HydraulicResults;
+use crate::units::{flow_unit_keyword, parse_flow_unit};
+
+pub const NETWORK_FORMAT: &str = "atakoris-network";
+pub const RESULTS_FORMAT: &str = "atakoris-results";
+
+///
+/// Version of the documents written by this library.
+///
+pub const DOCUMENT_VERSION: u32 = 1;
+
+///
+/// Migration of a network document from version i to version i+1 (MIGRATIONS[i]).
+/// Version 0 is the serialization of the Network structure (before versioned documents).
+///
+const MIGRATIONS: [fn(Value) -> Result<Value, DocumentError>; 1] = [migrate_v0_to_v1];
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Json(serde_json::Error),
+
+    ///
+    /// The document is not of the expected format (network or results).
+    ///
+    Format(String),
+
+    ///
+    /// The document is newer than this library.
+    ///
+    UnsupportedVersion(u32),
+
+    ///
+    /// A value of the document is invalid (unknown keyword, ...).
+    ///
+    InvalidValue(String),
+}
+
+impl Display for DocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "json error : {}", err),
+            Self::Format(format) => write!(f, "unexpected document format : {}", format),
+            Self::UnsupportedVersion(v) => write!(
+                f,
+                "document version {} is not supported (latest : {})",
+                v, DOCUMENT_VERSION
+            ),
+            Self::InvalidValue(msg) => write!(f, "invalid value : {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(err: serde_json::Error) -> Self {
+        DocumentError::Json(err)
+    }
+}
+
+//------------------------------ Network document ------------------------------
+
+///
+/// The network model document (version 1).
+///
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NetworkDocument {
+    ///
+    /// Always "atakoris-network".
+    ///
+    pub format: String,
+    pub version: u32,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub options: OptionsDocument,
+    #[serde(default)]
+    pub junctions: Vec<JunctionDocument>,
+    #[serde(default)]
+    pub tanks: Vec<TankDocument>,
+    #[serde(default)]
+    pub reservoirs: Vec<ReservoirDocument>,
+    #[serde(default)]
+    pub pipes: Vec<PipeDocument>,
+    #[serde(default)]
+    pub pumps: Vec<PumpDocument>,
+    #[serde(default)]
+    pub valves: Vec<ValveDocument>,
+    #[serde(default)]
+    pub patterns: Vec<PatternDocument>,
+
+    ///
+    /// Simple controls, in the Epanet syntax (e.g. "LINK 9 OPEN IF NODE 2 BELOW 110").
+    ///
+    #[serde(default)]
+    pub controls: Vec<String>,
+
+    ///
+    /// Rule-based controls, in the Epanet syntax (one rule per item, clauses on separate lines).
+    ///
+    #[serde(default)]
+    pub rules: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OptionsDocument {
+    ///
+    /// Epanet flow unit keyword : CFS, GPM, MGD, IMGD, AFD, LPS, LPM, MLD, CMH, CMD or CMS.
+    ///
+    pub flow_unit: String,
+
+    ///
+    /// H-W, D-W or C-M.
+    ///
+    pub headloss_formula: String,
+    pub viscosity: f64,
+    pub trials: usize,
+    pub accuracy: f64,
+
+    ///
+    /// Number of extra trials if the solver does not converge, None : stop.
+    ///
+    #[serde(default)]
+    pub unbalanced_continue: Option<usize>,
+    pub pattern: usize,
+    pub demand_multiplier: f64,
+    pub emitter_exponent: f64,
+    pub demand_model: DemandModelDocument,
+    pub energy: EnergyDocument,
+    #[serde(default)]
+    pub times: TimesDocument,
+}
+
+///
+/// Times of the extended period simulation, in seconds.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TimesDocument {
+    pub duration: u64,
+    pub hydraulic_step: u64,
+    pub pattern_step: u64,
+    pub pattern_start: u64,
+    pub start_clocktime: u64,
+}
+
+impl Default for TimesDocument {
+    fn default() -> Self {
+        TimesDocument::from(&TimeOptions::default())
+    }
+}
+
+impl From<&TimeOptions> for TimesDocument {
+    fn from(times: &TimeOptions) -> Self {
+        TimesDocument {
+            duration: times.duration,
+            hydraulic_step: times.hydraulic_step,
+            pattern_step: times.pattern_step,
+            pattern_start: times.pattern_start,
+            start_clocktime: times.start_clocktime,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "model", rename_all = "UPPERCASE")]
+pub enum DemandModelDocument {
+    Dda,
+    Pda {
+        minimum_pressure: f64,
+        required_pressure: f64,
+        exponent: f64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EnergyDocument {
+    pub global_efficiency: f64,
+    pub global_price: f64,
+    pub demand_charge: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JunctionDocument {
+    pub id: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    pub elevation: f64,
+    #[serde(default)]
+    pub demand: f64,
+    #[serde(default)]
+    pub pattern: Option<usize>,
+    #[serde(default)]
+    pub emitter_coefficient: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TankDocument {
+    pub id: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    pub elevation: f64,
+    pub initial_level: f64,
+    #[serde(default)]
+    pub min_level: f64,
+
+    ///
+    /// None : the initial level.
+    ///
+    #[serde(default)]
+    pub max_level: Option<f64>,
+
+    ///
+    /// 0 : fixed level.
+    ///
+    #[serde(default)]
+    pub diameter: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PatternDocument {
+    pub id: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub multipliers: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReservoirDocument {
+    pub id: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    pub head: f64,
+    #[serde(default)]
+    pub pattern: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PipeDocument {
+    pub id: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub start: usize,
+    pub end: usize,
+    pub length: f64,
+    pub diameter: f64,
+    pub roughness: f64,
+    #[serde(default)]
+    pub minor_loss: f64,
+    #[serde(default)]
+    pub material: Option<String>,
+
+    ///
+    /// OPEN or CLOSED.
+    ///
+    #[serde(default = "open_status")]
+    pub status: String,
+    #[serde(default)]
+    pub check_valve: bool,
+    #[serde(default)]
+    pub leakage: Option<LeakageDocument>,
+
+    ///
+    /// Intermediate points [x, y] of the pipe drawing.
+    ///
+    #[serde(default)]
+    pub vertices: Vec<[f32; 2]>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "model", rename_all = "UPPERCASE")]
+pub enum LeakageDocument {
+    Power { coefficient: f64, exponent: f64 },
+    Favad { area: f64, expansion: f64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PumpDocument {
+    pub id: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub start: usize,
+    pub end: usize,
+
+    ///
+    /// Head curve : alpha x Q^2 + beta x Q + gamma (alpha = 0 : constant power pump).
+    ///
+    pub alpha: f64,
+    pub beta: f64,
+    pub gamma: f64,
+    #[serde(default)]
+    pub power_rating: f64,
+    #[serde(default = "open_status")]
+    pub status: String,
+
+    ///
+    /// Efficiency curve points [flow, efficiency in %].
+    ///
+    #[serde(default)]
+    pub efficiency_curve: Vec<[f64; 2]>,
+
+    ///
+    /// Relative speed (1.0 : nominal speed).
+    ///
+    #[serde(default = "nominal_speed")]
+    pub speed: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ValveDocument {
+    pub id: usize,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub start: usize,
+    pub end: usize,
+
+    ///
+    /// FCV, PBV, PRV, TCV, PSV or GPV.
+    ///
+    pub valve_type: String,
+    pub diameter: f64,
+    #[serde(default)]
+    pub length: f64,
+    #[serde(default)]
+    pub roughness: f64,
+    #[serde(default)]
+    pub minor_loss: f64,
+    pub setting: f64,
+    #[serde(default = "open_status")]
+    pub status: String,
+}
+
+fn nominal_speed() -> f64 {
+    1.0
+}
+
+fn open_status() -> String {
+    "OPEN".to_owned()
+}
+
+fn status_keyword(status: LinkStatus) -> String {
+    match status {
+        LinkStatus::Open => "OPEN".to_owned(),
+        LinkStatus::Closed => "CLOSED".to_owned(),
+    }
+}
+
+fn parse_status(keyword: &str) -> Result<LinkStatus, DocumentError> {
+    match keyword.to_uppercase().as_str() {
+        "OPEN" => Ok(LinkStatus::Open),
+        "CLOSED" => Ok(LinkStatus::Closed),
+        _ => Err(DocumentError::InvalidValue(format!(
+            "link status {}",
+            keyword
+        ))),
+    }
+}
+
+fn parse_valve_type(keyword: &str) -> Result<ValveType, DocumentError> {
+    match keyword.to_uppercase().as_str() {
+        "FCV" => Ok(ValveType::FCV),
+        "PBV" => Ok(ValveType::PBV),
+        "PRV" => Ok(ValveType::PRV),
+        "TCV" => Ok(ValveType::TCV),
+        "PSV" => Ok(ValveType::PSV),
+        "GPV" => Ok(ValveType::GPV),
+        _ => Err(DocumentError::InvalidValue(format!(
+            "valve type {}",
+            keyword
+        ))),
+    }
+}
+
+impl From<&Options> for OptionsDocument {
+    fn from(options: &Options) -> Self {
+        OptionsDocument {
+            flow_unit: flow_unit_keyword(options.flow_unit).to_owned(),
+            headloss_formula: match options.headloss_formula {
+                HeadlossFormula::Hw => "H-W",
+                HeadlossFormula::Dw => "D-W",
+                HeadlossFormula::Cm => "C-M",
+            }
+            .to_owned(),
+            viscosity: options.viscosity,
+            trials: options.trials,
+            accuracy: options.accuracy,
+            unbalanced_continue: match options.unbalanced {
+                Unbalanced::StopIter => None,
+                Unbalanced::ContinueIter(n) => Some(n),
+            },
+            pattern: options.pattern,
+            demand_multiplier: options.demand_multiplier,
+            emitter_exponent: options.emitter_exponent,
+            demand_model: match options.demand_model {
+                DemandModel::Dda => DemandModelDocument::Dda,
+                DemandModel::Pda {
+                    minimum_pressure,
+                    required_pressure,
+                    exponent,
+                } => DemandModelDocument::Pda {
+                    minimum_pressure,
+                    required_pressure,
+                    exponent,
+                },
+            },
+            energy: EnergyDocument {
+                global_efficiency: options.energy.global_efficiency,
+                global_price: options.energy.global_price,
+                demand_charge: options.energy.demand_charge,
+            },
+            times: TimesDocument::from(&options.times),
+        }
+    }
+}
+
+impl TryFrom<&OptionsDocument> for Options {
+    type Error = DocumentError;
+
+    fn try_from(doc: &OptionsDocument) -> Result<Self, Self::Error> {
+        Ok(Options {
+            flow_unit: parse_flow_unit(&doc.flow_unit).ok_or_else(|| {
+                DocumentError::InvalidValue(format!("flow unit {}", doc.flow_unit))
+            })?,
+            headloss_formula: match doc.headloss_formula.to_uppercase().as_str() {
+                "H-W" => HeadlossFormula::Hw,
+                "D-W" => HeadlossFormula::Dw,
+                "C-M" => HeadlossFormula::Cm,
+                other => {
+                    return Err(DocumentError::InvalidValue(format!(
+                        "headloss formula {}",
+                        other
+                    )));
+                }
+            },
+            viscosity: doc.viscosity,
+            trials: doc.trials,
+            accuracy: doc.accuracy,
+            unbalanced: match doc.unbalanced_continue {
+                None => Unbalanced::StopIter,
+                Some(n) => Unbalanced::ContinueIter(n),
+            },
+            pattern: doc.pattern,
+            demand_multiplier: doc.demand_multiplier,
+            emitter_exponent: doc.emitter_exponent,
+            energy: EnergyOptions {
+                global_efficiency: doc.energy.global_efficiency,
+                global_price: doc.energy.global_price,
+                demand_charge: doc.energy.demand_charge,
+            },
+            demand_model: match doc.demand_model {
+                DemandModelDocument::Dda => DemandModel::Dda,
+                DemandModelDocument::Pda {
+                    minimum_pressure,
+                    required_pressure,
+                    exponent,
+                } => DemandModel::Pda {
+                    minimum_pressure,
+                    required_pressure,
+                    exponent,
+                },
+            },
+            times: TimeOptions {
+                duration: doc.times.duration,
+                hydraulic_step: doc.times.hydraulic_step,
+                pattern_step: doc.times.pattern_step,
+                pattern_start: doc.times.pattern_start,
+                start_clocktime: doc.times.start_clocktime,
+            },
+        })
+    }
+}
+
+impl From<&Network> for NetworkDocument {
+    fn from(network: &Network) -> Self {
+        NetworkDocument {
+            format: NETWORK_FORMAT.to_owned(),
+            version: DOCUMENT_VERSION,
+            title: network.title.clone(),
+            options: OptionsDocument::from(&network.options),
+            junctions: network.junctions.as_ref().map_or(Vec::new(), |items| {
+                items
+                    .iter()
+                    .map(|jn| JunctionDocument {
+                        id: jn.id,
+                        name: jn.name.clone(),
+                        x: jn.position.x,
+                        y: jn.position.y,
+                        elevation: jn.elevation,
+                        demand: jn.demand,
+                        pattern: jn.pattern,
+                        emitter_coefficient: jn.emitter_coefficient,
+                    })
+                    .collect()
+            }),
+            tanks: network.tanks.as_ref().map_or(Vec::new(), |items| {
+                items
+                    .iter()
+                    .map(|tnk| TankDocument {
+                        id: tnk.id,
+                        name: tnk.name.clone(),
+                        x: tnk.position.x,
+                        y: tnk.position.y,
+                        elevation: tnk.elevation,
+                        initial_level: tnk.initial_level,
+                        min_level: tnk.min_level,
+                        max_level: Some(tnk.max_level),
+                        diameter: tnk.diameter,
+                    })
+                    .collect()
+            }),
+            reservoirs: network.reservoirs.as_ref().map_or(Vec::new(), |items| {
+                items
+                    .iter()
+                    .map(|rsrvr| ReservoirDocument {
+                        id: rsrvr.id,
+                        name: rsrvr.name.clone(),
+                        x: rsrvr.position.x,
+                        y: rsrvr.position.y,
+                        head: rsrvr.head,
+                        pattern: rsrvr.pattern,
+                    })
+                    .collect()
+            }),
+            pipes: network.pipes.as_ref().map_or(Vec::new(), |items| {
+                items
+                    .iter()
+                    .map(|pipe| PipeDocument {
+                        id: pipe.id,
+                        name: pipe.name.clone(),
+                        start: pipe.start,
+                        end: pipe.end,
+                        length: pipe.length,
+                        diameter: pipe.diameter,
+                        roughness: pipe.roughness,
+                        minor_loss: pipe.minor_loss,
+                        material: pipe.material.clone(),
+                        status: status_keyword(pipe.status),
+                        check_valve: pipe.check_valve,
+                        leakage: pipe.leakage.map(|lk| match lk {
+                            PipeLeakage::Power {
+                                coefficient,
+                                exponent,
+                            } => LeakageDocument::Power {
+                                coefficient,
+                                exponent,
+                            },
+                            PipeLeakage::Favad { area, expansion } => {
+                                LeakageDocument::Favad { area, expansion }
+                            }
+                        }),
+                        vertices: pipe.vertices.as_ref().map_or(Vec::new(), |vrtxs| {
+                            vrtxs.iter().map(|v| [v.x, v.y]).collect()
+                        }),
+                    })
+                    .collect()
+            }),
+            pumps: network.pumps.as_ref().map_or(Vec::new(), |items| {
+                items
+                    .iter()
+                    .map(|pump| PumpDocument {
+                        id: pump.id,
+                        name: pump.name.clone(),
+                        start: pump.start,
+                        end: pump.end,
+                        alpha: pump.alpha,
+                        beta: pump.beta,
+                        gamma: pump.gamma,
+                        power_rating: pump.power_rating,
+                        status: status_keyword(pump.status),
+                        speed: pump.speed,
+                        efficiency_curve: pump
+                            .efficiency_curve
+                            .as_ref()
+                            .map_or(Vec::new(), |points| {
+                                points.iter().map(|(q, e)| [*q, *e]).collect()
+                            }),
+                    })
+                    .collect()
+            }),
+            valves: network.valves.as_ref().map_or(Vec::new(), |items| {
+                items
+                    .iter()
+                    .map(|valve| ValveDocument {
+                        id: valve.id,
+                        name: valve.name.clone(),
+                        start: valve.start,
+                        end: valve.end,
+                        valve_type: valve.valvetype.to_string(),
+                        diameter: valve.diameter,
+                        length: valve.length,
+                        roughness: valve.roughness,
+                        minor_loss: valve.minor_loss,
+                        setting: valve.k_value,
+                        status: status_keyword(valve.status),
+                    })
+                    .collect()
+            }),
+            patterns: network.patterns.as_ref().map_or(Vec::new(), |items| {
+                items
+                    .iter()
+                    .map(|pattern| PatternDocument {
+                        id: pattern.id,
+                        name: pattern.name.clone(),
+                        multipliers: pattern.multipliers.clone(),
+                    })
+                    .collect()
+            }),
+            controls: network.controls.as_ref().map_or(Vec::new(), |items| {
+                items.iter().map(|control| control.to_string()).collect()
+            }),
+            rules: network.rules.as_ref().map_or(Vec::new(), |items| {
+                items.iter().map(|rule| rule.to_string()).collect()
+            }),
+        }
+    }
+}
+
+impl NetworkDocument {
+    ///
+    /// Read a network document of any supported version (older versions are migrated).
+    ///
+    pub fn from_json(json: &str) -> Result<Self, DocumentError> {
+        let mut value: Value = serde_json::from_str(json)?;
+
+        let version = match value.get("format") {
+            None => 0,
+            Some(format) => {
+                if format.as_str() != Some(NETWORK_FORMAT) {
+                    return Err(DocumentError::Format(format.to_string()));
+                }
+                value
+                    .get("version")
+                    .and_then(|v| v.as_u64())
+                    .ok_or_else(|| DocumentError::InvalidValue("missing version".to_owned()))?
+                    as u32
+            }
+        };
+        if version > DOCUMENT_VERSION {
+            return Err(DocumentError::UnsupportedVersion(version));
+        }
+        for migration in MIGRATIONS.iter().skip(version as usize) {
+            value = migration(value)?;
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    ///
+    /// Build the network described by the document.
+    ///
+    pub fn to_network(&self) -> Result<Network, DocumentError> {
+        if self.format != NETWORK_FORMAT {
+            return Err(DocumentError::Format(self.format.clone()));
+        }
+        let options = Options::try_from(&self.options)?;
+
+        let junctions = self
+            .junctions
+            .iter()
+            .map(|jn| {
+                let mut builder = JunctionBuilder::new()
+                    .set_id(jn.id)
+                    .set_position(Position::new(jn.x, jn.y))
+                    .set_elevation(jn.elevation)
+                    .set_demand(jn.demand)
+                    .set_pattern(jn.pattern)
+                    .set_emitter_coefficient(jn.emitter_coefficient);
+                if let Some(name) = &jn.name {
+                    builder = builder.set_name(name);
+                }
+                builder.build()
+            })
+            .collect();
+
+        let tanks = self
+            .tanks
+            .iter()
+            .map(|tnk| {
+                let mut builder = TankBuilder::new()
+                    .set_id(tnk.id)
+                    .set_position(Position::new(tnk.x, tnk.y))
+                    .set_elevation(tnk.elevation)
+                    .set_initial_level(tnk.initial_level)
+                    .set_levels(tnk.min_level, tnk.max_level.unwrap_or(tnk.initial_level))
+                    .set_diameter(tnk.diameter);
+                if let Some(name) = &tnk.name {
+                    builder = builder.set_name(name);
+                }
+                builder.build()
+            })
+            .collect();
+
+        let reservoirs = self
+            .reservoirs
+            .iter()
+            .map(|rsrvr| {
+                let mut builder = ReservoirBuilder::new()
+                    .set_id(rsrvr.id)
+                    .set_position(Position::new(rsrvr.x, rsrvr.y))
+                    .set_head(rsrvr.head)
+                    .set_pattern(rsrvr.pattern);
+                if let Some(name) = &rsrvr.name {
+                    builder = builder.set_name(name);
+                }
+                builder.build()
+            })
+            .collect();
+
+        let mut pipes = Vec::with_capacity(self.pipes.len());
+        for pipe in self.pipes.iter() {
+            let vertices: Vec<Position> = pipe
+                .vertices
+                .iter()
+                .map(|v| Position::new(v[0], v[1]))
+                .collect();
+            let mut builder = PipeBuilder::new()
+                .set_id(pipe.id)
+                .set_start(pipe.start)
+                .set_end(pipe.end)
+                .set_length(pipe.length)
+                .set_diameter(pipe.diameter)
+                .set_roughness(pipe.roughness)
+                .set_minorloss(pipe.minor_loss)
+                .set_status(parse_status(&pipe.status)?)
+                .set_check_valve(pipe.check_valve)
+                .set_leakage(pipe.leakage.as_ref().map(|lk| match *lk {
+                    LeakageDocument::Power {
+                        coefficient,
+                        exponent,
+                    } => PipeLeakage::Power {
+                        coefficient,
+                        exponent,
+                    },
+                    LeakageDocument::Favad { area, expansion } => {
+                        PipeLeakage::Favad { area, expansion }
+                    }
+                }))
+                .set_vertices(if vertices.is_empty() {
+                    None
+                } else {
+                    Some(vertices)
+                });
+            if let Some(name) = &pipe.name {
+                builder = builder.set_name(name);
+            }
+            if let Some(material) = &pipe.material {
+                builder = builder.set_material(material);
+            }
+            pipes.push(builder.build());
+        }
+
+        let mut pumps = Vec::with_capacity(self.pumps.len());
+        for pump in self.pumps.iter() {
+            let mut builder = PumpBuilder::new()
+                .set_id(pump.id)
+                .set_start(pump.start)
+                .set_end(pump.end)
+                .set_alpha(pump.alpha)
+                .set_beta(pump.beta)
+                .set_gamma(pump.gamma)
+                .set_power_rating(pump.power_rating)
+                .set_speed(pump.speed)
+                .set_status(parse_status(&pump.status)?)
+                .set_efficiency_curve(if pump.efficiency_curve.is_empty() {
+                    None
+                } else {
+                    Some(pump.efficiency_curve.iter().map(|p| (p[0], p[1])).collect())
+                });
+            if let Some(name) = &pump.name {
+                builder = builder.set_name(name.clone());
+            }
+            pumps.push(builder.build());
+        }
+
+        let mut valves = Vec::with_capacity(self.valves.len());
+        for valve in self.valves.iter() {
+            let mut builder = ValveBuilder::new()
+                .set_id(valve.id)
+                .set_start(valve.start)
+                .set_end(valve.end)
+                .set_valve_type(parse_valve_type(&valve.valve_type)?)
+                .set_diameter(valve.diameter)
+                .set_length(valve.length)
+                .set_roughness(valve.roughness)
+                .set_minorloss(valve.minor_loss)
+                .set_k_value(valve.setting)
+                .set_status(parse_status(&valve.status)?);
+            if let Some(name) = &valve.name {
+                builder = builder.set_name(name);
+            }
+            valves.push(builder.build());
+        }
+
+        let patterns: Vec<Pattern> = self
+            .patterns
+            .iter()
+            .map(|pattern| Pattern {
+                id: pattern.id,
+                name: pattern.name.clone(),
+                multipliers: pattern.multipliers.clone(),
+            })
+            .collect();
+
+        let controls = self
+            .controls
+            .iter()
+            .map(|text| {
+                text.parse::<Control>()
+                    .map_err(|err| DocumentError::InvalidValue(err.to_string()))
+            })
+            .collect::<Result<Vec<Control>, DocumentError>>()?;
+        let rules = self
+            .rules
+            .iter()
+            .map(|text| {
+                text.parse::<Rule>()
+                    .map_err(|err| DocumentError::InvalidValue(err.to_string()))
+            })
+            .collect::<Result<Vec<Rule>, DocumentError>>()?;
+
+        let as_option = |count: usize| count > 0;
+        let wdn = NetworkBuilder::new()
+            .set_title(self.title.clone())
+            .set_options(options)
+            .set_junctions(Some(junctions).filter(|v: &Vec<_>| as_option(v.len())))
+            .set_tanks(Some(tanks).filter(|v: &Vec<_>| as_option(v.len())))
+            .set_reservoirs(Some(reservoirs).filter(|v: &Vec<_>| as_option(v.len())))
+            .set_pipes(Some(pipes).filter(|v| as_option(v.len())))
+            .set_pumps(Some(pumps).filter(|v| as_option(v.len())))
+            .set_valves(Some(valves).filter(|v| as_option(v.len())))
+            .set_patterns(Some(patterns).filter(|v| as_option(v.len())))
+            .set_controls(Some(controls).filter(|v| as_option(v.len())))
+            .set_rules(Some(rules).filter(|v| as_option(v.len())))
+            .build();
+        Ok(wdn)
+    }
+}
+
+///
+/// Version 0 (serialized Network structure) to version 1. The reservoirs patterns of version 0
+/// are labels : a numeric label is the pattern id, the others are dropped (version 0 has no patterns).
+///
+fn migrate_v0_to_v1(mut value: Value) -> Result<Value, DocumentError> {
+    if let Some(reservoirs) = value.get_mut("reservoirs").and_then(|r| r.as_array_mut()) {
+        for reservoir in reservoirs.iter_mut() {
+            if let Some(pattern) = reservoir.get_mut("pattern")
+                && let Some(label) = pattern.as_str()
+            {
+                *pattern = serde_json::to_value(label.trim().parse::<usize>().ok())?;
+            }
+        }
+    }
+    let network: Network = serde_json::from_value(value)?;
+    Ok(serde_json::to_value(NetworkDocument::from(&network))?)
+}
+
+//------------------------------ Results document ------------------------------
+
+///
+/// The results of a solved network (version 1), in the network units.
+///
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ResultsDocument {
+    ///
+    /// Always "atakoris-results".
+    ///
+    pub format: String,
+    pub version: u32,
+    pub flow_unit: String,
+    pub nodes: Vec<NodeResultDocument>,
+    pub links: Vec<LinkResultDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NodeResultDocument {
+    pub id: usize,
+    pub head: Option<f64>,
+    pub pressure: Option<f64>,
+    pub demand: f64,
+    #[serde(default)]
+    pub delivered_demand: Option<f64>,
+    #[serde(default)]
+    pub emitter_flow: Option<f64>,
+
+    ///
+    /// Not connected to a tank or a reservoir by open links.
+    ///
+    #[serde(default)]
+    pub disconnected: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LinkResultDocument {
+    pub id: usize,
+
+    ///
+    /// PIPE, PUMP or VALVE.
+    ///
+    pub link_type: String,
+    pub flow: Option<f64>,
+
+    ///
+    /// Velocity in the network velocity unit, m/s or ft/s (pipes and valves).
+    ///
+    #[serde(default)]
+    pub velocity: Option<f64>,
+
+    ///
+    /// Head loss in the network head unit (negative for the head gain of a pump).
+    ///
+    #[serde(default)]
+    pub headloss: Option<f64>,
+
+    ///
+    /// Head loss per 1000 length units (pipes).
+    ///
+    #[serde(default)]
+    pub unit_headloss: Option<f64>,
+
+    ///
+    /// Darcy-Weisbach friction factor (pipes).
+    ///
+    #[serde(default)]
+    pub friction_factor: Option<f64>,
+
+    ///
+    /// Actual status : OPEN or CLOSED.
+    ///
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub leakage_flow: Option<f64>,
+}
+
+impl ResultsDocument {
+    ///
+    /// Results of a computation of the network.
+    ///
+    pub fn new(network: &Network, results: &HydraulicResults) -> Self {
+        let nodes = results
+            .nodes
+            .iter()
+            .map(|node| {
+                let junction = network.junction(node.id);
+                NodeResultDocument {
+                    id: node.id,
+                    head: Some(node.head),
+                    pressure: Some(node.pressure),
+                    demand: junction.map_or(0.0, |jn| jn.demand),
+                    delivered_demand: junction.map(|_| node.demand),
+                    emitter_flow: junction.map(|_| node.emitter_flow),
+                    disconnected: node.disconnected,
+                }
+            })
+            .collect();
+
+        let links = results
+            .links
+            .iter()
+            .map(|link| {
+                let is_pipe = matches!(link.link_type, LinkType::Pipe);
+                LinkResultDocument {
+                    id: link.id,
+                    link_type: match link.link_type {
+                        LinkType::Pipe => "PIPE",
+                        LinkType::Pump => "PUMP",
+                        LinkType::Valve(_) => "VALVE",
+                    }
+                    .to_owned(),
+                    flow: Some(link.flow),
+                    velocity: (!matches!(link.link_type, LinkType::Pump)).then_some(link.velocity),
+                    headloss: Some(link.headloss),
+                    unit_headloss: is_pipe.then_some(link.unit_headloss),
+                    friction_factor: is_pipe.then_some(link.friction_factor),
+                    status: Some(status_keyword(link.status)),
+                    leakage_flow: is_pipe.then_some(link.leakage_flow),
+                }
+            })
+            .collect();
+
+        ResultsDocument {
+            format: RESULTS_FORMAT.to_owned(),
+            version: DOCUMENT_VERSION,
+            flow_unit: flow_unit_keyword(results.flow_unit).to_owned(),
+            nodes,
+            links,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, DocumentError> {
+        let value: Value = serde_json::from_str(json)?;
+        if value.get("format").and_then(|f| f.as_str()) != Some(RESULTS_FORMAT) {
+            return Err(DocumentError::Format(
+                value
+                    .get("format")
+                    .map_or("none".to_owned(), |f| f.to_string()),
+            ));
+        }
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+        if version > DOCUMENT_VERSION {
+            return Err(DocumentError::UnsupportedVersion(version));
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+//------------------------------ Schemas ------------------------------
+
+///
+/// JSON Schema of the network document.
+///
+pub fn network_schema() -> Value {
+    schemars::schema_for!(NetworkDocument).to_value()
+}
+
+///
+/// JSON Schema of the results document.
+///
+pub fn results_schema() -> Value {
+    schemars::schema_for!(ResultsDocument).to_value()
+}
+
+impl Network {
+    ///
+    /// Read a network from a JSON document (any supported version).
+    ///
+    pub fn from_json(json: &str) -> Result<Network, DocumentError> {
+        NetworkDocument::from_json(json)?.to_network()
+    }
+
+    ///
+    /// Write the network as a JSON document (latest version).
+    ///
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        NetworkDocument::from(self).to_json()
+    }
+
+    ///
+    /// Write the results of a computation of the network as a JSON document.
+    ///
+    pub fn results_to_json(&self, results: &HydraulicResults) -> Result<String, DocumentError> {
+        ResultsDocument::new(self, results).to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hanoi() -> Network {
+        let file = concat!(env!("CARGO_MANIFEST_DIR"), "/src/bin/data/Hanoi.inp");
+        Network::read_from_file(file).unwrap()
+    }
+
+    #[test]
+    fn migrates_a_version_0_document() {
+        let network = hanoi();
+        let v0 = serde_json::to_string(&network).unwrap();
+        let doc = NetworkDocument::from_json(&v0).unwrap();
+        assert_eq!(doc.format, NETWORK_FORMAT);
+        assert_eq!(doc.version, DOCUMENT_VERSION);
+
+        let migrated = doc.to_network().unwrap();
+        let junctions = network.junctions.as_ref().unwrap();
+        let migrated_junctions = migrated.junctions.as_ref().unwrap();
+        assert_eq!(migrated_junctions.len(), junctions.len());
+        for (a, b) in junctions.iter().zip(migrated_junctions.iter()) {
+            assert_eq!((a.id, a.elevation, a.demand), (b.id, b.elevation, b.demand));
+        }
+        let pipes = network.pipes.as_ref().unwrap();
+        let migrated_pipes = migrated.pipes.as_ref().unwrap();
+        assert_eq!(migrated_pipes.len(), pipes.len());
+        for (a, b) in pipes.iter().zip(migrated_pipes.iter()) {
+            assert_eq!(
+                (a.id, a.start, a.end, a.length, a.diameter, a.roughness),
+                (b.id, b.start, b.end, b.length, b.diameter, b.roughness)
+            );
+        }
+        assert_eq!(
+            migrated.reservoirs.as_ref().unwrap().len(),
+            network.reservoirs.as_ref().unwrap().len()
+        );
+        assert_eq!(migrated.options.flow_unit, network.options.flow_unit);
+    }
+
+    #[test]
+    fn migrates_the_reservoirs_patterns_labels() {
+        let mut v0 = serde_json::to_value(hanoi()).unwrap();
+        v0["reservoirs"][0]["pattern"] = Value::from("2");
+        let doc = NetworkDocument::from_json(&v0.to_string()).unwrap();
+        assert_eq!(doc.reservoirs[0].pattern, Some(2));
+
+        v0["reservoirs"][0]["pattern"] = Value::from("Pump station");
+        let doc = NetworkDocument::from_json(&v0.to_string()).unwrap();
+        assert_eq!(doc.reservoirs[0].pattern, None);
+    }
+
+    #[test]
+    fn reads_back_a_version_1_document() {
+        let json = hanoi().to_json().unwrap();
+        let doc = NetworkDocument::from_json(&json).unwrap();
+        assert_eq!(doc.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn rejects_a_newer_version() {
+        let json = format!(
+            r#"{{"format": "{}", "version": {}}}"#,
+            NETWORK_FORMAT,
+            DOCUMENT_VERSION + 1
+        );
+        assert!(matches!(
+            NetworkDocument::from_json(&json),
+            Err(DocumentError::UnsupportedVersion(v)) if v == DOCUMENT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn rejects_another_format() {
+        let json = format!(r#"{{"format": "{}", "version": 1}}"#, RESULTS_FORMAT);
+        assert!(matches!(
+            NetworkDocument::from_json(&json),
+            Err(DocumentError::Format(_))
+        ));
+    }
+}
@@ -0,0 +1,414 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::network::EnergyOptions;
+
+///
+/// Specific weight of water (KN/m3) : P(KW) = 9.81 x Q(m3/s) x H(m).
+///
+pub const SPECIFIC_WEIGHT: f64 = 9.81;
+
+///
+/// Energy results of one pump for a solved operating point.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PumpEnergy {
+    pub id: usize,
+    pub name: Option<String>,
+
+    ///
+    /// Pumped flow in m3/s.
+    ///
+    pub flow: f64,
+
+    ///
+    /// Head gain (downstream head - upstream head) in m.
+    ///
+    pub head: f64,
+
+    ///
+    /// Hydraulic (water) power in KW.
+    ///
+    pub hydraulic_power: f64,
+
+    ///
+    /// Wire-to-water efficiency in %.
+    ///
+    pub efficiency: f64,
+
+    ///
+    /// Absorbed (electrical) power in KW.
+    ///
+    pub power: f64,
+}
+
+impl PumpEnergy {
+    ///
+    /// Energy (KWh) consumed over a period given in hours.
+    ///
+    pub fn energy(&self, hours: f64) -> f64 {
+        self.power * hours.max(0.0)
+    }
+
+    ///
+    /// Specific energy in KWh per m3 pumped.
+    ///
+    pub fn kwh_per_m3(&self) -> f64 {
+        if self.flow.abs() > 0.0 {
+            self.power / (self.flow.abs() * 3600.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+///
+/// One tariff period : the price applies from start_hour (time of day) to the start of the next period.
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TariffPeriod {
+    pub start_hour: f64,
+    pub price: f64,
+}
+
+///
+/// Energy tariff (price per KWh along the day) and demand charge (per maximum KW).
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Tariff {
+    pub periods: Vec<TariffPeriod>,
+    pub demand_charge: f64,
+}
+
+impl Tariff {
+    ///
+    /// A flat tariff with the same price all the day.
+    ///
+    pub fn flat(price: f64) -> Self {
+        Tariff {
+            periods: vec![TariffPeriod {
+                start_hour: 0.0,
+                price,
+            }],
+            demand_charge: 0.0,
+        }
+    }
+
+    ///
+    /// A time-of-use tariff from (start hour, price) periods.
+    ///
+    pub fn time_of_use(periods: &[(f64, f64)]) -> Self {
+        let mut periods: Vec<TariffPeriod> = periods
+            .iter()
+            .map(|(h, p)| TariffPeriod {
+                start_hour: h.rem_euclid(24.0),
+                price: *p,
+            })
+            .collect();
+        periods.sort_by(|a, b| a.start_hour.total_cmp(&b.start_hour));
+        Tariff {
+            periods,
+            demand_charge: 0.0,
+        }
+    }
+
+    pub fn with_demand_charge(mut self, demand_charge: f64) -> Self {
+        self.demand_charge = demand_charge;
+        self
+    }
+
+    ///
+    /// Build the tariff from the network energy options.
+    ///
+    pub fn from_options(energy: &EnergyOptions) -> Self {
+        Tariff::flat(energy.global_price).with_demand_charge(energy.demand_charge)
+    }
+
+    ///
+    /// Price of one KWh at the given time of day (in hours).
+    ///
+    pub fn price_at(&self, hour: f64) -> f64 {
+        let h = hour.rem_euclid(24.0);
+        match self.periods.iter().rev().find(|p| p.start_hour <= h) {
+            Some(period) => period.price,
+            // before the first period, the last period of the previous day applies.
+            None => self.periods.last().map_or(0.0, |p| p.price),
+        }
+    }
+
+    ///
+    /// Cost of a constant power (KW) used from start_hour during the given hours.
+    ///
+    pub fn cost(&self, power: f64, start_hour: f64, hours: f64) -> f64 {
+        if self.periods.is_empty() || hours <= 0.0 {
+            return 0.0;
+        }
+        let mut cost = 0.0f64;
+        let mut t = start_hour;
+        let end = start_hour + hours;
+        while t < end {
+            let day_hour = t.rem_euclid(24.0);
+            let next_change = self
+                .periods
+                .iter()
+                .map(|p| p.start_hour)
+                .find(|h| *h > day_hour)
+                .unwrap_or(24.0);
+            let step = f64::min(next_change - day_hour, end - t);
+            if step <= 0.0 {
+                break;
+            }
+            cost += power * step * self.price_at(day_hour);
+            t += step;
+        }
+        cost
+    }
+}
+
+impl Default for Tariff {
+    fn default() -> Self {
+        Tariff::from_options(&EnergyOptions::default())
+    }
+}
+
+///
+/// Energy consumption of one pump over a period.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PumpEnergyUsage {
+    pub id: usize,
+    pub name: Option<String>,
+    pub efficiency: f64,
+    pub power: f64,
+    pub energy: f64,
+    pub kwh_per_m3: f64,
+    pub cost: f64,
+}
+
+///
+/// Energy summary of all pumps over a period, with costs computed from a tariff.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergySummary {
+    pub start_hour: f64,
+    pub hours: f64,
+    pub pumps: Vec<PumpEnergyUsage>,
+
+    ///
+    /// Total energy in KWh.
+    ///
+    pub total_energy: f64,
+
+    ///
+    /// Maximum absorbed power in KW.
+    ///
+    pub peak_power: f64,
+    pub demand_cost: f64,
+    pub total_cost: f64,
+}
+
+impl EnergySummary {
+    ///
+    /// Summarize the pump energies for an operating period of `hours` starting at `start_hour` (time of day).
+    ///
+    pub fn new(pumps: &[PumpEnergy], start_hour: f64, hours: f64, tariff: &Tariff) -> Self {
+        let usages: Vec<PumpEnergyUsage> = pumps
+            .iter()
+            .map(|p| PumpEnergyUsage {
+                id: p.id,
+                name: p.name.clone(),
+                efficiency: p.efficiency,
+                power: p.power,
+                energy: p.energy(hours),
+                kwh_per_m3: p.kwh_per_m3(),
+                cost: tariff.cost(p.power, start_hour, hours),
+            })
+            .collect();
+
+        let total_energy = usages.iter().fold(0.0f64, |acc, u| acc + u.energy);
+        let peak_power = usages.iter().fold(0.0f64, |acc, u| acc + u.power);
+        let demand_cost = tariff.demand_charge * peak_power;
+        let total_cost = usages.iter().fold(demand_cost, |acc, u| acc + u.cost);
+
+        EnergySummary {
+            start_hour,
+            hours,
+            pumps: usages,
+            total_energy,
+            peak_power,
+            demand_cost,
+            total_cost,
+        }
+    }
+}
+
+impl Display for EnergySummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Energy report (start: {} h, duration: {} h)",
+            self.start_hour, self.hours
+        )?;
+        writeln!(
+            f,
+            "{:>8} {:>12} {:>12} {:>12} {:>12} {:>12}",
+            "Pump", "Effic.(%)", "Power(KW)", "Energy(KWh)", "KWh/m3", "Cost"
+        )?;
+        for p in self.pumps.iter() {
+            writeln!(
+                f,
+                "{:>8} {:>12.2} {:>12.3} {:>12.3} {:>12.4} {:>12.2}",
+                p.id, p.efficiency, p.power, p.energy, p.kwh_per_m3, p.cost
+            )?;
+        }
+        writeln!(f, "Total energy: {:.3} KWh", self.total_energy)?;
+        writeln!(f, "Peak power: {:.3} KW", self.peak_power)?;
+        writeln!(f, "Demand charge: {:.2}", self.demand_cost)?;
+        write!(f, "Total cost: {:.2}", self.total_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::link::LinkStatus;
+    use crate::network::link::pipe::PipeBuilder;
+    use crate::network::link::pump::{Pump, PumpBuilder};
+    use crate::network::{
+        FlowUnits, JunctionBuilder, Network, NetworkBuilder, OptionsBuilder, ReservoirBuilder,
+    };
+    use crate::solver::Solver;
+
+    fn pump(efficiency_curve: Option<Vec<(f64, f64)>>) -> Pump {
+        PumpBuilder::new()
+            .set_id(10)
+            .set_start(100)
+            .set_end(1)
+            .set_alpha(-0.1)
+            .set_beta(0.0)
+            .set_gamma(50.0)
+            .set_status(LinkStatus::Open)
+            .set_flow_unit(FlowUnits::Lps)
+            .set_efficiency_curve(efficiency_curve)
+            .build()
+    }
+
+    ///
+    /// Reservoir 100 ---> pump 10 ---> 1 ---> 2 (10 l/s).
+    ///
+    fn network(pump: Pump, global_efficiency: f64) -> Network {
+        let mut options = OptionsBuilder::new().set_flow_unit(FlowUnits::Lps).build();
+        options.energy.global_efficiency = global_efficiency;
+        NetworkBuilder::new()
+            .set_options(options)
+            .set_reservoirs(Some(vec![
+                ReservoirBuilder::new().set_id(100).set_head(10.0).build(),
+            ]))
+            .set_junctions(Some(vec![
+                JunctionBuilder::new().set_id(1).build(),
+                JunctionBuilder::new().set_id(2).set_demand(10.0).build(),
+            ]))
+            .set_pipes(Some(vec![
+                PipeBuilder::new()
+                    .set_id(1)
+                    .set_start(1)
+                    .set_end(2)
+                    .set_length(500.0)
+                    .set_diameter(200.0)
+                    .set_roughness(100.0)
+                    .build(),
+            ]))
+            .set_pumps(Some(vec![pump]))
+            .build()
+    }
+
+    ///
+    /// Efficiency (%) and absorbed power (KW) of the pump of the network.
+    ///
+    fn pump_energy(network: &Network) -> (f64, f64) {
+        let mut solver = Solver::new(network, Some(1e-9));
+        let results = solver.compute().unwrap();
+        let energy = &solver.get_pump_energies().unwrap()[0];
+
+        // hand calculation : P = 9.81 x Q x H / efficiency
+        let head = results.head(1).unwrap() - 10.0;
+        assert!((energy.flow - 0.01).abs() < 1e-9);
+        assert!((energy.head - head).abs() < 1e-9);
+        let power = SPECIFIC_WEIGHT * 0.01 * head * 100.0 / energy.efficiency;
+        assert!((energy.power - power).abs() < 1e-9);
+        (energy.efficiency, energy.power)
+    }
+
+    #[test]
+    fn interpolates_the_efficiency_curve() {
+        let curved = pump(Some(vec![(10.0, 50.0), (20.0, 70.0), (40.0, 80.0)]));
+        for (flow, efficiency) in [
+            (15.0, 60.0),
+            (30.0, 75.0),
+            (20.0, 70.0),
+            (-15.0, 60.0),
+            // clamped at the end points
+            (5.0, 50.0),
+            (50.0, 80.0),
+        ] {
+            assert!(
+                (curved.efficiency(flow, 75.0) - efficiency).abs() < 1e-12,
+                "{}",
+                flow
+            );
+        }
+
+        // 10 l/s on the curve (5, 50) - (15, 70)
+        let curved = pump(Some(vec![(5.0, 50.0), (15.0, 70.0)]));
+        let (efficiency, _) = pump_energy(&network(curved, 75.0));
+        assert!((efficiency - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn falls_back_to_the_global_efficiency() {
+        assert_eq!(pump(None).efficiency(15.0, 65.0), 65.0);
+        assert_eq!(pump(Some(Vec::new())).efficiency(15.0, 65.0), 65.0);
+
+        let (efficiency, power) = pump_energy(&network(pump(None), 75.0));
+        assert_eq!(efficiency, 75.0);
+        let (efficiency, lower_power) = pump_energy(&network(pump(None), 60.0));
+        assert_eq!(efficiency, 60.0);
+        assert!((lower_power - power * 75.0 / 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn charges_the_energy_and_the_peak_power() {
+        let pump_energy = |id: usize, power: f64| PumpEnergy {
+            id,
+            name: None,
+            flow: 0.1,
+            head: 10.0,
+            hydraulic_power: power * 0.75,
+            efficiency: 75.0,
+            power,
+        };
+        // 0.1 from 22 h to 6 h, 0.2 from 6 h to 22 h ; 5 per KW of peak power
+        let tariff =
+            Tariff::time_of_use(&[(6.0, 0.2), (0.0, 0.1), (22.0, 0.1)]).with_demand_charge(5.0);
+        assert_eq!(tariff.price_at(23.0), 0.1);
+        assert_eq!(tariff.price_at(30.0), 0.2);
+
+        // 12 h from 20 h : 2 h at 0.2, 8 h at 0.1, 2 h at 0.2, i.e. 1.6 per KW
+        let summary = EnergySummary::new(
+            &[pump_energy(1, 30.0), pump_energy(2, 20.0)],
+            20.0,
+            12.0,
+            &tariff,
+        );
+        assert!((summary.pumps[0].cost - 48.0).abs() < 1e-9);
+        assert!((summary.pumps[1].cost - 32.0).abs() < 1e-9);
+        assert!((summary.pumps[0].energy - 360.0).abs() < 1e-9);
+        assert!((summary.total_energy - 600.0).abs() < 1e-9);
+        assert!((summary.peak_power - 50.0).abs() < 1e-9);
+        assert!((summary.demand_cost - 250.0).abs() < 1e-9);
+        assert!((summary.total_cost - 330.0).abs() < 1e-9);
+        // 30 KW for 0.1 m3/s : 30 / 360 KWh per m3
+        assert!((summary.pumps[0].kwh_per_m3 - 30.0 / 360.0).abs() < 1e-12);
+    }
+}
@@ -1,6 +1,5 @@
 use crate::network::Network;
 use serde::{Deserialize, Serialize};
-use serde_json;
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 
@@ -19,18 +18,15 @@ impl FfiDto {
                 err_net
             }
             Ok(json_net) => {
-                let net: Network = match serde_json::from_str(json_net) {
-                    Err(_eror) => Network::default(),
-                    Ok(net) => net,
-                };
-                net
+                // versioned document (older versions are migrated)
+                Network::from_json(json_net).unwrap_or_default()
             }
         };
         network
     }
 
     pub fn convert_to_json(network: &Network) -> *mut c_char {
-        let json_str = serde_json::to_string(network).unwrap();
+        let json_str = network.to_json().unwrap();
         CString::new(json_str).unwrap().into_raw()
     }
 
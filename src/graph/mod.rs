@@ -38,6 +38,8 @@ impl GraphNet {
             refnode_index : None,
             tree : None,
         };
+         // node id -> graph node index
+         let mut node_indices : HashMap<usize, NodeIndex> = HashMap::new();
 
          match &network.junctions {
             None =>{},
@@ -51,7 +53,8 @@ impl GraphNet {
                                 None => 00.0,
                                 Some(th) => th,
                             };
-                            netgraph.graph.add_node(GrNode{id : nd.id, tree_index : 0, target_head : th, head : None, demand : Self::convert2si(&options.flow_unit, nd.demand)});
+                            let ndix = netgraph.graph.add_node(GrNode{id : nd.id, tree_index : 0, target_head : th, head : None, demand : Self::convert2si(&options.flow_unit, nd.demand)});
+                            node_indices.insert(nd.id, ndix);
                         }
                     },
                 };                
@@ -65,6 +68,7 @@ impl GraphNet {
             Some(nodes) => {
                 for nd in nodes.iter(){
                   let ndix = netgraph.graph.add_node(GrNode{id : nd.id, tree_index : 0, demand : 0.0, target_head : nd.head(), head : Some(nd.head())});
+                  node_indices.insert(nd.id, ndix);
                     if nd.head() > max_head { max_head = nd.head(); netgraph.refnode_index = Some(ndix.clone());} 
                 } 
             }   
@@ -75,6 +79,7 @@ impl GraphNet {
             Some(nodes) => {
                 for nd in nodes.iter(){
                    let ndix = netgraph.graph.add_node(GrNode{id : nd.id, tree_index : 0, demand : 0.0, target_head : nd.head, head : Some(nd.head)});
+                   node_indices.insert(nd.id, ndix);
                    if nd.head > max_head {max_head = nd.head; netgraph.refnode_index = Some(ndix.clone());} 
                 }
             }
@@ -85,8 +90,8 @@ impl GraphNet {
             Some(pipes) => {
                 //let mut i : usize = 0;
                 for p in pipes.iter() {
-                     let a : NodeIndex = netgraph.graph.node_indices().find(|i| netgraph.graph[*i].id == p.start).unwrap(); //netgraph.graph.add_node(GrNode { id: p.start, target_head: 30.0});
-                     let b : NodeIndex = netgraph.graph.node_indices().find(|i| netgraph.graph[*i].id == p.end).unwrap();
+                     let a : NodeIndex = node_indices[&p.start];
+                     let b : NodeIndex = node_indices[&p.end];
                      let _edg = netgraph.graph.add_edge(a, b, GrEdge{ id : p.id, start : p.start, end : p.end, tree_index : 0, length : p.length, flow : None, headloss : None, diameter : None, roughnes : p.roughness});
                 //     i+=1;
                 };
@@ -99,19 +104,7 @@ impl GraphNet {
     }
 
     fn convert2si(unit : &FlowUnits, value : f64)-> f64 {
-        match &unit {
-         FlowUnits::Afd => value*0.0142764,
-         FlowUnits::Cfs => value*0.0283168,
-         FlowUnits::Cmd => value/86400.0, //3600 x 24
-         FlowUnits::Cmh => value/3600.0,
-         FlowUnits::Cms => value,
-         FlowUnits::Gpm => value*6.309e-5,
-         FlowUnits::Imgd => value,
-         FlowUnits::Lpm => value*0.06,
-         FlowUnits::Lps => value*0.001,
-         FlowUnits::Mgd => value,
-         FlowUnits::Mld => value/86.40,
-        }
+        value * crate::units::flow_factor(*unit)
     }
 
    fn get_tree(&mut self) {        
@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+///
+/// Leakage of one pipe for a solved operating point.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipeLeakageVolume {
+    pub id: usize,
+    pub name: Option<String>,
+
+    ///
+    /// Leakage outflow in m3/s.
+    ///
+    pub flow: f64,
+
+    ///
+    /// Leaked volume in m3 over the report period.
+    ///
+    pub volume: f64,
+}
+
+///
+/// Leakage of the network pipes over a period.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeakageReport {
+    pub hours: f64,
+    pub pipes: Vec<PipeLeakageVolume>,
+
+    ///
+    /// Total leakage outflow in m3/s.
+    ///
+    pub total_flow: f64,
+
+    ///
+    /// Total leaked volume in m3.
+    ///
+    pub total_volume: f64,
+}
+
+impl LeakageReport {
+    ///
+    /// Build the report from the pipes leakage flows (id, name, m3/s) for a period given in hours.
+    ///
+    pub fn new(flows: &[(usize, Option<String>, f64)], hours: f64) -> Self {
+        let seconds = hours.max(0.0) * 3600.0;
+        let pipes: Vec<PipeLeakageVolume> = flows
+            .iter()
+            .map(|(id, name, flow)| PipeLeakageVolume {
+                id: *id,
+                name: name.clone(),
+                flow: *flow,
+                volume: flow * seconds,
+            })
+            .collect();
+
+        let total_flow = pipes.iter().fold(0.0f64, |acc, p| acc + p.flow);
+
+        LeakageReport {
+            hours,
+            pipes,
+            total_flow,
+            total_volume: total_flow * seconds,
+        }
+    }
+}
+
+impl Display for LeakageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Leakage report (duration: {} h)", self.hours)?;
+        writeln!(f, "{:>8} {:>14} {:>14}", "Pipe", "Flow(m3/s)", "Volume(m3)")?;
+        for p in self.pipes.iter() {
+            writeln!(f, "{:>8} {:>14.6} {:>14.3}", p.id, p.flow, p.volume)?;
+        }
+        writeln!(f, "Total leakage flow: {:.6} m3/s", self.total_flow)?;
+        write!(f, "Total leakage volume: {:.3} m3", self.total_volume)
+    }
+}
@@ -1,7 +1,13 @@
+pub mod batch;
+pub mod document;
+pub mod energy;
 pub mod graph;
+pub mod leakage;
 pub mod network;
 pub mod parsers;
+pub mod simulation;
 pub mod solver;
+pub mod units;
 //-------------- FFI using data transfer objects DTO (using Json)-------
 pub mod ffi_dto;
 //use data_transfer_objects::JunctionDto;
@@ -0,0 +1,585 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::link::LinkStatus;
+use super::*;
+
+///
+/// Tolerance of the equality of a condition (=, <>).
+///
+const EQUALITY_EPSILON: f64 = 1.0e-6;
+
+///
+/// Quantity tested by a control condition.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Variable {
+    ///
+    /// Level of a tank or pressure of a junction (network head unit).
+    ///
+    Node(usize),
+
+    ///
+    /// Time from the start of the simulation (s).
+    ///
+    Time,
+
+    ///
+    /// Time of day (s), from Options::times.start_clocktime.
+    ///
+    ClockTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Relation {
+    Equal,
+    NotEqual,
+    Below,
+    Above,
+    AtMost,
+    AtLeast,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    pub variable: Variable,
+    pub relation: Relation,
+
+    ///
+    /// Level or pressure in the network head unit, or time in seconds.
+    ///
+    pub value: f64,
+}
+
+impl Condition {
+    pub fn new(variable: Variable, relation: Relation, value: f64) -> Self {
+        Condition {
+            variable,
+            relation,
+            value,
+        }
+    }
+
+    ///
+    /// True if the actual value of the variable satisfies the condition.
+    ///
+    pub fn holds(&self, actual: f64) -> bool {
+        match self.relation {
+            Relation::Equal => (actual - self.value).abs() <= EQUALITY_EPSILON,
+            Relation::NotEqual => (actual - self.value).abs() > EQUALITY_EPSILON,
+            Relation::Below => actual < self.value,
+            Relation::Above => actual > self.value,
+            Relation::AtMost => actual <= self.value,
+            Relation::AtLeast => actual >= self.value,
+        }
+    }
+}
+
+///
+/// Change of a link : status, or setting (relative speed of a pump, setting of a valve).
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    Status(LinkStatus),
+    Setting(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LinkAction {
+    pub link: usize,
+    pub action: Action,
+}
+
+///
+/// Simple control ([CONTROLS] section) : an action on a link when a condition holds, e.g.
+/// "LINK 9 OPEN IF NODE 2 BELOW 110" or "LINK 12 CLOSED AT CLOCKTIME 10 PM".
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Control {
+    pub action: LinkAction,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Logic {
+    And,
+    Or,
+}
+
+///
+/// Condition of a rule, combined with the previous ones (the logic of the first premise is ignored).
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Premise {
+    pub logic: Logic,
+    pub condition: Condition,
+}
+
+///
+/// Rule-based control ([RULES] section) : the THEN actions if the premises hold, else the ELSE
+/// actions. On a link changed by several rules, the rule of highest priority wins.
+///
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub premises: Vec<Premise>,
+    pub then_actions: Vec<LinkAction>,
+    pub else_actions: Vec<LinkAction>,
+    pub priority: f64,
+}
+
+impl Rule {
+    ///
+    /// Evaluate the premises from left to right, as Epanet : an OR premise is only checked when the
+    /// result so far is false, a false result before an AND premise is final.
+    /// "a AND b OR c" is "a AND (b OR c)", "a OR b AND c" is "(a OR b) AND c".
+    /// `holds` gives the state of a condition.
+    ///
+    pub fn evaluate(&self, mut holds: impl FnMut(&Condition) -> bool) -> bool {
+        let mut result = true;
+        for (k, premise) in self.premises.iter().enumerate() {
+            if k > 0 && premise.logic == Logic::Or {
+                if !result {
+                    result = holds(&premise.condition);
+                }
+            } else {
+                if !result {
+                    return false;
+                }
+                result = holds(&premise.condition);
+            }
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseControlError(pub String);
+
+impl Display for ParseControlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid control : {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseControlError {}
+
+fn parse_id(token: Option<&&str>, text: &str) -> Result<usize, ParseControlError> {
+    token
+        .and_then(|t| t.parse::<usize>().ok())
+        .ok_or_else(|| ParseControlError(text.to_owned()))
+}
+
+fn parse_relation(token: &str) -> Option<Relation> {
+    match token.to_uppercase().as_str() {
+        "=" | "IS" => Some(Relation::Equal),
+        "<>" | "NOT" => Some(Relation::NotEqual),
+        "<" | "BELOW" => Some(Relation::Below),
+        ">" | "ABOVE" => Some(Relation::Above),
+        "<=" => Some(Relation::AtMost),
+        ">=" => Some(Relation::AtLeast),
+        _ => None,
+    }
+}
+
+///
+/// OPEN, CLOSED or a setting.
+///
+fn parse_action(token: &str) -> Option<Action> {
+    match token.to_uppercase().as_str() {
+        "OPEN" => Some(Action::Status(LinkStatus::Open)),
+        "CLOSED" => Some(Action::Status(LinkStatus::Closed)),
+        other => other.parse::<f64>().ok().map(Action::Setting),
+    }
+}
+
+///
+/// Time written as h:mm:ss.
+///
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    )
+}
+
+impl FromStr for Control {
+    type Err = ParseControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseControlError(s.to_owned());
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        if tokens.len() < 5 || !tokens[0].eq_ignore_ascii_case("LINK") {
+            return Err(error());
+        }
+        let link = parse_id(tokens.get(1), s)?;
+        let action = parse_action(tokens[2]).ok_or_else(error)?;
+
+        let keyword = (tokens[3].to_uppercase(), tokens[4].to_uppercase());
+        let condition = match (keyword.0.as_str(), keyword.1.as_str()) {
+            ("IF", "NODE") if tokens.len() > 7 => Condition::new(
+                Variable::Node(parse_id(tokens.get(5), s)?),
+                match tokens[6].to_uppercase().as_str() {
+                    "BELOW" => Relation::Below,
+                    "ABOVE" => Relation::Above,
+                    _ => return Err(error()),
+                },
+                tokens[7].parse::<f64>().map_err(|_| error())?,
+            ),
+            ("AT", "TIME") | ("AT", "CLOCKTIME") => Condition::new(
+                if keyword.1 == "TIME" {
+                    Variable::Time
+                } else {
+                    Variable::ClockTime
+                },
+                Relation::Equal,
+                parse_time(&tokens[5..]).ok_or_else(error)? as f64,
+            ),
+            _ => return Err(error()),
+        };
+        Ok(Control {
+            action: LinkAction { link, action },
+            condition,
+        })
+    }
+}
+
+impl Display for Control {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self.action.action {
+            Action::Status(LinkStatus::Open) => "OPEN".to_owned(),
+            Action::Status(LinkStatus::Closed) => "CLOSED".to_owned(),
+            Action::Setting(v) => v.to_string(),
+        };
+        write!(f, "LINK {} {} ", self.action.link, action)?;
+        let condition = &self.condition;
+        match condition.variable {
+            Variable::Node(id) => write!(
+                f,
+                "IF NODE {} {} {}",
+                id,
+                if condition.relation == Relation::Below {
+                    "BELOW"
+                } else {
+                    "ABOVE"
+                },
+                condition.value
+            ),
+            Variable::Time => write!(f, "AT TIME {}", format_time(condition.value)),
+            Variable::ClockTime => write!(f, "AT CLOCKTIME {}", format_time(condition.value)),
+        }
+    }
+}
+
+impl Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Equal => write!(f, "="),
+            Self::NotEqual => write!(f, "<>"),
+            Self::Below => write!(f, "<"),
+            Self::Above => write!(f, ">"),
+            Self::AtMost => write!(f, "<="),
+            Self::AtLeast => write!(f, ">="),
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.variable {
+            Variable::Node(id) => {
+                write!(f, "NODE {} PRESSURE {} {}", id, self.relation, self.value)
+            }
+            Variable::Time => write!(
+                f,
+                "SYSTEM TIME {} {}",
+                self.relation,
+                format_time(self.value)
+            ),
+            Variable::ClockTime => write!(
+                f,
+                "SYSTEM CLOCKTIME {} {}",
+                self.relation,
+                format_time(self.value)
+            ),
+        }
+    }
+}
+
+impl Display for LinkAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.action {
+            Action::Status(LinkStatus::Open) => write!(f, "LINK {} STATUS = OPEN", self.link),
+            Action::Status(LinkStatus::Closed) => write!(f, "LINK {} STATUS = CLOSED", self.link),
+            Action::Setting(v) => write!(f, "LINK {} SETTING = {}", self.link, v),
+        }
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "RULE {}", self.id)?;
+        for (k, premise) in self.premises.iter().enumerate() {
+            let keyword = match (k, premise.logic) {
+                (0, _) => "IF",
+                (_, Logic::And) => "AND",
+                (_, Logic::Or) => "OR",
+            };
+            writeln!(f, "{} {}", keyword, premise.condition)?;
+        }
+        for (k, action) in self.then_actions.iter().enumerate() {
+            writeln!(f, "{} {}", if k == 0 { "THEN" } else { "AND" }, action)?;
+        }
+        for (k, action) in self.else_actions.iter().enumerate() {
+            writeln!(f, "{} {}", if k == 0 { "ELSE" } else { "AND" }, action)?;
+        }
+        write!(f, "PRIORITY {}", self.priority)
+    }
+}
+
+///
+/// Clause of a rule being read.
+///
+#[derive(PartialEq)]
+enum Clause {
+    Premises,
+    Then,
+    Else,
+}
+
+impl FromStr for Rule {
+    type Err = ParseControlError;
+
+    ///
+    /// Read a rule written on several lines : RULE id, IF, AND, OR, THEN, ELSE and PRIORITY clauses.
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+        let header: Vec<&str> = lines
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect();
+        if header.len() < 2 || !header[0].eq_ignore_ascii_case("RULE") {
+            return Err(ParseControlError(s.to_owned()));
+        }
+        let mut rule = Rule {
+            id: header[1].to_owned(),
+            premises: Vec::new(),
+            then_actions: Vec::new(),
+            else_actions: Vec::new(),
+            priority: 0.0,
+        };
+
+        let mut clause = Clause::Premises;
+        for line in lines {
+            let error = || ParseControlError(format!("rule {} : {}", rule.id, line));
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let keyword = tokens[0].to_uppercase();
+            match keyword.as_str() {
+                "IF" | "OR" => clause = Clause::Premises,
+                "THEN" => clause = Clause::Then,
+                "ELSE" => clause = Clause::Else,
+                "AND" => {}
+                "PRIORITY" => {
+                    rule.priority = tokens
+                        .get(1)
+                        .and_then(|v| v.parse::<f64>().ok())
+                        .ok_or_else(error)?;
+                    continue;
+                }
+                _ => return Err(error()),
+            }
+            match clause {
+                Clause::Premises => {
+                    let condition = Self::parse_premise(&tokens[1..]).ok_or_else(error)?;
+                    rule.premises.push(Premise {
+                        logic: if keyword == "OR" {
+                            Logic::Or
+                        } else {
+                            Logic::And
+                        },
+                        condition,
+                    });
+                }
+                Clause::Then => rule
+                    .then_actions
+                    .push(Self::parse_link_action(&tokens[1..]).ok_or_else(error)?),
+                Clause::Else => rule
+                    .else_actions
+                    .push(Self::parse_link_action(&tokens[1..]).ok_or_else(error)?),
+            }
+        }
+        if rule.premises.is_empty() {
+            return Err(ParseControlError(s.to_owned()));
+        }
+        Ok(rule)
+    }
+}
+
+impl Rule {
+    ///
+    /// SYSTEM TIME|CLOCKTIME relation time, or NODE|JUNCTION|TANK id LEVEL|PRESSURE relation value.
+    ///
+    fn parse_premise(tokens: &[&str]) -> Option<Condition> {
+        let object = tokens.first()?.to_uppercase();
+        if object == "SYSTEM" {
+            let variable = match tokens.get(1)?.to_uppercase().as_str() {
+                "TIME" => Variable::Time,
+                "CLOCKTIME" => Variable::ClockTime,
+                _ => return None,
+            };
+            let relation = parse_relation(tokens.get(2)?)?;
+            return Some(Condition::new(
+                variable,
+                relation,
+                parse_time(tokens.get(3..)?)? as f64,
+            ));
+        }
+        if !matches!(object.as_str(), "NODE" | "JUNCTION" | "TANK") {
+            return None;
+        }
+        let id = tokens.get(1)?.parse::<usize>().ok()?;
+        if !matches!(tokens.get(2)?.to_uppercase().as_str(), "LEVEL" | "PRESSURE") {
+            return None;
+        }
+        Some(Condition::new(
+            Variable::Node(id),
+            parse_relation(tokens.get(3)?)?,
+            tokens.get(4)?.parse::<f64>().ok()?,
+        ))
+    }
+
+    ///
+    /// LINK|PIPE|PUMP|VALVE id STATUS|SETTING =|IS value.
+    ///
+    fn parse_link_action(tokens: &[&str]) -> Option<LinkAction> {
+        if !matches!(
+            tokens.first()?.to_uppercase().as_str(),
+            "LINK" | "PIPE" | "PUMP" | "VALVE"
+        ) {
+            return None;
+        }
+        let link = tokens.get(1)?.parse::<usize>().ok()?;
+        let action = parse_action(tokens.get(4)?)?;
+        let valid = matches!(
+            (tokens.get(2)?.to_uppercase().as_str(), action),
+            ("STATUS", Action::Status(_)) | ("SETTING", Action::Setting(_))
+        );
+        valid.then_some(LinkAction { link, action })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(premises: &[(Logic, usize)]) -> Rule {
+        Rule {
+            id: "1".to_owned(),
+            premises: premises
+                .iter()
+                .map(|&(logic, node)| Premise {
+                    logic,
+                    condition: Condition::new(Variable::Node(node), Relation::Above, 0.0),
+                })
+                .collect(),
+            then_actions: Vec::new(),
+            else_actions: Vec::new(),
+            priority: 0.0,
+        }
+    }
+
+    ///
+    /// State of the conditions on the nodes 0, 1, 2... given by the bits of `states`.
+    ///
+    fn evaluate(rule: &Rule, states: u32) -> bool {
+        rule.evaluate(|condition| match condition.variable {
+            Variable::Node(id) => states & (1 << id) != 0,
+            _ => unreachable!(),
+        })
+    }
+
+    #[test]
+    fn evaluates_the_premises_from_left_to_right() {
+        // a AND b OR c = a AND (b OR c)
+        let and_or = rule(&[(Logic::And, 0), (Logic::And, 1), (Logic::Or, 2)]);
+        // a OR b AND c = (a OR b) AND c
+        let or_and = rule(&[(Logic::And, 0), (Logic::Or, 1), (Logic::And, 2)]);
+        for states in 0..8u32 {
+            let (a, b, c) = (states & 1 != 0, states & 2 != 0, states & 4 != 0);
+            assert_eq!(
+                evaluate(&and_or, states),
+                a && (b || c),
+                "states {states:03b}"
+            );
+            assert_eq!(
+                evaluate(&or_and, states),
+                (a || b) && c,
+                "states {states:03b}"
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_the_logic_of_the_first_premise() {
+        let single = rule(&[(Logic::Or, 0)]);
+        assert!(evaluate(&single, 1));
+        assert!(!evaluate(&single, 0));
+    }
+
+    #[test]
+    fn parses_a_node_control() {
+        let control: Control = "LINK 9 OPEN IF NODE 2 BELOW 110".parse().unwrap();
+        assert_eq!(
+            control,
+            Control {
+                action: LinkAction {
+                    link: 9,
+                    action: Action::Status(LinkStatus::Open),
+                },
+                condition: Condition::new(Variable::Node(2), Relation::Below, 110.0),
+            }
+        );
+        assert_eq!(control.to_string().parse::<Control>(), Ok(control));
+    }
+
+    #[test]
+    fn parses_time_controls() {
+        let control: Control = "link 12 closed at clocktime 10 PM".parse().unwrap();
+        assert_eq!(control.action.action, Action::Status(LinkStatus::Closed));
+        assert_eq!(
+            control.condition,
+            Condition::new(Variable::ClockTime, Relation::Equal, 22.0 * 3600.0)
+        );
+        assert_eq!(control.to_string().parse::<Control>(), Ok(control));
+
+        let control: Control = "LINK 3 1.5 AT TIME 4:30".parse().unwrap();
+        assert_eq!(control.action.action, Action::Setting(1.5));
+        assert_eq!(
+            control.condition,
+            Condition::new(Variable::Time, Relation::Equal, 4.5 * 3600.0)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_controls() {
+        for text in [
+            "",
+            "NODE 9 OPEN IF NODE 2 BELOW 110",
+            "LINK x OPEN IF NODE 2 BELOW 110",
+            "LINK 9 HALF IF NODE 2 BELOW 110",
+            "LINK 9 OPEN IF NODE 2 EQUALS 110",
+            "LINK 9 OPEN IF NODE 2 BELOW",
+            "LINK 9 OPEN AT NOON 12",
+        ] {
+            assert_eq!(
+                text.parse::<Control>(),
+                Err(ParseControlError(text.to_owned()))
+            );
+        }
+    }
+}
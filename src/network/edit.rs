@@ -0,0 +1,745 @@
+use std::fmt::Display;
+
+use super::*;
+
+///
+/// Errors of the network editing functions.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditError {
+    ///
+    /// A node with this id already exists.
+    ///
+    DuplicateNodeId(usize),
+
+    ///
+    /// A link with this id already exists.
+    ///
+    DuplicateLinkId(usize),
+    NodeNotFound(usize),
+    LinkNotFound(usize),
+
+    ///
+    /// The link references a node that does not exist, or starts and ends at the same node.
+    ///
+    InvalidEndpoints {
+        link: usize,
+        start: usize,
+        end: usize,
+    },
+
+    ///
+    /// The node can not be removed : links are still connected to it.
+    ///
+    NodeInUse {
+        node: usize,
+        links: Vec<usize>,
+    },
+
+    ///
+    /// The node can not be removed : a control or a rule refers to it.
+    ///
+    NodeInControls(usize),
+}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateNodeId(id) => write!(f, "a node with id {} already exists", id),
+            Self::DuplicateLinkId(id) => write!(f, "a link with id {} already exists", id),
+            Self::NodeNotFound(id) => write!(f, "no node with id {}", id),
+            Self::LinkNotFound(id) => write!(f, "no link with id {}", id),
+            Self::InvalidEndpoints { link, start, end } => write!(
+                f,
+                "link {} : invalid end nodes ({} ---> {})",
+                link, start, end
+            ),
+            Self::NodeInUse { node, links } => {
+                write!(f, "node {} is still connected to links {:?}", node, links)
+            }
+            Self::NodeInControls(id) => write!(f, "node {} is used by a control or a rule", id),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+///
+/// What to do with the connected links when a node is removed.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoveMode {
+    ///
+    /// Fail if links are still connected to the node or if a control or a rule refers to it.
+    ///
+    Fail,
+
+    ///
+    /// Remove the connected links with the node, and the controls and rules which refer to them.
+    ///
+    Cascade,
+}
+
+///
+/// A node of any type, as removed from the network.
+///
+#[derive(Debug, Clone)]
+pub enum NodeElement {
+    Junction(Junction),
+    Tank(Tank),
+    Reservoir(Reservoir),
+}
+
+///
+/// A link of any type, to replace a link or as removed from the network.
+///
+#[derive(Debug, Clone)]
+pub enum LinkElement {
+    Pipe(Pipe),
+    Pump(Pump),
+    Valve(Valve),
+}
+
+impl LinkElement {
+    pub fn id(&self) -> usize {
+        match self {
+            Self::Pipe(lnk) => lnk.id,
+            Self::Pump(lnk) => lnk.id,
+            Self::Valve(lnk) => lnk.id,
+        }
+    }
+
+    pub fn ends(&self) -> (usize, usize) {
+        match self {
+            Self::Pipe(lnk) => (lnk.start, lnk.end),
+            Self::Pump(lnk) => (lnk.start, lnk.end),
+            Self::Valve(lnk) => (lnk.start, lnk.end),
+        }
+    }
+}
+
+impl Network {
+    pub fn add_junction(&mut self, mut junction: Junction) -> Result<(), EditError> {
+        self.check_new_node(junction.id)?;
+        junction.set_flow_unit(self.options.flow_unit);
+        let (id, name) = (junction.id, junction.name.clone());
+        let junctions = self.junctions.get_or_insert_with(Vec::new);
+        junctions.push(junction);
+        let node = NodeRef::Junction(junctions.len() - 1);
+        self.index_node(id, name.as_deref(), node);
+        Ok(())
+    }
+
+    pub fn add_tank(&mut self, mut tank: Tank) -> Result<(), EditError> {
+        self.check_new_node(tank.id)?;
+        tank.set_flow_unit(self.options.flow_unit);
+        let (id, name) = (tank.id, tank.name.clone());
+        let tanks = self.tanks.get_or_insert_with(Vec::new);
+        tanks.push(tank);
+        let node = NodeRef::Tank(tanks.len() - 1);
+        self.index_node(id, name.as_deref(), node);
+        Ok(())
+    }
+
+    pub fn add_reservoir(&mut self, mut reservoir: Reservoir) -> Result<(), EditError> {
+        self.check_new_node(reservoir.id)?;
+        reservoir.set_flow_unit(self.options.flow_unit);
+        let (id, name) = (reservoir.id, reservoir.name.clone());
+        let reservoirs = self.reservoirs.get_or_insert_with(Vec::new);
+        reservoirs.push(reservoir);
+        let node = NodeRef::Reservoir(reservoirs.len() - 1);
+        self.index_node(id, name.as_deref(), node);
+        Ok(())
+    }
+
+    pub fn add_pipe(&mut self, mut pipe: Pipe) -> Result<(), EditError> {
+        self.check_new_link(pipe.id, pipe.start, pipe.end)?;
+        pipe.set_flow_unit(self.options.flow_unit);
+        let pipes = self.pipes.get_or_insert_with(Vec::new);
+        pipes.push(pipe.clone());
+        let link_ref = LinkRef::Pipe(pipes.len() - 1);
+        if let Some(index) = self.index.get_mut() {
+            index.insert_link(&pipe, pipe.name.as_deref(), link_ref);
+        }
+        Ok(())
+    }
+
+    pub fn add_pump(&mut self, mut pump: Pump) -> Result<(), EditError> {
+        self.check_new_link(pump.id, pump.start, pump.end)?;
+        pump.set_flow_unit(self.options.flow_unit);
+        let pumps = self.pumps.get_or_insert_with(Vec::new);
+        pumps.push(pump.clone());
+        let link_ref = LinkRef::Pump(pumps.len() - 1);
+        if let Some(index) = self.index.get_mut() {
+            index.insert_link(&pump, pump.name.as_deref(), link_ref);
+        }
+        Ok(())
+    }
+
+    pub fn add_valve(&mut self, mut valve: Valve) -> Result<(), EditError> {
+        self.check_new_link(valve.id, valve.start, valve.end)?;
+        valve.set_flow_unit(self.options.flow_unit);
+        let valves = self.valves.get_or_insert_with(Vec::new);
+        valves.push(valve.clone());
+        let link_ref = LinkRef::Valve(valves.len() - 1);
+        if let Some(index) = self.index.get_mut() {
+            index.insert_link(&valve, valve.name.as_deref(), link_ref);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Remove a node. With RemoveMode::Fail, the node must not be connected to any link nor be used
+    /// by a control or a rule; with RemoveMode::Cascade, the connected links are removed too, and the
+    /// controls and rules with a condition on the node (see remove_link for the links).
+    /// Return the removed node and the removed links.
+    ///
+    pub fn remove_node(
+        &mut self,
+        id: usize,
+        mode: RemoveMode,
+    ) -> Result<(NodeElement, Vec<LinkElement>), EditError> {
+        let node = self.locate_node(id)?;
+        let connected = self.links_of(id).to_vec();
+
+        if mode == RemoveMode::Fail {
+            if !connected.is_empty() {
+                return Err(EditError::NodeInUse {
+                    node: id,
+                    links: connected,
+                });
+            }
+            if self.is_in_controls(id) {
+                return Err(EditError::NodeInControls(id));
+            }
+        }
+
+        let mut removed_links: Vec<LinkElement> = Vec::with_capacity(connected.len());
+        for link_id in connected {
+            removed_links.push(self.remove_link(link_id)?);
+        }
+
+        let removed = match node {
+            NodeRef::Junction(i) => Self::take(&mut self.junctions, i).map(NodeElement::Junction),
+            NodeRef::Tank(i) => Self::take(&mut self.tanks, i).map(NodeElement::Tank),
+            NodeRef::Reservoir(i) => {
+                Self::take(&mut self.reservoirs, i).map(NodeElement::Reservoir)
+            }
+        }
+        .ok_or(EditError::NodeNotFound(id))?;
+        self.remove_control_references(Some(id), None);
+        self.reindex();
+        Ok((removed, removed_links))
+    }
+
+    ///
+    /// Remove a link and return it. The simple controls of the link and the actions of the rules
+    /// on it are removed (a rule left without action is removed).
+    ///
+    pub fn remove_link(&mut self, id: usize) -> Result<LinkElement, EditError> {
+        let link = self.locate_link(id)?;
+        let removed = match link {
+            LinkRef::Pipe(k) => Self::take(&mut self.pipes, k).map(LinkElement::Pipe),
+            LinkRef::Pump(k) => Self::take(&mut self.pumps, k).map(LinkElement::Pump),
+            LinkRef::Valve(k) => Self::take(&mut self.valves, k).map(LinkElement::Valve),
+        }
+        .ok_or(EditError::LinkNotFound(id))?;
+        self.remove_control_references(None, Some(id));
+        self.reindex();
+        Ok(removed)
+    }
+
+    ///
+    /// Replace the link `id` by a new link (of any type). The new link may keep the id
+    /// or take a new unique id; its end nodes must exist. The controls and rules of the link
+    /// act on the new link. Return the replaced link.
+    ///
+    pub fn replace_link(&mut self, id: usize, link: LinkElement) -> Result<LinkElement, EditError> {
+        let actual = self.locate_link(id)?;
+        let new_id = link.id();
+        if new_id != id && self.checked_link(new_id).is_some() {
+            return Err(EditError::DuplicateLinkId(new_id));
+        }
+        let (start, end) = link.ends();
+        self.check_endpoints(new_id, start, end)?;
+
+        let flow_unit = self.options.flow_unit;
+        let same_type = matches!(
+            (&actual, &link),
+            (LinkRef::Pipe(_), LinkElement::Pipe(_))
+                | (LinkRef::Pump(_), LinkElement::Pump(_))
+                | (LinkRef::Valve(_), LinkElement::Valve(_))
+        );
+
+        let replaced = if same_type {
+            // keep the link at its place (order of the links).
+            match (actual, link) {
+                (LinkRef::Pipe(k), LinkElement::Pipe(mut pipe)) => {
+                    pipe.set_flow_unit(flow_unit);
+                    let actual = self
+                        .pipes
+                        .as_mut()
+                        .and_then(|pipes| pipes.get_mut(k))
+                        .ok_or(EditError::LinkNotFound(id))?;
+                    LinkElement::Pipe(std::mem::replace(actual, pipe))
+                }
+                (LinkRef::Pump(k), LinkElement::Pump(mut pump)) => {
+                    pump.set_flow_unit(flow_unit);
+                    let actual = self
+                        .pumps
+                        .as_mut()
+                        .and_then(|pumps| pumps.get_mut(k))
+                        .ok_or(EditError::LinkNotFound(id))?;
+                    LinkElement::Pump(std::mem::replace(actual, pump))
+                }
+                (LinkRef::Valve(k), LinkElement::Valve(mut valve)) => {
+                    valve.set_flow_unit(flow_unit);
+                    let actual = self
+                        .valves
+                        .as_mut()
+                        .and_then(|valves| valves.get_mut(k))
+                        .ok_or(EditError::LinkNotFound(id))?;
+                    LinkElement::Valve(std::mem::replace(actual, valve))
+                }
+                _ => unreachable!(),
+            }
+        } else {
+            // the controls of the link are kept for the new link
+            let (controls, rules) = (self.controls.take(), self.rules.take());
+            let replaced = self.remove_link(id)?;
+            (self.controls, self.rules) = (controls, rules);
+            match link {
+                LinkElement::Pipe(pipe) => self.add_pipe(pipe)?,
+                LinkElement::Pump(pump) => self.add_pump(pump)?,
+                LinkElement::Valve(valve) => self.add_valve(valve)?,
+            };
+            replaced
+        };
+        if new_id != id {
+            self.retarget_controls(id, new_id);
+        }
+        self.reindex();
+        Ok(replaced)
+    }
+
+    ///
+    /// Move a node to a new position.
+    ///
+    pub fn move_node(&mut self, id: usize, position: Position) -> Result<(), EditError> {
+        match self.locate_node(id)? {
+            NodeRef::Junction(i) => {
+                if let Some(node) = self
+                    .junctions
+                    .as_mut()
+                    .and_then(|junctions| junctions.get_mut(i))
+                {
+                    node.position = position;
+                }
+            }
+            NodeRef::Tank(i) => {
+                if let Some(node) = self.tanks.as_mut().and_then(|tanks| tanks.get_mut(i)) {
+                    node.position = position;
+                }
+            }
+            NodeRef::Reservoir(i) => {
+                if let Some(node) = self
+                    .reservoirs
+                    .as_mut()
+                    .and_then(|reservoirs| reservoirs.get_mut(i))
+                {
+                    node.position = position;
+                }
+            }
+        };
+        Ok(())
+    }
+
+    fn check_new_node(&mut self, id: usize) -> Result<(), EditError> {
+        if self.checked_node(id).is_some() {
+            return Err(EditError::DuplicateNodeId(id));
+        }
+        Ok(())
+    }
+
+    fn check_new_link(&mut self, id: usize, start: usize, end: usize) -> Result<(), EditError> {
+        if self.checked_link(id).is_some() {
+            return Err(EditError::DuplicateLinkId(id));
+        }
+        self.check_endpoints(id, start, end)
+    }
+
+    fn check_endpoints(&mut self, id: usize, start: usize, end: usize) -> Result<(), EditError> {
+        if start == end || self.checked_node(start).is_none() || self.checked_node(end).is_none() {
+            return Err(EditError::InvalidEndpoints {
+                link: id,
+                start,
+                end,
+            });
+        }
+        Ok(())
+    }
+
+    fn index_node(&mut self, id: usize, name: Option<&str>, node: NodeRef) {
+        if let Some(index) = self.index.get_mut() {
+            index.insert_node(id, name, node);
+        }
+    }
+
+    fn locate_node(&mut self, id: usize) -> Result<NodeRef, EditError> {
+        self.checked_node(id).ok_or(EditError::NodeNotFound(id))
+    }
+
+    fn locate_link(&mut self, id: usize) -> Result<LinkRef, EditError> {
+        self.checked_link(id).ok_or(EditError::LinkNotFound(id))
+    }
+
+    ///
+    /// Position of the node : the index is rebuilt if it is stale (element vectors edited
+    /// without reindex()), i.e. if the indexed position holds another node or, for a node
+    /// missing from the index, if the index does not count all the nodes.
+    ///
+    pub(super) fn checked_node(&mut self, id: usize) -> Option<NodeRef> {
+        match self.index().node(id) {
+            Some(node) if self.node_id_at(node) == Some(id) => return Some(node),
+            None if self.index().node_count() == self.node_total() => return None,
+            _ => {}
+        }
+        self.reindex();
+        self.index().node(id)
+    }
+
+    ///
+    /// Position of the link : the index is rebuilt if it is stale.
+    ///
+    pub(super) fn checked_link(&mut self, id: usize) -> Option<LinkRef> {
+        match self.index().link(id) {
+            Some(link) if self.link_id_at(link) == Some(id) => return Some(link),
+            None if self.index().link_count() == self.link_total() => return None,
+            _ => {}
+        }
+        self.reindex();
+        self.index().link(id)
+    }
+
+    fn node_total(&self) -> usize {
+        let len = |v: Option<usize>| v.unwrap_or(0);
+        len(self.junctions.as_ref().map(|v| v.len()))
+            + len(self.tanks.as_ref().map(|v| v.len()))
+            + len(self.reservoirs.as_ref().map(|v| v.len()))
+    }
+
+    fn link_total(&self) -> usize {
+        let len = |v: Option<usize>| v.unwrap_or(0);
+        len(self.pipes.as_ref().map(|v| v.len()))
+            + len(self.pumps.as_ref().map(|v| v.len()))
+            + len(self.valves.as_ref().map(|v| v.len()))
+    }
+
+    ///
+    /// True if a condition of a control or of a rule is on the node.
+    ///
+    fn is_in_controls(&self, node_id: usize) -> bool {
+        let on_node = |condition: &Condition| condition.variable == Variable::Node(node_id);
+        self.controls
+            .iter()
+            .flatten()
+            .any(|control| on_node(&control.condition))
+            || self
+                .rules
+                .iter()
+                .flatten()
+                .any(|rule| rule.premises.iter().any(|p| on_node(&p.condition)))
+    }
+
+    ///
+    /// Remove the controls and the rules with a condition on the node, the controls of the link
+    /// and the actions of the rules on the link (then the rules without action).
+    ///
+    fn remove_control_references(&mut self, node_id: Option<usize>, link_id: Option<usize>) {
+        let on_node = |condition: &Condition| matches!(condition.variable, Variable::Node(id) if Some(id) == node_id);
+        let on_link = |action: &LinkAction| Some(action.link) == link_id;
+
+        if let Some(controls) = &mut self.controls {
+            controls.retain(|c| !on_node(&c.condition) && !on_link(&c.action));
+            if controls.is_empty() {
+                self.controls = None;
+            }
+        }
+        if let Some(rules) = &mut self.rules {
+            rules.retain_mut(|rule| {
+                rule.then_actions.retain(|a| !on_link(a));
+                rule.else_actions.retain(|a| !on_link(a));
+                let has_actions = !rule.then_actions.is_empty() || !rule.else_actions.is_empty();
+                has_actions && !rule.premises.iter().any(|p| on_node(&p.condition))
+            });
+            if rules.is_empty() {
+                self.rules = None;
+            }
+        }
+    }
+
+    ///
+    /// Make the controls and the rules on the link `from` act on the link `to`.
+    ///
+    fn retarget_controls(&mut self, from: usize, to: usize) {
+        let actions = self
+            .controls
+            .iter_mut()
+            .flatten()
+            .map(|control| &mut control.action)
+            .chain(self.rules.iter_mut().flatten().flat_map(|rule| {
+                rule.then_actions
+                    .iter_mut()
+                    .chain(rule.else_actions.iter_mut())
+            }));
+        for action in actions.filter(|a| a.link == from) {
+            action.link = to;
+        }
+    }
+
+    fn node_id_at(&self, node: NodeRef) -> Option<usize> {
+        match node {
+            NodeRef::Junction(i) => self.junctions.as_ref()?.get(i).map(|jn| jn.id),
+            NodeRef::Tank(i) => self.tanks.as_ref()?.get(i).map(|tnk| tnk.id),
+            NodeRef::Reservoir(i) => self.reservoirs.as_ref()?.get(i).map(|rsrvr| rsrvr.id),
+        }
+    }
+
+    fn link_id_at(&self, link: LinkRef) -> Option<usize> {
+        match link {
+            LinkRef::Pipe(k) => self.pipes.as_ref()?.get(k).map(|p| p.id),
+            LinkRef::Pump(k) => self.pumps.as_ref()?.get(k).map(|p| p.id),
+            LinkRef::Valve(k) => self.valves.as_ref()?.get(k).map(|v| v.id),
+        }
+    }
+
+    ///
+    /// Remove the element at position i (None if there is none),
+    /// the collection is set to None when it becomes empty.
+    ///
+    fn take<T>(items: &mut Option<Vec<T>>, i: usize) -> Option<T> {
+        let vector = items.as_mut()?;
+        if i >= vector.len() {
+            return None;
+        }
+        let item = vector.remove(i);
+        if vector.is_empty() {
+            *items = None;
+        }
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::link::pipe::PipeBuilder;
+    use crate::network::link::pump::PumpBuilder;
+
+    fn pipe(id: usize, start: usize, end: usize) -> Pipe {
+        PipeBuilder::new()
+            .set_id(id)
+            .set_start(start)
+            .set_end(end)
+            .set_length(100.0)
+            .set_diameter(200.0)
+            .set_roughness(100.0)
+            .build()
+    }
+
+    ///
+    /// Reservoir 100 -> pipe 1 -> junction 1 -> pipe 2 -> junction 2 -> pipe 3 -> junction 3,
+    /// with controls and a rule on the pipes 1 to 3 and the junctions 2 and 3.
+    ///
+    fn network() -> Network {
+        let mut wdn = Network::default();
+        wdn.add_reservoir(ReservoirBuilder::new().set_id(100).set_head(50.0).build())
+            .unwrap();
+        for id in 1..=3 {
+            wdn.add_junction(JunctionBuilder::new().set_id(id).build())
+                .unwrap();
+        }
+        wdn.add_pipe(pipe(1, 100, 1)).unwrap();
+        wdn.add_pipe(pipe(2, 1, 2)).unwrap();
+        wdn.add_pipe(pipe(3, 2, 3)).unwrap();
+        wdn.controls = Some(
+            [
+                "LINK 3 CLOSED IF NODE 2 BELOW 10",
+                "LINK 2 OPEN AT TIME 2",
+                "LINK 1 CLOSED AT TIME 5",
+            ]
+            .iter()
+            .map(|text| text.parse().unwrap())
+            .collect(),
+        );
+        wdn.rules = Some(vec![
+            "RULE 1\nIF NODE 3 PRESSURE ABOVE 5\nTHEN LINK 1 STATUS = CLOSED"
+                .parse()
+                .unwrap(),
+            "RULE 2\nIF SYSTEM TIME >= 3\nTHEN LINK 2 STATUS = CLOSED\nELSE LINK 3 STATUS = OPEN"
+                .parse()
+                .unwrap(),
+        ]);
+        wdn
+    }
+
+    fn control_links(wdn: &Network) -> Vec<usize> {
+        wdn.controls
+            .iter()
+            .flatten()
+            .map(|c| c.action.link)
+            .collect()
+    }
+
+    #[test]
+    fn adds_elements_and_rejects_duplicates() {
+        let mut wdn = network();
+        wdn.add_junction(JunctionBuilder::new().set_id(4).build())
+            .unwrap();
+        wdn.add_pipe(pipe(4, 3, 4)).unwrap();
+        assert_eq!(wdn.links_of(4), &[4]);
+
+        assert_eq!(
+            wdn.add_tank(TankBuilder::new().set_id(1).build()),
+            Err(EditError::DuplicateNodeId(1))
+        );
+        assert_eq!(
+            wdn.add_pipe(pipe(1, 3, 4)),
+            Err(EditError::DuplicateLinkId(1))
+        );
+        for (start, end) in [(3, 99), (3, 3)] {
+            assert_eq!(
+                wdn.add_pipe(pipe(5, start, end)),
+                Err(EditError::InvalidEndpoints {
+                    link: 5,
+                    start,
+                    end
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_duplicates_after_a_direct_edit() {
+        let mut wdn = network();
+        // the index is built, then the vectors are edited without reindex()
+        assert!(wdn.junction(1).is_some());
+        let junction = JunctionBuilder::new().set_id(7).build();
+        wdn.junctions.as_mut().unwrap().push(junction.clone());
+        wdn.pipes.as_mut().unwrap().push(pipe(8, 3, 7));
+
+        assert_eq!(
+            wdn.add_junction(junction),
+            Err(EditError::DuplicateNodeId(7))
+        );
+        assert_eq!(
+            wdn.add_pipe(pipe(8, 1, 7)),
+            Err(EditError::DuplicateLinkId(8))
+        );
+        wdn.add_pipe(pipe(9, 1, 7)).unwrap();
+        assert_eq!(wdn.pipe(9).map(|p| p.end), Some(7));
+    }
+
+    #[test]
+    fn fails_to_remove_a_node_in_use() {
+        let mut wdn = network();
+        match wdn.remove_node(2, RemoveMode::Fail) {
+            Err(EditError::NodeInUse { node, mut links }) => {
+                links.sort_unstable();
+                assert_eq!((node, links), (2, vec![2, 3]));
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // junction 3 without its link is still used by the rule 1
+        wdn.remove_link(3).unwrap();
+        assert_eq!(
+            wdn.remove_node(3, RemoveMode::Fail).err(),
+            Some(EditError::NodeInControls(3))
+        );
+        assert!(wdn.junction(3).is_some());
+        assert_eq!(
+            wdn.remove_node(42, RemoveMode::Fail).err(),
+            Some(EditError::NodeNotFound(42))
+        );
+    }
+
+    #[test]
+    fn removes_a_node_with_its_links_and_controls() {
+        let mut wdn = network();
+        let (node, links) = wdn.remove_node(2, RemoveMode::Cascade).unwrap();
+        assert!(matches!(node, NodeElement::Junction(jn) if jn.id == 2));
+        let mut link_ids: Vec<usize> = links.iter().map(|l| l.id()).collect();
+        link_ids.sort_unstable();
+        assert_eq!(link_ids, vec![2, 3]);
+
+        assert!(wdn.node(2).is_none() && wdn.link(2).is_none() && wdn.link(3).is_none());
+        assert_eq!(wdn.links_of(1), &[1]);
+        // the controls on the node 2 and on the links 2 and 3 are removed
+        assert_eq!(control_links(&wdn), vec![1]);
+        // the rule 2 has no action left, the rule 1 acts on the link 1
+        let rules = wdn.rules.as_ref().unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].id, "1");
+
+        // the rule 1 has a condition on the junction 3
+        wdn.remove_node(3, RemoveMode::Cascade).unwrap();
+        assert!(wdn.rules.is_none());
+        assert_eq!(control_links(&wdn), vec![1]);
+    }
+
+    #[test]
+    fn replaces_a_link() {
+        let mut wdn = network();
+        let mut larger = pipe(2, 1, 2);
+        larger.diameter = 300.0;
+        let replaced = wdn.replace_link(2, LinkElement::Pipe(larger)).unwrap();
+        assert!(matches!(replaced, LinkElement::Pipe(p) if p.diameter == 200.0));
+        // kept at its place
+        assert_eq!(wdn.pipes.as_ref().unwrap()[1].diameter, 300.0);
+
+        // a pump with a new id : the controls of the pipe 3 act on the pump 10
+        let pump = PumpBuilder::new()
+            .set_id(10)
+            .set_start(2)
+            .set_end(3)
+            .set_gamma(30.0)
+            .build();
+        wdn.replace_link(3, LinkElement::Pump(pump)).unwrap();
+        assert!(wdn.link(3).is_none());
+        assert_eq!(wdn.pump(10).map(|p| (p.start, p.end)), Some((2, 3)));
+        assert_eq!(control_links(&wdn), vec![10, 2, 1]);
+        let else_actions = &wdn.rules.as_ref().unwrap()[1].else_actions;
+        assert_eq!(else_actions[0].link, 10);
+
+        assert_eq!(
+            wdn.replace_link(2, LinkElement::Pipe(pipe(1, 1, 2))).err(),
+            Some(EditError::DuplicateLinkId(1))
+        );
+        assert_eq!(
+            wdn.replace_link(2, LinkElement::Pipe(pipe(2, 1, 99))).err(),
+            Some(EditError::InvalidEndpoints {
+                link: 2,
+                start: 1,
+                end: 99
+            })
+        );
+        assert_eq!(
+            wdn.replace_link(42, LinkElement::Pipe(pipe(42, 1, 2)))
+                .err(),
+            Some(EditError::LinkNotFound(42))
+        );
+    }
+
+    #[test]
+    fn moves_a_node() {
+        let mut wdn = network();
+        wdn.move_node(100, Position::new(10.0, 20.0)).unwrap();
+        let position = &wdn.reservoir(100).unwrap().position;
+        assert_eq!((position.x, position.y), (10.0, 20.0));
+        assert_eq!(
+            wdn.move_node(42, Position::new(0.0, 0.0)),
+            Err(EditError::NodeNotFound(42))
+        );
+    }
+}
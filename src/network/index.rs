@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use super::Network;
+use super::link::Link;
+
+///
+/// Position of a node in the network element vectors.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeRef {
+    Junction(usize),
+    Tank(usize),
+    Reservoir(usize),
+}
+
+///
+/// Position of a link in the network element vectors.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkRef {
+    Pipe(usize),
+    Pump(usize),
+    Valve(usize),
+}
+
+///
+/// Lookup tables of a network : id -> element, name -> element and node -> connected links.
+/// Node ids and link ids are independent (a node and a link may have the same id).
+///
+#[derive(Debug, Clone, Default)]
+pub struct NetworkIndex {
+    nodes: HashMap<usize, NodeRef>,
+    links: HashMap<usize, LinkRef>,
+    node_names: HashMap<String, usize>,
+    link_names: HashMap<String, usize>,
+
+    ///
+    /// node id -> ids of the links connected to the node.
+    ///
+    adjacency: HashMap<usize, Vec<usize>>,
+
+    ///
+    /// link id -> (start node id, end node id).
+    ///
+    ends: HashMap<usize, (usize, usize)>,
+}
+
+impl NetworkIndex {
+    ///
+    /// Build the index of the network elements.
+    ///
+    pub fn build(network: &Network) -> Self {
+        let mut index = NetworkIndex::default();
+
+        if let Some(junctions) = &network.junctions {
+            for (i, jn) in junctions.iter().enumerate() {
+                index.insert_node(jn.id, jn.name.as_deref(), NodeRef::Junction(i));
+            }
+        }
+        if let Some(tanks) = &network.tanks {
+            for (i, tnk) in tanks.iter().enumerate() {
+                index.insert_node(tnk.id, tnk.name.as_deref(), NodeRef::Tank(i));
+            }
+        }
+        if let Some(reservoirs) = &network.reservoirs {
+            for (i, rsrvr) in reservoirs.iter().enumerate() {
+                index.insert_node(rsrvr.id, rsrvr.name.as_deref(), NodeRef::Reservoir(i));
+            }
+        }
+
+        if let Some(pipes) = &network.pipes {
+            for (k, pipe) in pipes.iter().enumerate() {
+                index.insert_link(pipe, pipe.name.as_deref(), LinkRef::Pipe(k));
+            }
+        }
+        if let Some(pumps) = &network.pumps {
+            for (k, pump) in pumps.iter().enumerate() {
+                index.insert_link(pump, pump.name.as_deref(), LinkRef::Pump(k));
+            }
+        }
+        if let Some(valves) = &network.valves {
+            for (k, valve) in valves.iter().enumerate() {
+                index.insert_link(valve, valve.name.as_deref(), LinkRef::Valve(k));
+            }
+        }
+        index
+    }
+
+    pub(crate) fn insert_node(&mut self, id: usize, name: Option<&str>, node: NodeRef) {
+        self.nodes.insert(id, node);
+        if let Some(name) = name {
+            self.node_names.insert(name.to_owned(), id);
+        }
+        self.adjacency.entry(id).or_default();
+    }
+
+    pub(crate) fn insert_link<L: Link>(&mut self, link: &L, name: Option<&str>, link_ref: LinkRef) {
+        let id = link.get_id();
+        let (start, end) = (link.get_start_node(), link.get_end_node());
+        self.links.insert(id, link_ref);
+        if let Some(name) = name {
+            self.link_names.insert(name.to_owned(), id);
+        }
+        self.ends.insert(id, (start, end));
+        self.adjacency.entry(start).or_default().push(id);
+        if end != start {
+            self.adjacency.entry(end).or_default().push(id);
+        }
+    }
+
+    pub fn node(&self, id: usize) -> Option<NodeRef> {
+        self.nodes.get(&id).copied()
+    }
+
+    pub fn link(&self, id: usize) -> Option<LinkRef> {
+        self.links.get(&id).copied()
+    }
+
+    ///
+    /// Id of the node with the given name.
+    ///
+    pub fn node_id(&self, name: &str) -> Option<usize> {
+        self.node_names.get(name).copied()
+    }
+
+    ///
+    /// Id of the link with the given name.
+    ///
+    pub fn link_id(&self, name: &str) -> Option<usize> {
+        self.link_names.get(name).copied()
+    }
+
+    ///
+    /// Ids of the links connected to the node.
+    ///
+    pub fn links_of(&self, node_id: usize) -> &[usize] {
+        self.adjacency
+            .get(&node_id)
+            .map_or(&[], |links| links.as_slice())
+    }
+
+    ///
+    /// (start node id, end node id) of the link.
+    ///
+    pub fn ends_of(&self, link_id: usize) -> Option<(usize, usize)> {
+        self.ends.get(&link_id).copied()
+    }
+
+    ///
+    /// Ids of the nodes connected to the node by a link (each neighbor is given once).
+    ///
+    pub fn neighbors(&self, node_id: usize) -> Vec<usize> {
+        let mut result: Vec<usize> = Vec::new();
+        for link_id in self.links_of(node_id) {
+            if let Some((start, end)) = self.ends_of(*link_id) {
+                let other = if start == node_id { end } else { start };
+                if other != node_id && !result.contains(&other) {
+                    result.push(other);
+                }
+            }
+        }
+        result
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn link_count(&self) -> usize {
+        self.links.len()
+    }
+}
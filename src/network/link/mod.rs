@@ -61,7 +61,9 @@ pub trait Link {
         start_node: usize,
         end_node: usize,
         vertices: Option<Vec<Position>>,
-    ) -> Self;
+    ) -> Self
+    where
+        Self: Sized;
     fn link_type(&self) -> LinkType;
     fn get_id(&self) -> usize;
     fn get_vertices(&self) -> Option<&Vec<Position>>;
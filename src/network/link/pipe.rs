@@ -1,4 +1,5 @@
-use crate::{CMH_FACTOR, FlowUnits, LPM_FACTOR, LPS_FACTOR, network::Position};
+use crate::units::{Quantity, Units};
+use crate::{FlowUnits, network::Position};
 
 use super::*;
 use serde::{Deserialize, Serialize};
@@ -16,11 +17,11 @@ pub struct Pipe {
     pub end: usize,
 
     ///
-    /// Pipe length in meter (m).
+    /// Pipe length in meter (m), in feet (ft) for US customary units.
     pub length: f64,
 
     ///
-    /// Pipe diameter in mm.
+    /// Pipe diameter in mm, in inch (in) for US customary units.
     ///
     pub diameter: f64,
 
@@ -32,35 +33,104 @@ pub struct Pipe {
     pub minor_loss: f64,
 
     ///
-    /// Pipe flow in m3/s (IS system)
+    /// Pipe material (cast iron, PVC, ...), informative.
     ///
-    pub flow: Option<f64>,
-    //velocity : Option<f64>,
+    #[serde(default)]
+    pub material: Option<String>,
+
     pub status: LinkStatus,
     pub check_valve: bool,
     flow_unit: FlowUnits,
+
+    ///
+    /// Leakage model of the pipe (None : no leakage).
+    ///
+    #[serde(default)]
+    pub leakage: Option<PipeLeakage>,
+}
+
+///
+/// Pressure dependent leakage along a pipe. Leakage is split onto the pipe end nodes :
+/// each half of the pipe leaks under the pressure of its end junction, the whole pipe under the
+/// pressure of its junction when the other end is a tank or a reservoir.
+///
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+pub enum PipeLeakage {
+    ///
+    /// Background leakage : q = coefficient x (L/1000) x p^exponent, in the network units :
+    /// coefficient in flow unit per km of pipe at a unit pressure (1 m in SI units, 1 psi in US
+    /// units), as the emitter coefficients.
+    ///
+    Power { coefficient: f64, exponent: f64 },
+
+    ///
+    /// FAVAD (Fixed And Variable Area Discharge) : q = Cd x sqrt(2g) x (A0 + m x p) x p^0.5,
+    /// in SI units whatever the network units : area A0 in mm2 per 100 m of pipe,
+    /// expansion m in mm2 per m of pressure per 100 m of pipe.
+    ///
+    Favad { area: f64, expansion: f64 },
+}
+
+/// Discharge coefficient of the leak openings (FAVAD).
+const LEAK_DISCHARGE_COEFFICIENT: f64 = 0.6;
+
+impl PipeLeakage {
+    ///
+    /// Leakage terms (C, n) such as q (m3/s) = sum(C x p^n) for a pipe length (m) and a pressure p (m),
+    /// flow_factor converts the flow unit to m3/s and pressure_factor the pressure unit to m.
+    ///
+    pub fn terms(&self, length: f64, flow_factor: f64, pressure_factor: f64) -> Vec<(f64, f64)> {
+        match *self {
+            PipeLeakage::Power {
+                coefficient,
+                exponent,
+            } => vec![(
+                coefficient * flow_factor / pressure_factor.powf(exponent) * length / 1000.0,
+                exponent,
+            )],
+            PipeLeakage::Favad { area, expansion } => {
+                let cd = LEAK_DISCHARGE_COEFFICIENT * (2.0 * 9.81f64).sqrt();
+                vec![
+                    (cd * area * 1.0e-6 * length / 100.0, 0.5),
+                    (cd * expansion * 1.0e-6 * length / 100.0, 1.5),
+                ]
+            }
+        }
+    }
+
+    ///
+    /// Leakage (m3/s) of a pipe length (m) under the given pressure (m).
+    ///
+    pub fn flow(&self, length: f64, pressure: f64, flow_factor: f64, pressure_factor: f64) -> f64 {
+        let p = pressure.max(0.0);
+        self.terms(length, flow_factor, pressure_factor)
+            .iter()
+            .fold(0.0f64, |acc, (c, n)| acc + c * p.powf(*n))
+    }
 }
 
 impl Pipe {
-    pub fn headloss(&self) -> Option<f64> {
-        let hl = match self.flow {
-            Some(q) => Some(self.resistance() * (q.abs() * self.get_flow_factor()).powf(1.852)),
-            None => None,
-        };
-        hl
+    ///
+    /// Head loss in the network head unit for a flow in the network flow unit.
+    ///
+    pub fn headloss(&self, flow: f64) -> f64 {
+        let units = self.units();
+        units.from_si(
+            Quantity::Head,
+            self.resistance() * (units.to_si(Quantity::Flow, flow.abs())).powf(1.852),
+        )
     }
-    pub fn unit_headloss(&self) -> f64 {
-        let uhl = match self.headloss() {
-            Some(hl) => hl / self.length,
-            None => 0.0000001f64,
-        };
-        uhl
+
+    ///
+    /// Head loss per length unit for a flow in the network flow unit.
+    ///
+    pub fn unit_headloss(&self, flow: f64) -> f64 {
+        self.headloss(flow) / self.length
     }
 
     pub fn resistance(&self) -> f64 {
         if self.status == LinkStatus::Open {
-            (CHW * self.length)
-                / (self.roughness.powf(1.852) * (self.diameter * 0.001).powf(4.8704))
+            self.hw_resistance()
         } else {
             99.99f64.powi(20)
         }
@@ -73,37 +143,40 @@ impl Pipe {
                 if flow < 0.0 {
                     99.99f64.powi(20)
                 } else {
-                    (CHW * self.length)
-                        / (self.roughness.powf(1.852) * (self.diameter * 0.001).powf(4.8704))
+                    self.hw_resistance()
                 }
             } else {
-                (CHW * self.length)
-                    / (self.roughness.powf(1.852) * (self.diameter * 0.001).powf(4.8704))
+                self.hw_resistance()
             }
         } else {
             99.99f64.powi(20)
         }
     }
 
-    #[allow(dead_code)]
-    pub fn velocity(&self) -> Option<f64> {
-        let v = match self.flow {
-            Some(q) => Some(
-                (4.0 * q * self.get_flow_factor())
-                    / (std::f64::consts::PI * (self.diameter * 0.001).powi(2)),
-            ),
-            None => None,
-        };
-        v
-    }
-    fn get_flow_factor(&self) -> f64 {
-        match self.flow_unit {
-            FlowUnits::Lps => LPS_FACTOR,
-            FlowUnits::Cms => 1.0,
-            FlowUnits::Cmh => CMH_FACTOR,
-            FlowUnits::Lpm => LPM_FACTOR,
-            _ => 1.0,
-        }
+    ///
+    /// Hazen-Williams resistance (SI : head loss in m for a flow in m3/s).
+    ///
+    fn hw_resistance(&self) -> f64 {
+        let units = self.units();
+        (CHW * units.to_si(Quantity::Length, self.length))
+            / (self.roughness.powf(1.852)
+                * units.to_si(Quantity::Diameter, self.diameter).powf(4.8704))
+    }
+
+    ///
+    /// Velocity in m/s for a flow in the network flow unit.
+    ///
+    pub fn velocity(&self, flow: f64) -> f64 {
+        let units = self.units();
+        (4.0 * units.to_si(Quantity::Flow, flow))
+            / (std::f64::consts::PI * units.to_si(Quantity::Diameter, self.diameter).powi(2))
+    }
+
+    ///
+    /// Units of the pipe (set by its flow unit).
+    ///
+    pub fn units(&self) -> Units {
+        Units::new(self.flow_unit)
     }
     pub fn get_vertices(&self) -> Option<&Vec<Position>> {
         match &self.vertices {
@@ -166,7 +239,7 @@ impl Link for Pipe {
     }
     fn to_string(&self) -> String {
         format!(
-            "id: {}, name: {:?}, category: {:?} , {}--->{} : diametre: {}, length: {}, R: {}, flow: {:?}",
+            "id: {}, name: {:?}, category: {:?} , {}--->{} : diametre: {}, length: {}, R: {}",
             self.id,
             self.name,
             self.link_type(),
@@ -174,8 +247,7 @@ impl Link for Pipe {
             self.end,
             self.diameter,
             self.length,
-            self.resistance(),
-            self.flow
+            self.resistance()
         )
     }
 }
@@ -192,10 +264,11 @@ impl Default for Pipe {
             diameter: 100.0,
             roughness: 130.0,
             minor_loss: 0.0,
-            flow: None,
+            material: None,
             status: LinkStatus::Open,
             check_valve: false,
             flow_unit: FlowUnits::Cms,
+            leakage: None,
         }
     }
 }
@@ -212,11 +285,11 @@ pub struct PipeBuilder {
     pub diameter: f64,
     pub roughness: f64,
     pub minor_loss: f64,
-    pub flow: Option<f64>,
-    //velocity : Option<f64>,
+    pub material: Option<String>,
     pub status: LinkStatus,
     pub check_valve: bool,
     pub flow_unit: FlowUnits,
+    pub leakage: Option<PipeLeakage>,
 }
 
 impl PipeBuilder {
@@ -269,6 +342,11 @@ impl PipeBuilder {
         self
     }
 
+    pub fn set_material(mut self, material: &str) -> Self {
+        self.material = Some(material.into());
+        self
+    }
+
     pub fn set_status(mut self, status: LinkStatus) -> Self {
         self.status = status;
         self
@@ -283,6 +361,11 @@ impl PipeBuilder {
         self
     }
 
+    pub fn set_leakage(mut self, leakage: Option<PipeLeakage>) -> Self {
+        self.leakage = leakage;
+        self
+    }
+
     pub fn build(self) -> Pipe {
         Pipe {
             id: self.id,
@@ -294,10 +377,11 @@ impl PipeBuilder {
             diameter: self.diameter,
             roughness: self.roughness,
             minor_loss: self.minor_loss,
-            flow: None,
+            material: self.material,
             status: self.status,
             check_valve: self.check_valve,
             flow_unit: self.flow_unit,
+            leakage: self.leakage,
         }
     }
 }
@@ -314,10 +398,43 @@ impl Default for PipeBuilder {
             diameter: 100.0,
             roughness: 130.0,
             minor_loss: 0.0,
-            flow: None,
+            material: None,
             status: LinkStatus::Open,
             check_valve: false,
             flow_unit: FlowUnits::Cms,
+            leakage: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_the_power_leakage_from_the_network_units() {
+        let leakage = PipeLeakage::Power {
+            coefficient: 2.0,
+            exponent: 0.5,
+        };
+        // 2 l/s per km at 1 m, 1 km under 4 m : 4 l/s
+        let si = Units::new(FlowUnits::Lps);
+        let q = leakage.flow(
+            1000.0,
+            4.0,
+            si.factor(Quantity::Flow),
+            si.factor(Quantity::Pressure),
+        );
+        assert!((q - 0.004).abs() < 1e-12);
+
+        // 2 gpm per km at 1 psi, 1 km under 4 psi : 4 gpm
+        let us = Units::new(FlowUnits::Gpm);
+        let q = leakage.flow(
+            1000.0,
+            us.to_si(Quantity::Pressure, 4.0),
+            us.factor(Quantity::Flow),
+            us.factor(Quantity::Pressure),
+        );
+        assert!((us.from_si(Quantity::Flow, q) - 4.0).abs() < 1e-9);
+    }
+}
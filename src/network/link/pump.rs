@@ -1,4 +1,5 @@
 use crate::FLOW_EPSILON;
+use crate::energy::SPECIFIC_WEIGHT;
 
 use super::*;
 use serde::{Deserialize, Serialize};
@@ -23,15 +24,42 @@ pub struct Pump {
     /// Power rating in KW.
     pub power_rating: f64,
 
-    pub flow: Option<f64>,
-
     /// Velocity : Option<f64>,
     pub status: LinkStatus,
     pub parameters: Option<String>,
     pub flow_unit: FlowUnits,
+
+    ///
+    /// Efficiency curve as (flow, efficiency in %) points, flow in the network flow unit.
+    /// If None, the global efficiency of the energy options is used.
+    ///
+    #[serde(default)]
+    pub efficiency_curve: Option<Vec<(f64, f64)>>,
+
+    ///
+    /// Relative speed (1.0 : nominal speed). The head curve is scaled by the affinity laws.
+    ///
+    #[serde(default = "nominal_speed")]
+    pub speed: f64,
+}
+
+fn nominal_speed() -> f64 {
+    1.0
 }
 
 impl Pump {
+    ///
+    /// Set the relative speed : a null speed closes the pump, a positive speed opens it.
+    ///
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+        self.status = if speed > 0.0 {
+            LinkStatus::Open
+        } else {
+            LinkStatus::Closed
+        };
+    }
+
     #[allow(dead_code)]
     fn head_of(&mut self, flow: f64, flow_unit_multiplier: f64) -> f64 {
         if flow > FLOW_EPSILON {
@@ -49,23 +77,56 @@ impl Pump {
     }
 
     #[allow(dead_code)]
-    fn head(&self, flow_unit_multiplier: f64) -> Option<f64> {
-        let _hq = match self.flow {
-            Some(q) => {
-                if self.alpha != 0.0 {
-                    Some(
-                        self.alpha * (q / flow_unit_multiplier).powi(2)
-                            + self.beta * (q / flow_unit_multiplier)
-                            + self.gamma,
-                    )
-                } else {
-                    Some(self.power_rating / (9.81 * f64::max(q.abs(), FLOW_EPSILON)))
+    fn head(&self, q: f64, flow_unit_multiplier: f64) -> f64 {
+        if self.alpha != 0.0 {
+            self.alpha * (q / flow_unit_multiplier).powi(2)
+                + self.beta * (q / flow_unit_multiplier)
+                + self.gamma
+        } else {
+            self.power_rating / (9.81 * f64::max(q.abs(), FLOW_EPSILON))
+        }
+    }
+
+    ///
+    /// Wire-to-water efficiency (in %) for the given flow (network flow unit).
+    /// The efficiency curve is linearly interpolated and clamped at its end points.
+    ///
+    pub fn efficiency(&self, flow: f64, default_efficiency: f64) -> f64 {
+        match &self.efficiency_curve {
+            None => default_efficiency,
+            Some(points) => {
+                if points.is_empty() {
+                    return default_efficiency;
                 }
+                let q = flow.abs();
+                let first = points[0];
+                let last = points[points.len() - 1];
+                if q <= first.0 {
+                    return first.1;
+                }
+                if q >= last.0 {
+                    return last.1;
+                }
+                for w in points.windows(2) {
+                    let (q1, e1) = w[0];
+                    let (q2, e2) = w[1];
+                    if q >= q1 && q <= q2 {
+                        if q2 - q1 <= f64::EPSILON {
+                            return e2;
+                        }
+                        return e1 + (e2 - e1) * (q - q1) / (q2 - q1);
+                    }
+                }
+                last.1
             }
+        }
+    }
 
-            None => None,
-        };
-        _hq
+    ///
+    /// Hydraulic (water) power in KW for a flow in m3/s and a head gain in m.
+    ///
+    pub fn hydraulic_power(flow: f64, head_gain: f64) -> f64 {
+        SPECIFIC_WEIGHT * flow.abs() * head_gain.max(0.0)
     }
 
     /// Compute the generated head/Q
@@ -128,7 +189,7 @@ impl Link for Pump {
 
     fn to_string(&self) -> String {
         format!(
-            "id: {}, name: {:?}, category: {:?} , {}--->{}, alpha: {}, beta: {}, gamma: {}, power_rating: {}, Q: {:?}.",
+            "id: {}, name: {:?}, category: {:?} , {}--->{}, alpha: {}, beta: {}, gamma: {}, power_rating: {}.",
             self.id,
             self.name,
             self.link_type(),
@@ -138,7 +199,6 @@ impl Link for Pump {
             self.beta,
             self.gamma,
             self.power_rating,
-            self.flow,
         )
     }
 }
@@ -158,6 +218,8 @@ pub struct PumpBuilder {
     pub status: LinkStatus,
     pub parameters: Option<String>,
     pub flow_unit: FlowUnits,
+    pub efficiency_curve: Option<Vec<(f64, f64)>>,
+    pub speed: f64,
 }
 
 impl PumpBuilder {
@@ -202,7 +264,7 @@ impl PumpBuilder {
 
     /// Set the power rating in KW.
     pub fn set_power_rating(mut self, power_kw: f64) -> Self {
-        self.gamma = power_kw;
+        self.power_rating = power_kw;
         self
     }
 
@@ -221,6 +283,18 @@ impl PumpBuilder {
         self
     }
 
+    /// Set the efficiency curve as (flow, efficiency in %) points.
+    pub fn set_efficiency_curve(mut self, curve: Option<Vec<(f64, f64)>>) -> Self {
+        self.efficiency_curve = curve;
+        self
+    }
+
+    /// Set the relative speed (1.0 : nominal speed).
+    pub fn set_speed(mut self, speed: f64) -> Self {
+        self.speed = f64::max(0.0, speed);
+        self
+    }
+
     pub fn build(self) -> Pump {
         Pump {
             id: self.id,
@@ -231,10 +305,11 @@ impl PumpBuilder {
             beta: self.beta,
             gamma: self.gamma,
             power_rating: self.power_rating,
-            flow: None,
             status: self.status,
             parameters: self.parameters,
             flow_unit: self.flow_unit,
+            efficiency_curve: self.efficiency_curve,
+            speed: self.speed,
         }
     }
 }
@@ -253,6 +328,8 @@ impl Default for PumpBuilder {
             status: LinkStatus::Open,
             parameters: None,
             flow_unit: FlowUnits::default(),
+            efficiency_curve: None,
+            speed: nominal_speed(),
         }
     }
 }
@@ -12,7 +12,6 @@ pub struct Valve {
     pub diameter: f64,
     pub roughness: f64,
     pub minor_loss: f64,
-    pub flow: Option<f64>,
     pub k_value: f64,
     //velocity : Option<f64>,
     pub status: LinkStatus,
@@ -54,6 +53,36 @@ impl Valve {
             }
         }
     }
+
+    ///
+    /// Whether the setting of the valve is its loss coefficient (TCV). The setting of the other
+    /// valves is a pressure (PRV, PSV, PBV), a flow (FCV) or a curve (GPV) : the solver does not
+    /// regulate them and keeps their loss coefficient.
+    ///
+    pub fn has_loss_setting(&self) -> bool {
+        self.valvetype == ValveType::TCV
+    }
+
+    ///
+    /// Apply a control setting : the loss coefficient of a TCV is set, the other valves are
+    /// only opened. Returns true if the valve is changed.
+    ///
+    pub fn apply_setting(&mut self, setting: f64) -> bool {
+        let mut changed = self.status != LinkStatus::Open;
+        self.status = LinkStatus::Open;
+        if self.has_loss_setting() {
+            changed |= self.k_value != setting;
+            self.k_value = setting;
+        } else {
+            log::warn!(
+                "valve {} : the {} setting {} is not regulated, the valve is only opened",
+                self.id,
+                self.valvetype,
+                setting
+            );
+        }
+        changed
+    }
 }
 
 impl Link for Valve {
@@ -121,7 +150,6 @@ impl Default for Valve {
             diameter: 100.0,
             roughness: 130.0,
             minor_loss: 0.0,
-            flow: None,
             status: LinkStatus::Open,
             k_value: 0.0,
             valvetype: ValveType::GPV,
@@ -141,7 +169,6 @@ pub struct ValveBuilder {
     pub diameter: f64,
     pub roughness: f64,
     pub minor_loss: f64,
-    pub flow: Option<f64>,
     pub k_value: f64,
     //velocity : Option<f64>,
     pub status: LinkStatus,
@@ -224,7 +251,6 @@ impl ValveBuilder {
             diameter: self.diameter,
             roughness: self.roughness,
             minor_loss: self.minor_loss,
-            flow: None,
             status: self.status,
             k_value: self.k_value,
             valvetype: self.valvetype,
@@ -244,7 +270,6 @@ impl Default for ValveBuilder {
             diameter: 100.0,
             roughness: 130.0,
             minor_loss: 0.0,
-            flow: None,
             status: LinkStatus::Open,
             k_value: 0.0,
             valvetype: ValveType::GPV,
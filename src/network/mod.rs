@@ -1,45 +1,49 @@
 //pub mod network;
-use core::panic;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
+pub mod control;
+pub mod edit;
+pub mod index;
 pub mod link;
 pub mod node;
+pub mod parameters;
+pub mod pattern;
 pub mod position;
+pub mod summary;
+pub mod validation;
 
+pub use control::{
+    Action, Condition, Control, LinkAction, Logic, ParseControlError, Premise, Relation, Rule,
+    Variable,
+};
+pub use edit::{EditError, LinkElement, NodeElement, RemoveMode};
+pub use index::{LinkRef, NetworkIndex, NodeRef};
 pub use link::Link;
 pub use node::Node;
 pub use node::junction::{Junction, JunctionBuilder};
 pub use node::reservoir::{Reservoir, ReservoirBuilder};
 pub use node::tank::{Tank, TankBuilder};
 
-pub use link::pipe::Pipe;
+pub use link::pipe::{Pipe, PipeLeakage};
 pub use link::pump::Pump;
 pub use link::valve::Valve;
 
+pub use parameters::{
+    Parameter, ParameterChange, ParameterError, ParameterKind, ParameterSet, Target,
+};
+pub use pattern::Pattern;
 pub use position::Position;
+pub use summary::{DiameterLength, MaterialLength, NetworkSummary};
+pub use validation::{ElementId, IssueKind, Severity, ValidationIssue, ValidationReport};
 
 use super::parsers::inpfileparser::InpFileParser;
+use crate::units::Units;
 //------------------------------------------------
-/*
-FlowUnits::Lps => 0.001,
-               FlowUnits::Afd => 0.014276394,
-               FlowUnits::Cfs => 1.0,
-               FlowUnits::Cmd => 1.0,
-               FlowUnits::Cmh => 1.0 / 3600.0,
-               FlowUnits::Gpm => 1.0,
-               FlowUnits::Imgd => 1.0,
-               FlowUnits::Lpm => 1.0,
-               FlowUnits::Mgd => 1.0,
-               FlowUnits::Mld => 1.0,
-               FlowUnits::Cms => 1.0,
-               */
-pub(crate) const AFD_FACTOR: f64 = 0.014276394;
-pub(crate) const LPS_FACTOR: f64 = 0.001;
-pub(crate) const LPM_FACTOR: f64 = 0.001 / 60.0;
-pub(crate) const CMH_FACTOR: f64 = 1.0 / 3600.0;
-pub(crate) const CMD_FACTOR: f64 = 1.0 / (24.0 * 3600.0);
-
 /// The minimal flow considered as null = 1.0cm3/s.
 pub(crate) const FLOW_EPSILON: f64 = 0.000001;
+
+/// The resistance used for a closed link.
+pub(crate) const CLOSED_LINK_RESISTANCE: f64 = 1.0e20;
 // -----------------------------------------------
 include!("options.rs");
 
@@ -52,7 +56,27 @@ pub struct Network {
     pub pipes: Option<Vec<Pipe>>,
     pub pumps: Option<Vec<Pump>>,
     pub valves: Option<Vec<Valve>>,
+
+    ///
+    /// Time patterns of the demands and of the reservoir heads.
+    ///
+    #[serde(default)]
+    pub patterns: Option<Vec<Pattern>>,
+
+    ///
+    /// Simple controls and rule-based controls of the links (applied by the simulation).
+    ///
+    #[serde(default)]
+    pub controls: Option<Vec<Control>>,
+    #[serde(default)]
+    pub rules: Option<Vec<Rule>>,
     pub options: Options,
+
+    ///
+    /// Lookup index, built on first use. Call reindex() after editing the element vectors directly.
+    ///
+    #[serde(skip)]
+    index: OnceCell<NetworkIndex>,
 }
 
 impl Network {
@@ -61,6 +85,109 @@ impl Network {
         Ok(wdn)
     }
 
+    ///
+    /// Lookup index of the network elements (id, name, connectivity).
+    ///
+    pub fn index(&self) -> &NetworkIndex {
+        self.index.get_or_init(|| NetworkIndex::build(self))
+    }
+
+    ///
+    /// Drop the lookup index : it will be rebuilt on next use.
+    ///
+    pub fn reindex(&mut self) {
+        self.index = OnceCell::new();
+    }
+
+    ///
+    /// Node (junction, tank or reservoir) with the given id. None if the index is stale
+    /// (element vectors edited without reindex()).
+    ///
+    pub fn node(&self, id: usize) -> Option<&dyn Node> {
+        let node: &dyn Node = match self.index().node(id)? {
+            NodeRef::Junction(i) => self.junctions.as_ref()?.get(i)?,
+            NodeRef::Tank(i) => self.tanks.as_ref()?.get(i)?,
+            NodeRef::Reservoir(i) => self.reservoirs.as_ref()?.get(i)?,
+        };
+        (node.get_id() == id).then_some(node)
+    }
+
+    ///
+    /// Link (pipe, pump or valve) with the given id. None if the index is stale.
+    ///
+    pub fn link(&self, id: usize) -> Option<&dyn Link> {
+        let link: &dyn Link = match self.index().link(id)? {
+            LinkRef::Pipe(k) => self.pipes.as_ref()?.get(k)?,
+            LinkRef::Pump(k) => self.pumps.as_ref()?.get(k)?,
+            LinkRef::Valve(k) => self.valves.as_ref()?.get(k)?,
+        };
+        (link.get_id() == id).then_some(link)
+    }
+
+    pub fn node_by_name(&self, name: &str) -> Option<&dyn Node> {
+        self.node(self.index().node_id(name)?)
+    }
+
+    pub fn link_by_name(&self, name: &str) -> Option<&dyn Link> {
+        self.link(self.index().link_id(name)?)
+    }
+
+    pub fn junction(&self, id: usize) -> Option<&Junction> {
+        match self.index().node(id)? {
+            NodeRef::Junction(i) => self.junctions.as_ref()?.get(i).filter(|e| e.id == id),
+            _ => None,
+        }
+    }
+
+    pub fn tank(&self, id: usize) -> Option<&Tank> {
+        match self.index().node(id)? {
+            NodeRef::Tank(i) => self.tanks.as_ref()?.get(i).filter(|e| e.id == id),
+            _ => None,
+        }
+    }
+
+    pub fn reservoir(&self, id: usize) -> Option<&Reservoir> {
+        match self.index().node(id)? {
+            NodeRef::Reservoir(i) => self.reservoirs.as_ref()?.get(i).filter(|e| e.id == id),
+            _ => None,
+        }
+    }
+
+    pub fn pipe(&self, id: usize) -> Option<&Pipe> {
+        match self.index().link(id)? {
+            LinkRef::Pipe(k) => self.pipes.as_ref()?.get(k).filter(|e| e.id == id),
+            _ => None,
+        }
+    }
+
+    pub fn pump(&self, id: usize) -> Option<&Pump> {
+        match self.index().link(id)? {
+            LinkRef::Pump(k) => self.pumps.as_ref()?.get(k).filter(|e| e.id == id),
+            _ => None,
+        }
+    }
+
+    pub fn valve(&self, id: usize) -> Option<&Valve> {
+        match self.index().link(id)? {
+            LinkRef::Valve(k) => self.valves.as_ref()?.get(k).filter(|e| e.id == id),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Ids of the nodes connected to the node by a link.
+    ///
+    pub fn neighbors(&self, node_id: usize) -> Vec<usize> {
+        self.index().neighbors(node_id)
+    }
+
+    ///
+    /// Ids of the links connected to the node.
+    ///
+    pub fn links_of(&self, node_id: usize) -> &[usize] {
+        self.index().links_of(node_id)
+    }
+
     pub fn get_pipes_resistances(&self) -> Option<Vec<f64>> {
         //resistance for pipes
         let _resistance = match self.pipes.clone() {
@@ -75,25 +202,6 @@ impl Network {
         };
         _resistance
     }
-
-    //#[cfg(feature = "optimization")]
-    pub fn update_pipes_diameters(&mut self, diameters: &[f64]) {
-        match &mut self.pipes {
-            None => {}
-            Some(pipes) => {
-                if diameters.len() < pipes.len() {
-                    panic!("no sufficient diameters to update network !!!")
-                }
-
-                let mut i: usize = 0;
-                for p in pipes.iter_mut() {
-                    p.diameter = diameters[i];
-                    //p.set_diameter(diameters[i].clone());
-                    i += 1;
-                }
-            }
-        };
-    }
 }
 
 impl Default for Network {
@@ -106,7 +214,11 @@ impl Default for Network {
             pipes: None,
             pumps: None,
             valves: None,
+            patterns: None,
+            controls: None,
+            rules: None,
             options: Options::default(),
+            index: OnceCell::new(),
         }
     }
 }
@@ -120,6 +232,9 @@ pub struct NetworkBuilder {
     pub pipes: Option<Vec<Pipe>>,
     pub pumps: Option<Vec<Pump>>,
     pub valves: Option<Vec<Valve>>,
+    pub patterns: Option<Vec<Pattern>>,
+    pub controls: Option<Vec<Control>>,
+    pub rules: Option<Vec<Rule>>,
     pub options: Options,
 }
 
@@ -133,6 +248,9 @@ impl NetworkBuilder {
             pipes: None,
             pumps: None,
             valves: None,
+            patterns: None,
+            controls: None,
+            rules: None,
             options: Options::default(),
         };
 
@@ -174,6 +292,21 @@ impl NetworkBuilder {
         self
     }
 
+    pub fn set_patterns(mut self, patterns: Option<Vec<Pattern>>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    pub fn set_controls(mut self, controls: Option<Vec<Control>>) -> Self {
+        self.controls = controls;
+        self
+    }
+
+    pub fn set_rules(mut self, rules: Option<Vec<Rule>>) -> Self {
+        self.rules = rules;
+        self
+    }
+
     pub fn set_options(mut self, options: Options) -> Self {
         self.options = options;
         self
@@ -191,7 +324,11 @@ impl NetworkBuilder {
             pipes: self.pipes,
             pumps: self.pumps,
             valves: self.valves,
+            patterns: self.patterns,
+            controls: self.controls,
+            rules: self.rules,
             options: self.options,
+            index: OnceCell::new(),
         };
         //-----------------------------------------
         // update node and pipe flow_unit:
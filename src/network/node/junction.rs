@@ -11,7 +11,14 @@ pub struct Junction {
     pub demand: f64,
     pub pattern: Option<usize>,
     pub name: Option<String>,
-    pub head: Option<f64>,
+
+    ///
+    /// Emitter discharge coefficient C (q = C x p^n), in network flow unit per (network pressure unit)^n.
+    /// No emitter if 0.
+    ///
+    #[serde(default)]
+    pub emitter_coefficient: f64,
+
     flow_unit: FlowUnits,
     #[cfg(feature = "optimization")]
     target_head: Option<f64>,
@@ -25,7 +32,7 @@ impl Junction {
             elevation,
             demand,
             name: None,
-            head: None,
+            emitter_coefficient: 0.0,
             pattern: None,
             #[cfg(feature = "optimization")]
             target_head: None,
@@ -69,14 +76,12 @@ impl Node for Junction {
     }
     fn to_string(&self) -> String {
         format!(
-            "id: {}, categ.: {:?}, demand: {}, elev.: {}, name: {:?}, head: {:?}, pressure: {:?}",
+            "id: {}, categ.: {:?}, demand: {}, elev.: {}, name: {:?}",
             self.id,
             self.node_type(),
             self.demand,
             self.elevation,
-            self.name,
-            self.head,
-            self.pressure()
+            self.name
         )
     }
 
@@ -84,10 +89,6 @@ impl Node for Junction {
         self.elevation
     }
 
-    fn get_head(&self) -> Option<f64> {
-        self.head
-    }
-
     fn set_flow_unit(&mut self, flow_unit: FlowUnits) {
         self.flow_unit = flow_unit;
     }
@@ -110,7 +111,7 @@ pub struct JunctionBuilder {
     demand: f64,
     pattern: Option<usize>,
     name: Option<String>,
-    head: Option<f64>,
+    emitter_coefficient: f64,
     flow_unit: FlowUnits,
     #[cfg(feature = "optimization")]
     target_head: Option<f64>,
@@ -124,7 +125,7 @@ impl JunctionBuilder {
             demand: 0.0f64,
             pattern: None,
             name: None,
-            head: None,
+            emitter_coefficient: 0.0,
             flow_unit: FlowUnits::default(),
             #[cfg(feature = "optimization")]
             target_head: None,
@@ -166,6 +167,13 @@ impl JunctionBuilder {
         self.flow_unit = flow_unit;
         self
     }
+
+    /// Set the emitter discharge coefficient (q = C x p^n).
+    pub fn set_emitter_coefficient(mut self, coefficient: f64) -> Self {
+        self.emitter_coefficient = f64::max(0.0, coefficient);
+        self
+    }
+
     #[cfg(feature = "optimization")]
     pub fn set_target_head(mut self, required_head: f64) -> Self {
         self.target_head = Some(required_head);
@@ -179,7 +187,7 @@ impl JunctionBuilder {
             name: self.name,
             elevation: self.elevation,
             demand: self.demand,
-            head: self.head,
+            emitter_coefficient: self.emitter_coefficient,
             pattern: self.pattern,
             flow_unit: self.flow_unit,
 
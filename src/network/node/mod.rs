@@ -21,7 +21,9 @@ pub enum NodeType {
 //------------------------------------Node-------------------------------
 
 pub trait Node {
-    fn default_with(id: usize, pos: Position, flow_unit: FlowUnits) -> Self;
+    fn default_with(id: usize, pos: Position, flow_unit: FlowUnits) -> Self
+    where
+        Self: Sized;
 
     fn get_id(&self) -> usize;
 
@@ -7,7 +7,7 @@ pub struct Reservoir {
     pub position: Position,
     pub name: Option<String>,
     pub head: f64,
-    pub pattern: Option<String>,
+    pub pattern: Option<usize>,
     flow_unit: FlowUnits,
 }
 
@@ -69,7 +69,7 @@ pub struct ReservoirBuilder {
     pub position: Position,
     pub name: Option<String>,
     pub head: f64,
-    pub pattern: Option<String>,
+    pub pattern: Option<usize>,
     pub flow_unit: FlowUnits,
 }
 
@@ -104,8 +104,8 @@ impl ReservoirBuilder {
         self
     }
 
-    pub fn set_pattern(mut self, pattern: &str) -> Self {
-        self.pattern = Some(pattern.to_string());
+    pub fn set_pattern(mut self, pattern: Option<usize>) -> Self {
+        self.pattern = pattern;
         self
     }
 
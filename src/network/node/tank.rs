@@ -1,4 +1,5 @@
 use super::*;
+use crate::units::{Quantity, Units};
 use serde::{Deserialize, Serialize};
 //-----------------------------------Tank-------------------------------
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,9 +11,22 @@ pub struct Tank {
     //pub head : Option<f64>,
     pub initial_level: f64,
     flow_unit: FlowUnits,
-    //pub min_level : f64,
-    //pub max_level : f64,
-    //pub diameter : f64,
+    #[serde(default)]
+    pub min_level: f64,
+    #[serde(default)]
+    pub max_level: f64,
+
+    ///
+    /// Diameter of a cylindrical tank (length unit). The level of a tank of null diameter is fixed.
+    ///
+    #[serde(default)]
+    pub diameter: f64,
+
+    ///
+    /// Actual level (computed by the extended period simulation). None : the initial level.
+    ///
+    #[serde(default)]
+    pub level: Option<f64>,
     //pub min_volume : f64,
     //pub volume_curve_id : Option<usize>,
     //pub overflow_indicator : bool,
@@ -27,11 +41,30 @@ impl Tank {
             initial_level,
             name: None,
             flow_unit: FlowUnits::default(),
+            min_level: 0.0,
+            max_level: initial_level,
+            diameter: 0.0,
+            level: None,
         }
     }
 
     pub fn head(&self) -> f64 {
-        self.elevation + self.initial_level
+        self.elevation + self.level.unwrap_or(self.initial_level)
+    }
+
+    ///
+    /// Units of the tank (set by its flow unit).
+    ///
+    pub fn units(&self) -> Units {
+        Units::new(self.flow_unit)
+    }
+
+    ///
+    /// Cross section area (m2) of the tank.
+    ///
+    pub fn area(&self) -> f64 {
+        let diameter = self.units().to_si(Quantity::Length, self.diameter);
+        std::f64::consts::PI * diameter * diameter / 4.0
     }
 }
 
@@ -87,9 +120,9 @@ pub struct TankBuilder {
     //pub head : Option<f64>,
     pub initial_level: f64,
     pub flow_unit: FlowUnits,
-    //pub min_level : f64,
-    //pub max_level : f64,
-    //pub diameter : f64,
+    pub min_level: f64,
+    pub max_level: f64,
+    pub diameter: f64,
     //pub min_volume : f64,
     //pub volume_curve_id : Option<usize>,
     //pub overflow_indicator : bool,
@@ -104,6 +137,9 @@ impl TankBuilder {
             elevation: 0.0f64,
             initial_level: 0.0f64,
             flow_unit: FlowUnits::default(),
+            min_level: 0.0f64,
+            max_level: 0.0f64,
+            diameter: 0.0f64,
         }
     }
 
@@ -137,6 +173,20 @@ impl TankBuilder {
         self
     }
 
+    ///
+    /// Minimum and maximum levels.
+    ///
+    pub fn set_levels(mut self, min_level: f64, max_level: f64) -> Self {
+        self.min_level = min_level;
+        self.max_level = max_level;
+        self
+    }
+
+    pub fn set_diameter(mut self, diameter: f64) -> Self {
+        self.diameter = diameter;
+        self
+    }
+
     pub fn build(self) -> Tank {
         Tank {
             id: self.id,
@@ -145,6 +195,10 @@ impl TankBuilder {
             elevation: self.elevation,
             initial_level: self.initial_level,
             flow_unit: self.flow_unit,
+            min_level: self.min_level,
+            max_level: self.max_level,
+            diameter: self.diameter,
+            level: None,
         }
     }
 }
@@ -1,5 +1,10 @@
 use std::fmt::Display;
 
+///
+/// Default convergence criterion of the solver (see OptionsBuilder::set_accuracy).
+///
+pub(crate) const DEFAULT_ACCURACY: f64 = 0.0001;
+
 ///
 /// Network and Analysis options
 ///
@@ -14,6 +19,21 @@ pub struct Options {
     pub pattern: usize,
     pub demand_multiplier: f64,
     pub emitter_exponent: f64,
+    #[serde(default)]
+    pub energy: EnergyOptions,
+    #[serde(default)]
+    pub demand_model: DemandModel,
+    #[serde(default)]
+    pub times: TimeOptions,
+}
+
+impl Options {
+    ///
+    /// Units of the network (set by the flow unit).
+    ///
+    pub fn units(&self) -> Units {
+        Units::new(self.flow_unit)
+    }
 }
 
 impl Default for Options {
@@ -23,11 +43,145 @@ impl Default for Options {
             headloss_formula: HeadlossFormula::Hw,
             viscosity: 0.0000001f64,
             trials: 40,
-            accuracy: 0.0001,
+            accuracy: DEFAULT_ACCURACY,
             unbalanced: Unbalanced::StopIter,
             pattern: 0,
             demand_multiplier: 1.0,
             emitter_exponent: 0.5,
+            energy: EnergyOptions::default(),
+            demand_model: DemandModel::default(),
+            times: TimeOptions::default(),
+        }
+    }
+}
+
+///
+/// Times of the extended period simulation ([TIMES] section), in seconds.
+///
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TimeOptions {
+    ///
+    /// Duration of the simulation (0 : single period).
+    ///
+    pub duration: u64,
+    pub hydraulic_step: u64,
+
+    ///
+    /// Duration of each multiplier of the patterns.
+    ///
+    pub pattern_step: u64,
+
+    ///
+    /// Time of the patterns at the start of the simulation.
+    ///
+    pub pattern_start: u64,
+
+    ///
+    /// Time of day at the start of the simulation.
+    ///
+    pub start_clocktime: u64,
+}
+
+impl Default for TimeOptions {
+    fn default() -> Self {
+        Self {
+            duration: 0,
+            hydraulic_step: 3600,
+            pattern_step: 3600,
+            pattern_start: 0,
+            start_clocktime: 0,
+        }
+    }
+}
+
+///
+/// Parse a time ([TIMES], [CONTROLS] and [RULES] sections) in seconds : "h:mm[:ss]" or a
+/// decimal number of hours, optionally followed by a unit (SEC, MIN, HOURS, DAYS) or by
+/// AM / PM for a clock time.
+///
+pub(crate) fn parse_time(value: &[&str]) -> Option<u64> {
+    let text = value.first()?;
+    let mut seconds: f64 = if text.contains(':') {
+        let mut total = 0.0;
+        for (part, scale) in text.split(':').zip([3600.0, 60.0, 1.0]) {
+            total += part.parse::<f64>().ok()? * scale;
+        }
+        total
+    } else {
+        text.parse::<f64>().ok()? * 3600.0
+    };
+
+    if let Some(unit) = value.get(1).map(|u| u.to_uppercase()) {
+        let number = text.parse::<f64>().ok();
+        match unit.as_str() {
+            "AM" | "PM" => {
+                // 12 am is midnight, 12 pm is noon
+                seconds %= 12.0 * 3600.0;
+                if unit == "PM" {
+                    seconds += 12.0 * 3600.0;
+                }
+            }
+            u if u.starts_with("SEC") => seconds = number?,
+            u if u.starts_with("MIN") => seconds = number? * 60.0,
+            u if u.starts_with("DAY") => seconds = number? * 86400.0,
+            _ => {}
+        }
+    }
+    (seconds.is_finite() && seconds >= 0.0).then_some(seconds.round() as u64)
+}
+
+///
+/// Nodal demand model.
+///
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Default)]
+pub enum DemandModel {
+    ///
+    /// Demand driven analysis (DDA) : the full demand is delivered whatever the pressure.
+    ///
+    #[default]
+    Dda,
+
+    ///
+    /// Pressure driven analysis (PDA) using the Wagner relation :
+    /// delivered = demand x ((p - minimum_pressure)/(required_pressure - minimum_pressure))^exponent,
+    /// with no delivery below the minimum pressure and the full demand above the required pressure
+    /// (pressures in the network pressure unit). The solver computes the delivered demands
+    /// (see HydraulicResults, NodeResult::demand).
+    ///
+    Pda {
+        minimum_pressure: f64,
+        required_pressure: f64,
+        exponent: f64,
+    },
+}
+
+///
+/// Energy options ([ENERGY] section).
+///
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+pub struct EnergyOptions {
+    ///
+    /// Efficiency (in %) used for pumps without efficiency curve. Default value : 75 %.
+    ///
+    pub global_efficiency: f64,
+
+    ///
+    /// Energy price per KWh.
+    ///
+    pub global_price: f64,
+
+    ///
+    /// Added cost per maximum KW usage.
+    ///
+    pub demand_charge: f64,
+}
+
+impl Default for EnergyOptions {
+    fn default() -> Self {
+        Self {
+            global_efficiency: 75.0,
+            global_price: 0.0,
+            demand_charge: 0.0,
         }
     }
 }
@@ -50,9 +204,16 @@ pub enum HeadlossFormula {
     Cm,
 }
 
+///
+/// Action of the solver when it does not converge within the trials.
+///
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Unbalanced {
     StopIter,
+
+    ///
+    /// Continue for a number of extra trials, with the link statuses frozen.
+    ///
     ContinueIter(usize),
 }
 
@@ -147,6 +308,9 @@ pub struct OptionsBuilder {
     pub pattern: usize,
     pub demand_multiplier: f64,
     pub emitter_exponent: f64,
+    pub energy: EnergyOptions,
+    pub demand_model: DemandModel,
+    pub times: TimeOptions,
 }
 
 impl OptionsBuilder {
@@ -156,11 +320,14 @@ impl OptionsBuilder {
             headloss_formula: HeadlossFormula::Hw,
             viscosity: 0.0000001f64,
             trials: 40,
-            accuracy: 0.0001,
+            accuracy: DEFAULT_ACCURACY,
             unbalanced: Unbalanced::StopIter,
             pattern: 0,
             demand_multiplier: 1.0,
             emitter_exponent: 0.5,
+            energy: EnergyOptions::default(),
+            demand_model: DemandModel::default(),
+            times: TimeOptions::default(),
         }
     }
 
@@ -174,17 +341,70 @@ impl OptionsBuilder {
         self
     }
 
+    pub fn set_energy(mut self, energy: EnergyOptions) -> Self {
+        self.energy = energy;
+        self
+    }
+
+    pub fn set_emitter_exponent(mut self, emitter_exponent: f64) -> Self {
+        self.emitter_exponent = emitter_exponent;
+        self
+    }
+
+    pub fn set_demand_model(mut self, demand_model: DemandModel) -> Self {
+        self.demand_model = demand_model;
+        self
+    }
+
+    ///
+    /// Maximum number of iterations of the solver.
+    ///
+    pub fn set_trials(mut self, trials: usize) -> Self {
+        self.trials = trials;
+        self
+    }
+
+    ///
+    /// Convergence criterion of the solver : relative change of the flows and heads,
+    /// and relative flow balance residual of the junctions. Set non-zero & strict positive.
+    ///
+    pub fn set_accuracy(mut self, accuracy: f64) -> Self {
+        self.accuracy = f64::max(accuracy, 0.0000000000001);
+        self
+    }
+
+    pub fn set_unbalanced(mut self, unbalanced: Unbalanced) -> Self {
+        self.unbalanced = unbalanced;
+        self
+    }
+
+    ///
+    /// Default demand pattern (id) of the junctions without pattern.
+    ///
+    pub fn set_pattern(mut self, pattern: usize) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn set_times(mut self, times: TimeOptions) -> Self {
+        self.times = times;
+        self
+    }
+
     pub fn build(self) -> Options {
         Options {
             flow_unit: self.flow_unit,
             headloss_formula: self.headloss_formula,
             viscosity: 0.0000001f64,
-            trials: 40,
-            accuracy: 0.0001,
-            unbalanced: Unbalanced::StopIter,
-            pattern: 0,
+            trials: self.trials,
+            accuracy: self.accuracy,
+            unbalanced: self.unbalanced,
+            pattern: self.pattern,
             demand_multiplier: 1.0,
-            emitter_exponent: 0.5,
+            emitter_exponent: self.emitter_exponent,
+            energy: self.energy,
+            demand_model: self.demand_model,
+            times: self.times,
         }
     }
 }
@@ -0,0 +1,406 @@
+use std::fmt::Display;
+
+use super::link::LinkStatus;
+use super::*;
+
+///
+/// Element addressed by a parameter change.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Target {
+    ///
+    /// Position in the vector of the element type (junctions, pipes, pumps or valves).
+    ///
+    Index(usize),
+    Id(usize),
+}
+
+///
+/// A parameter and its new value. Values are in the network units.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Parameter {
+    PipeDiameter(f64),
+    PipeRoughness(f64),
+    PipeStatus(LinkStatus),
+    PumpStatus(LinkStatus),
+
+    ///
+    /// Relative speed (1.0 : nominal speed) : a null speed closes the pump, a positive speed opens it.
+    ///
+    PumpSpeed(f64),
+    ValveSetting(f64),
+    ValveStatus(LinkStatus),
+
+    ///
+    /// Base demand of a junction.
+    ///
+    JunctionDemand(f64),
+    ReservoirHead(f64),
+}
+
+///
+/// Numeric parameter of an element type, whose value is given later (see Batch).
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParameterKind {
+    PipeDiameter,
+    PipeRoughness,
+    PumpSpeed,
+    ValveSetting,
+    JunctionDemand,
+    ReservoirHead,
+}
+
+impl ParameterKind {
+    ///
+    /// Parameter of this kind with the value.
+    ///
+    pub fn with_value(&self, value: f64) -> Parameter {
+        match self {
+            Self::PipeDiameter => Parameter::PipeDiameter(value),
+            Self::PipeRoughness => Parameter::PipeRoughness(value),
+            Self::PumpSpeed => Parameter::PumpSpeed(value),
+            Self::ValveSetting => Parameter::ValveSetting(value),
+            Self::JunctionDemand => Parameter::JunctionDemand(value),
+            Self::ReservoirHead => Parameter::ReservoirHead(value),
+        }
+    }
+}
+
+impl Parameter {
+    ///
+    /// Type of the element holding the parameter.
+    ///
+    pub fn element(&self) -> &'static str {
+        match self {
+            Self::PipeDiameter(_) | Self::PipeRoughness(_) | Self::PipeStatus(_) => "pipe",
+            Self::PumpStatus(_) | Self::PumpSpeed(_) => "pump",
+            Self::ValveSetting(_) | Self::ValveStatus(_) => "valve",
+            Self::JunctionDemand(_) => "junction",
+            Self::ReservoirHead(_) => "reservoir",
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match *self {
+            Self::PipeDiameter(v) | Self::PipeRoughness(v) => v.is_finite() && v > 0.0,
+            Self::PumpSpeed(v) | Self::ValveSetting(v) => v.is_finite() && v >= 0.0,
+            Self::JunctionDemand(v) | Self::ReservoirHead(v) => v.is_finite(),
+            Self::PipeStatus(_) | Self::PumpStatus(_) | Self::ValveStatus(_) => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ParameterChange {
+    pub target: Target,
+    pub parameter: Parameter,
+}
+
+///
+/// A set of parameter changes applied in one call (see Network::apply_parameters).
+///
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParameterSet {
+    pub changes: Vec<ParameterChange>,
+
+    ///
+    /// Global demand multiplier (None : unchanged).
+    ///
+    pub demand_multiplier: Option<f64>,
+}
+
+impl ParameterSet {
+    pub fn new() -> Self {
+        ParameterSet::default()
+    }
+
+    pub fn set(mut self, target: Target, parameter: Parameter) -> Self {
+        self.changes.push(ParameterChange { target, parameter });
+        self
+    }
+
+    ///
+    /// Diameters of the pipes, by index (diameters[i] for the pipe i).
+    ///
+    pub fn set_pipe_diameters(mut self, diameters: &[f64]) -> Self {
+        self.changes
+            .extend(diameters.iter().enumerate().map(|(i, d)| ParameterChange {
+                target: Target::Index(i),
+                parameter: Parameter::PipeDiameter(*d),
+            }));
+        self
+    }
+
+    ///
+    /// Roughnesses of the pipes, by index (roughnesses[i] for the pipe i).
+    ///
+    pub fn set_pipe_roughnesses(mut self, roughnesses: &[f64]) -> Self {
+        self.changes.extend(
+            roughnesses
+                .iter()
+                .enumerate()
+                .map(|(i, r)| ParameterChange {
+                    target: Target::Index(i),
+                    parameter: Parameter::PipeRoughness(*r),
+                }),
+        );
+        self
+    }
+
+    pub fn set_demand_multiplier(mut self, multiplier: f64) -> Self {
+        self.demand_multiplier = Some(multiplier);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len() + usize::from(self.demand_multiplier.is_some())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterError {
+    ///
+    /// No element of the parameter type at this index or with this id.
+    ///
+    NotFound(ParameterChange),
+
+    ///
+    /// The value is out of range (non positive diameter, negative speed, ...).
+    ///
+    InvalidValue(ParameterChange),
+    InvalidDemandMultiplier(f64),
+}
+
+impl Display for ParameterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(change) => write!(
+                f,
+                "no {} at {:?}",
+                change.parameter.element(),
+                change.target
+            ),
+            Self::InvalidValue(change) => write!(
+                f,
+                "invalid value {:?} for the {} at {:?}",
+                change.parameter,
+                change.parameter.element(),
+                change.target
+            ),
+            Self::InvalidDemandMultiplier(v) => write!(f, "invalid demand multiplier {}", v),
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {}
+
+impl Network {
+    ///
+    /// Apply a set of parameter changes. All the changes are checked first :
+    /// on error, the network is left unchanged.
+    ///
+    pub fn apply_parameters(&mut self, parameters: &ParameterSet) -> Result<(), ParameterError> {
+        if let Some(multiplier) = parameters.demand_multiplier
+            && !(multiplier.is_finite() && multiplier >= 0.0)
+        {
+            return Err(ParameterError::InvalidDemandMultiplier(multiplier));
+        }
+
+        let mut positions: Vec<usize> = Vec::with_capacity(parameters.changes.len());
+        for change in parameters.changes.iter() {
+            if !change.parameter.is_valid() {
+                return Err(ParameterError::InvalidValue(*change));
+            }
+            positions.push(
+                self.parameter_position(change)
+                    .ok_or(ParameterError::NotFound(*change))?,
+            );
+        }
+
+        for (change, i) in parameters.changes.iter().zip(positions) {
+            match change.parameter {
+                Parameter::PipeDiameter(v) => {
+                    if let Some(pipes) = &mut self.pipes {
+                        pipes[i].diameter = v;
+                    }
+                }
+                Parameter::PipeRoughness(v) => {
+                    if let Some(pipes) = &mut self.pipes {
+                        pipes[i].roughness = v;
+                    }
+                }
+                Parameter::PipeStatus(v) => {
+                    if let Some(pipes) = &mut self.pipes {
+                        pipes[i].status = v;
+                    }
+                }
+                Parameter::PumpStatus(v) => {
+                    if let Some(pumps) = &mut self.pumps {
+                        pumps[i].status = v;
+                    }
+                }
+                Parameter::PumpSpeed(v) => {
+                    if let Some(pumps) = &mut self.pumps {
+                        pumps[i].set_speed(v);
+                    }
+                }
+                Parameter::ValveSetting(v) => {
+                    if let Some(valves) = &mut self.valves {
+                        valves[i].k_value = v;
+                    }
+                }
+                Parameter::ValveStatus(v) => {
+                    if let Some(valves) = &mut self.valves {
+                        valves[i].status = v;
+                    }
+                }
+                Parameter::JunctionDemand(v) => {
+                    if let Some(junctions) = &mut self.junctions {
+                        junctions[i].demand = v;
+                    }
+                }
+                Parameter::ReservoirHead(v) => {
+                    if let Some(reservoirs) = &mut self.reservoirs {
+                        reservoirs[i].head = v;
+                    }
+                }
+            }
+        }
+
+        if let Some(multiplier) = parameters.demand_multiplier {
+            self.options.demand_multiplier = multiplier;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Position of the changed element in the vector of its type. An element addressed by its id
+    /// is located through the index, rebuilt if it is stale.
+    ///
+    fn parameter_position(&mut self, change: &ParameterChange) -> Option<usize> {
+        let count = |len: Option<usize>| len.unwrap_or(0);
+        match (change.parameter, change.target) {
+            (
+                Parameter::PipeDiameter(_) | Parameter::PipeRoughness(_) | Parameter::PipeStatus(_),
+                Target::Index(i),
+            ) => (i < count(self.pipes.as_ref().map(|v| v.len()))).then_some(i),
+            (Parameter::PumpStatus(_) | Parameter::PumpSpeed(_), Target::Index(i)) => {
+                (i < count(self.pumps.as_ref().map(|v| v.len()))).then_some(i)
+            }
+            (Parameter::ValveSetting(_) | Parameter::ValveStatus(_), Target::Index(i)) => {
+                (i < count(self.valves.as_ref().map(|v| v.len()))).then_some(i)
+            }
+            (Parameter::JunctionDemand(_), Target::Index(i)) => {
+                (i < count(self.junctions.as_ref().map(|v| v.len()))).then_some(i)
+            }
+            (Parameter::ReservoirHead(_), Target::Index(i)) => {
+                (i < count(self.reservoirs.as_ref().map(|v| v.len()))).then_some(i)
+            }
+            (
+                Parameter::PipeDiameter(_) | Parameter::PipeRoughness(_) | Parameter::PipeStatus(_),
+                Target::Id(id),
+            ) => match self.checked_link(id)? {
+                LinkRef::Pipe(k) => Some(k),
+                _ => None,
+            },
+            (Parameter::PumpStatus(_) | Parameter::PumpSpeed(_), Target::Id(id)) => {
+                match self.checked_link(id)? {
+                    LinkRef::Pump(k) => Some(k),
+                    _ => None,
+                }
+            }
+            (Parameter::ValveSetting(_) | Parameter::ValveStatus(_), Target::Id(id)) => {
+                match self.checked_link(id)? {
+                    LinkRef::Valve(k) => Some(k),
+                    _ => None,
+                }
+            }
+            (Parameter::JunctionDemand(_), Target::Id(id)) => match self.checked_node(id)? {
+                NodeRef::Junction(i) => Some(i),
+                _ => None,
+            },
+            (Parameter::ReservoirHead(_), Target::Id(id)) => match self.checked_node(id)? {
+                NodeRef::Reservoir(i) => Some(i),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hanoi() -> Network {
+        let file = concat!(env!("CARGO_MANIFEST_DIR"), "/src/bin/data/Hanoi.inp");
+        Network::read_from_file(file).unwrap()
+    }
+
+    #[test]
+    fn applies_by_index_and_by_id() {
+        let mut wdn = hanoi();
+        let id = wdn.pipes.as_ref().unwrap()[3].id;
+        let set = ParameterSet::new()
+            .set(Target::Index(1), Parameter::PipeDiameter(300.0))
+            .set(Target::Id(id), Parameter::PipeRoughness(90.0))
+            .set_demand_multiplier(1.5);
+        wdn.apply_parameters(&set).unwrap();
+        let pipes = wdn.pipes.as_ref().unwrap();
+        assert_eq!(pipes[1].diameter, 300.0);
+        assert_eq!(pipes[3].roughness, 90.0);
+        assert_eq!(wdn.options.demand_multiplier, 1.5);
+    }
+
+    #[test]
+    fn locates_the_id_after_a_direct_edit() {
+        let mut wdn = hanoi();
+        let id = wdn.pipes.as_ref().unwrap()[3].id;
+        // the index is built, then the pipes vector is edited without reindex()
+        assert!(wdn.pipe(id).is_some());
+        wdn.pipes.as_mut().unwrap().remove(0);
+
+        let set = ParameterSet::new().set(Target::Id(id), Parameter::PipeDiameter(300.0));
+        wdn.apply_parameters(&set).unwrap();
+        let pipes = wdn.pipes.as_ref().unwrap();
+        assert_eq!(pipes[2].id, id);
+        assert_eq!(pipes[2].diameter, 300.0);
+        assert!(pipes[3].diameter != 300.0);
+    }
+
+    #[test]
+    fn rejects_unknown_elements_and_invalid_values() {
+        let mut wdn = hanoi();
+        let count = wdn.pipes.as_ref().unwrap().len();
+        for change in [
+            ParameterChange {
+                target: Target::Index(count),
+                parameter: Parameter::PipeDiameter(300.0),
+            },
+            ParameterChange {
+                target: Target::Id(9999),
+                parameter: Parameter::PipeDiameter(300.0),
+            },
+            ParameterChange {
+                target: Target::Index(0),
+                parameter: Parameter::PumpSpeed(1.0),
+            },
+        ] {
+            let set = ParameterSet::new().set(change.target, change.parameter);
+            assert_eq!(
+                wdn.apply_parameters(&set),
+                Err(ParameterError::NotFound(change))
+            );
+        }
+        let set = ParameterSet::new().set(Target::Index(0), Parameter::PipeDiameter(f64::NAN));
+        assert!(matches!(
+            wdn.apply_parameters(&set),
+            Err(ParameterError::InvalidValue(_))
+        ));
+    }
+}
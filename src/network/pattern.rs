@@ -0,0 +1,53 @@
+use super::*;
+
+///
+/// Time pattern : its multipliers apply in turn over periods of Options::times.pattern_step,
+/// and repeat after the last one.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pattern {
+    pub id: usize,
+    pub name: Option<String>,
+    pub multipliers: Vec<f64>,
+}
+
+impl Pattern {
+    pub fn new(id: usize, multipliers: Vec<f64>) -> Self {
+        Pattern {
+            id,
+            name: None,
+            multipliers,
+        }
+    }
+
+    ///
+    /// Multiplier at a time (s) from the start of the simulation (1.0 for an empty pattern).
+    ///
+    pub fn multiplier_at(&self, time: u64, times: &TimeOptions) -> f64 {
+        if self.multipliers.is_empty() {
+            return 1.0;
+        }
+        let period = (time + times.pattern_start) / u64::max(times.pattern_step, 1);
+        self.multipliers[(period % self.multipliers.len() as u64) as usize]
+    }
+}
+
+impl Network {
+    pub fn pattern(&self, id: usize) -> Option<&Pattern> {
+        self.patterns.as_ref()?.iter().find(|p| p.id == id)
+    }
+
+    ///
+    /// Demand pattern of a junction : its own pattern, else the default pattern of the options.
+    ///
+    pub fn demand_pattern(&self, junction: &Junction) -> Option<&Pattern> {
+        self.pattern(junction.pattern.unwrap_or(self.options.pattern))
+    }
+
+    ///
+    /// Head pattern of a reservoir : its own pattern, else none (constant head).
+    ///
+    pub fn head_pattern(&self, reservoir: &Reservoir) -> Option<&Pattern> {
+        self.pattern(reservoir.pattern?)
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use super::*;
+use crate::units::Quantity;
+
+///
+/// Total length of the pipes of a diameter.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiameterLength {
+    pub diameter: f64,
+    pub count: usize,
+    pub length: f64,
+}
+
+///
+/// Total length of the pipes of a material (None : material not given).
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialLength {
+    pub material: Option<String>,
+    pub count: usize,
+    pub length: f64,
+}
+
+///
+/// Statistics of a network (see Network::summary), in the network units.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkSummary {
+    pub units: Units,
+    pub junctions: usize,
+    pub tanks: usize,
+    pub reservoirs: usize,
+    pub pipes: usize,
+    pub pumps: usize,
+    pub valves: usize,
+    pub total_pipe_length: f64,
+
+    ///
+    /// Sorted by increasing diameter.
+    ///
+    pub length_by_diameter: Vec<DiameterLength>,
+
+    ///
+    /// Sorted by material name, pipes without material last.
+    ///
+    pub length_by_material: Vec<MaterialLength>,
+    pub total_base_demand: f64,
+
+    ///
+    /// Elevation range of the junctions and tanks.
+    ///
+    pub min_elevation: Option<f64>,
+    pub max_elevation: Option<f64>,
+
+    ///
+    /// Number of independent loops : links - nodes + connected components.
+    ///
+    pub loops: usize,
+
+    ///
+    /// Junctions connected to a single link.
+    ///
+    pub dead_ends: usize,
+}
+
+impl Display for NetworkSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let length = self.units.label(Quantity::Length);
+        let diameter = self.units.label(Quantity::Diameter);
+
+        writeln!(f, "Network summary ({})", self.units)?;
+        writeln!(
+            f,
+            "Junctions: {}, tanks: {}, reservoirs: {}",
+            self.junctions, self.tanks, self.reservoirs
+        )?;
+        writeln!(
+            f,
+            "Pipes: {}, pumps: {}, valves: {}",
+            self.pipes, self.pumps, self.valves
+        )?;
+        writeln!(
+            f,
+            "Total pipe length: {:.2} {}",
+            self.total_pipe_length, length
+        )?;
+        for item in self.length_by_diameter.iter() {
+            writeln!(
+                f,
+                "  diameter {} {} : {} pipe(s), {:.2} {}",
+                item.diameter, diameter, item.count, item.length, length
+            )?;
+        }
+        for item in self.length_by_material.iter() {
+            writeln!(
+                f,
+                "  material {} : {} pipe(s), {:.2} {}",
+                item.material.as_deref().unwrap_or("(unknown)"),
+                item.count,
+                item.length,
+                length
+            )?;
+        }
+        writeln!(
+            f,
+            "Total base demand: {:.4} {}",
+            self.total_base_demand,
+            self.units.label(Quantity::Flow)
+        )?;
+        if let (Some(min), Some(max)) = (self.min_elevation, self.max_elevation) {
+            writeln!(f, "Elevation: {:.2} to {:.2} {}", min, max, length)?;
+        }
+        writeln!(f, "Loops: {}, dead ends: {}", self.loops, self.dead_ends)
+    }
+}
+
+impl Network {
+    ///
+    /// Element counts, pipe lengths, demand, elevations and topology of the network.
+    ///
+    pub fn summary(&self) -> NetworkSummary {
+        let pipes: &[Pipe] = self.pipes.as_deref().unwrap_or_default();
+
+        let mut by_diameter: Vec<DiameterLength> = Vec::new();
+        let mut by_material: HashMap<Option<String>, (usize, f64)> = HashMap::new();
+        for pipe in pipes.iter() {
+            match by_diameter.iter_mut().find(|d| d.diameter == pipe.diameter) {
+                Some(item) => {
+                    item.count += 1;
+                    item.length += pipe.length;
+                }
+                None => by_diameter.push(DiameterLength {
+                    diameter: pipe.diameter,
+                    count: 1,
+                    length: pipe.length,
+                }),
+            }
+            let item = by_material.entry(pipe.material.clone()).or_default();
+            item.0 += 1;
+            item.1 += pipe.length;
+        }
+        by_diameter.sort_by(|a, b| a.diameter.total_cmp(&b.diameter));
+        let mut length_by_material: Vec<MaterialLength> = by_material
+            .into_iter()
+            .map(|(material, (count, length))| MaterialLength {
+                material,
+                count,
+                length,
+            })
+            .collect();
+        // None is greater than any material name : pipes without material are listed last.
+        length_by_material.sort_by(|a, b| match (&a.material, &b.material) {
+            (Some(x), Some(y)) => x.cmp(y),
+            (x, y) => y.is_some().cmp(&x.is_some()),
+        });
+
+        let mut elevations: Vec<f64> = Vec::new();
+        if let Some(junctions) = &self.junctions {
+            elevations.extend(junctions.iter().map(|jn| jn.elevation));
+        }
+        if let Some(tanks) = &self.tanks {
+            elevations.extend(tanks.iter().map(|tnk| tnk.elevation));
+        }
+
+        let dead_ends = self.junctions.as_ref().map_or(0, |junctions| {
+            junctions
+                .iter()
+                .filter(|jn| self.links_of(jn.id).len() == 1)
+                .count()
+        });
+
+        NetworkSummary {
+            units: self.options.units(),
+            junctions: self.junctions.as_ref().map_or(0, |v| v.len()),
+            tanks: self.tanks.as_ref().map_or(0, |v| v.len()),
+            reservoirs: self.reservoirs.as_ref().map_or(0, |v| v.len()),
+            pipes: pipes.len(),
+            pumps: self.pumps.as_ref().map_or(0, |v| v.len()),
+            valves: self.valves.as_ref().map_or(0, |v| v.len()),
+            total_pipe_length: pipes.iter().map(|p| p.length).sum(),
+            length_by_diameter: by_diameter,
+            length_by_material,
+            total_base_demand: self
+                .junctions
+                .as_ref()
+                .map_or(0.0, |v| v.iter().map(|jn| jn.demand).sum()),
+            min_elevation: elevations.iter().copied().reduce(f64::min),
+            max_elevation: elevations.iter().copied().reduce(f64::max),
+            loops: self.count_loops(),
+            dead_ends,
+        }
+    }
+
+    ///
+    /// Cyclomatic number of the network graph (links whose end nodes exist).
+    ///
+    fn count_loops(&self) -> usize {
+        let index = self.index();
+        let mut node_ids: Vec<usize> = Vec::with_capacity(index.node_count());
+        if let Some(junctions) = &self.junctions {
+            node_ids.extend(junctions.iter().map(|jn| jn.id));
+        }
+        if let Some(tanks) = &self.tanks {
+            node_ids.extend(tanks.iter().map(|tnk| tnk.id));
+        }
+        if let Some(reservoirs) = &self.reservoirs {
+            node_ids.extend(reservoirs.iter().map(|rsrvr| rsrvr.id));
+        }
+
+        // union find on node positions
+        let position: HashMap<usize, usize> = node_ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect();
+        let mut parent: Vec<usize> = (0..node_ids.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        let mut links = 0usize;
+        let mut components = node_ids.len();
+        for node_id in node_ids.iter() {
+            for link_id in index.links_of(*node_id) {
+                let Some((start, end)) = index.ends_of(*link_id) else {
+                    continue;
+                };
+                // each link is visited from its start node only
+                if start != *node_id {
+                    continue;
+                }
+                let (Some(i), Some(j)) = (position.get(&start), position.get(&end)) else {
+                    continue;
+                };
+                links += 1;
+                let (ri, rj) = (root(&mut parent, *i), root(&mut parent, *j));
+                if ri != rj {
+                    parent[ri] = rj;
+                    components -= 1;
+                }
+            }
+        }
+        (links + components).saturating_sub(node_ids.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::link::pipe::PipeBuilder;
+
+    fn pipe(id: usize, start: usize, end: usize, length: f64, diameter: f64) -> PipeBuilder {
+        PipeBuilder::new()
+            .set_id(id)
+            .set_start(start)
+            .set_end(end)
+            .set_length(length)
+            .set_diameter(diameter)
+    }
+
+    #[test]
+    fn sums_up_a_small_network() {
+        let junction = |id: usize, elevation: f64, demand: f64| {
+            JunctionBuilder::new()
+                .set_id(id)
+                .set_elevation(elevation)
+                .set_demand(demand)
+                .build()
+        };
+        // a loop 1-2-3, a dead end 4, fed by a reservoir and a tank
+        let network = NetworkBuilder::new()
+            .set_options(OptionsBuilder::new().set_flow_unit(FlowUnits::Lps).build())
+            .set_reservoirs(Some(vec![
+                ReservoirBuilder::new().set_id(100).set_head(120.0).build(),
+            ]))
+            .set_tanks(Some(vec![
+                TankBuilder::new().set_id(50).set_elevation(90.0).build(),
+            ]))
+            .set_junctions(Some(vec![
+                junction(1, 10.0, 5.0),
+                junction(2, 20.0, 3.0),
+                junction(3, 5.0, 2.0),
+                junction(4, 30.0, 0.0),
+            ]))
+            .set_pipes(Some(vec![
+                pipe(1, 100, 1, 1000.0, 300.0).set_material("PVC").build(),
+                pipe(2, 1, 2, 500.0, 200.0).set_material("PVC").build(),
+                pipe(3, 2, 3, 400.0, 200.0).build(),
+                pipe(4, 3, 1, 300.0, 150.0).set_material("DI").build(),
+                pipe(5, 2, 4, 200.0, 150.0).set_material("DI").build(),
+                pipe(6, 50, 3, 100.0, 300.0).set_material("PVC").build(),
+            ]))
+            .build();
+
+        let summary = network.summary();
+        assert_eq!(
+            (summary.junctions, summary.tanks, summary.reservoirs),
+            (4, 1, 1)
+        );
+        assert_eq!((summary.pipes, summary.pumps, summary.valves), (6, 0, 0));
+        assert_eq!(summary.total_pipe_length, 2500.0);
+        assert_eq!(summary.total_base_demand, 10.0);
+        assert_eq!(summary.min_elevation, Some(5.0));
+        assert_eq!(summary.max_elevation, Some(90.0));
+        assert_eq!((summary.loops, summary.dead_ends), (1, 1));
+
+        let by_diameter: Vec<(f64, usize, f64)> = summary
+            .length_by_diameter
+            .iter()
+            .map(|d| (d.diameter, d.count, d.length))
+            .collect();
+        assert_eq!(
+            by_diameter,
+            vec![(150.0, 2, 500.0), (200.0, 2, 900.0), (300.0, 2, 1100.0)]
+        );
+        let by_material: Vec<(Option<&str>, usize, f64)> = summary
+            .length_by_material
+            .iter()
+            .map(|m| (m.material.as_deref(), m.count, m.length))
+            .collect();
+        assert_eq!(
+            by_material,
+            vec![
+                (Some("DI"), 2, 500.0),
+                (Some("PVC"), 3, 1600.0),
+                (None, 1, 400.0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,607 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    ///
+    /// The network can not be solved.
+    ///
+    Error,
+
+    ///
+    /// The network can be solved, but the model is suspicious.
+    ///
+    Warning,
+}
+
+///
+/// A network element involved in a validation issue.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ElementId {
+    Node(usize),
+    Link(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IssueKind {
+    NoJunction,
+    NoLink,
+    NoFixedHead,
+    DuplicateNodeId,
+    DuplicateLinkId,
+
+    ///
+    /// A link end node does not exist.
+    ///
+    DanglingLink,
+
+    ///
+    /// A link starts and ends at the same node.
+    ///
+    SelfLoop,
+
+    ///
+    /// A node not connected to any link (a junction is solved as disconnected).
+    ///
+    IsolatedNode,
+
+    ///
+    /// A group of connected nodes without tank or reservoir (the junctions are solved as disconnected).
+    ///
+    IslandWithoutFixedHead,
+    InvalidLength,
+    InvalidDiameter,
+    InvalidRoughness,
+    PumpBetweenFixedHeads,
+    NegativeDemand,
+
+    ///
+    /// The initial level of a tank of variable level is not between its minimum and maximum levels.
+    ///
+    InvalidTankLevels,
+
+    ///
+    /// A junction or a reservoir refers to a pattern which does not exist.
+    ///
+    UnknownPattern,
+
+    ///
+    /// A control or a rule refers to a link or a node which does not exist.
+    ///
+    UnknownControlElement,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub elements: Vec<ElementId>,
+    pub message: String,
+}
+
+///
+/// Errors and warnings found in a network (see Network::validate).
+///
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    ///
+    /// True if the network can be solved (no errors, warnings are allowed).
+    ///
+    pub fn is_valid(&self) -> bool {
+        !self.has_errors()
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        kind: IssueKind,
+        elements: Vec<ElementId>,
+        message: String,
+    ) {
+        self.issues.push(ValidationIssue {
+            severity,
+            kind,
+            elements,
+            message,
+        });
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Validation : {} error(s), {} warning(s)",
+            self.errors().count(),
+            self.warnings().count()
+        )?;
+        for issue in self.issues.iter() {
+            writeln!(
+                f,
+                "{:?} {:?} : {}",
+                issue.severity, issue.kind, issue.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Network {
+    ///
+    /// Check the network before solving : ids, connectivity, fixed heads and link parameters.
+    ///
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        // node id -> is a fixed head node (tank, reservoir)
+        let mut nodes: HashMap<usize, bool> = HashMap::new();
+        let mut node_ids: Vec<(usize, bool)> = Vec::new();
+        if let Some(junctions) = &self.junctions {
+            node_ids.extend(junctions.iter().map(|j| (j.id, false)));
+        }
+        if let Some(tanks) = &self.tanks {
+            node_ids.extend(tanks.iter().map(|t| (t.id, true)));
+        }
+        if let Some(reservoirs) = &self.reservoirs {
+            node_ids.extend(reservoirs.iter().map(|r| (r.id, true)));
+        }
+        for (id, fixed) in node_ids {
+            if nodes.insert(id, fixed).is_some() {
+                report.push(
+                    Severity::Error,
+                    IssueKind::DuplicateNodeId,
+                    vec![ElementId::Node(id)],
+                    format!("node id {} is used more than once", id),
+                );
+            }
+        }
+
+        if self.junctions.as_ref().is_none_or(|j| j.is_empty()) {
+            report.push(
+                Severity::Error,
+                IssueKind::NoJunction,
+                Vec::new(),
+                "the network has no junction".to_owned(),
+            );
+        }
+        if !nodes.values().any(|fixed| *fixed) {
+            report.push(
+                Severity::Error,
+                IssueKind::NoFixedHead,
+                Vec::new(),
+                "the network has no tank or reservoir".to_owned(),
+            );
+        }
+
+        // links : (id, start, end, is a pump)
+        let mut links: Vec<(usize, usize, usize, bool)> = Vec::new();
+        if let Some(pipes) = &self.pipes {
+            links.extend(pipes.iter().map(|p| (p.id, p.start, p.end, false)));
+            for pipe in pipes.iter() {
+                Self::validate_link_size(&mut report, pipe.id, pipe.length, pipe.diameter);
+                if pipe.roughness <= 0.0 {
+                    report.push(
+                        Severity::Error,
+                        IssueKind::InvalidRoughness,
+                        vec![ElementId::Link(pipe.id)],
+                        format!(
+                            "pipe {} : roughness {} must be positive",
+                            pipe.id, pipe.roughness
+                        ),
+                    );
+                }
+            }
+        }
+        if let Some(pumps) = &self.pumps {
+            links.extend(pumps.iter().map(|p| (p.id, p.start, p.end, true)));
+        }
+        if let Some(valves) = &self.valves {
+            links.extend(valves.iter().map(|v| (v.id, v.start, v.end, false)));
+        }
+        if links.is_empty() {
+            report.push(
+                Severity::Error,
+                IssueKind::NoLink,
+                Vec::new(),
+                "the network has no link".to_owned(),
+            );
+        }
+
+        let mut link_ids: HashSet<usize> = HashSet::new();
+        let mut adjacency: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (id, start, end, is_pump) in links.iter().copied() {
+            if !link_ids.insert(id) {
+                report.push(
+                    Severity::Error,
+                    IssueKind::DuplicateLinkId,
+                    vec![ElementId::Link(id)],
+                    format!("link id {} is used more than once", id),
+                );
+            }
+            let missing: Vec<usize> = [start, end]
+                .into_iter()
+                .filter(|n| !nodes.contains_key(n))
+                .collect();
+            if !missing.is_empty() {
+                let mut elements = vec![ElementId::Link(id)];
+                elements.extend(missing.iter().map(|n| ElementId::Node(*n)));
+                report.push(
+                    Severity::Error,
+                    IssueKind::DanglingLink,
+                    elements,
+                    format!("link {} : end node(s) {:?} do not exist", id, missing),
+                );
+                continue;
+            }
+            if start == end {
+                report.push(
+                    Severity::Error,
+                    IssueKind::SelfLoop,
+                    vec![ElementId::Link(id), ElementId::Node(start)],
+                    format!("link {} starts and ends at node {}", id, start),
+                );
+                continue;
+            }
+            if is_pump && nodes[&start] && nodes[&end] {
+                report.push(
+                    Severity::Error,
+                    IssueKind::PumpBetweenFixedHeads,
+                    vec![
+                        ElementId::Link(id),
+                        ElementId::Node(start),
+                        ElementId::Node(end),
+                    ],
+                    format!(
+                        "pump {} connects two fixed head nodes ({} ---> {})",
+                        id, start, end
+                    ),
+                );
+            }
+            adjacency.entry(start).or_default().push(end);
+            adjacency.entry(end).or_default().push(start);
+        }
+
+        if let Some(junctions) = &self.junctions {
+            for jn in junctions.iter().filter(|j| j.demand < 0.0) {
+                report.push(
+                    Severity::Warning,
+                    IssueKind::NegativeDemand,
+                    vec![ElementId::Node(jn.id)],
+                    format!(
+                        "junction {} : negative demand {} (inflow)",
+                        jn.id, jn.demand
+                    ),
+                );
+            }
+        }
+
+        if let Some(tanks) = &self.tanks {
+            for tnk in tanks.iter().filter(|t| {
+                t.diameter > 0.0
+                    && !(t.min_level <= t.initial_level && t.initial_level <= t.max_level)
+            }) {
+                report.push(
+                    Severity::Error,
+                    IssueKind::InvalidTankLevels,
+                    vec![ElementId::Node(tnk.id)],
+                    format!(
+                        "tank {} : initial level {} is not between {} and {}",
+                        tnk.id, tnk.initial_level, tnk.min_level, tnk.max_level
+                    ),
+                );
+            }
+        }
+        self.validate_patterns(&mut report);
+        self.validate_controls(&mut report);
+
+        Self::validate_connectivity(&mut report, &nodes, &adjacency);
+        report
+    }
+
+    fn validate_patterns(&self, report: &mut ValidationReport) {
+        if let Some(junctions) = &self.junctions {
+            for jn in junctions.iter() {
+                if let Some(pattern) = jn.pattern
+                    && self.pattern(pattern).is_none()
+                {
+                    report.push(
+                        Severity::Warning,
+                        IssueKind::UnknownPattern,
+                        vec![ElementId::Node(jn.id)],
+                        format!("junction {} : unknown pattern {}", jn.id, pattern),
+                    );
+                }
+            }
+        }
+        if let Some(reservoirs) = &self.reservoirs {
+            for rsrvr in reservoirs.iter() {
+                if let Some(pattern) = rsrvr.pattern
+                    && self.pattern(pattern).is_none()
+                {
+                    report.push(
+                        Severity::Warning,
+                        IssueKind::UnknownPattern,
+                        vec![ElementId::Node(rsrvr.id)],
+                        format!("reservoir {} : unknown pattern {}", rsrvr.id, pattern),
+                    );
+                }
+            }
+        }
+    }
+
+    fn validate_controls(&self, report: &mut ValidationReport) {
+        // (control description, link ids, node ids)
+        let mut controls: Vec<(String, Vec<usize>, Vec<usize>)> = Vec::new();
+        let node_of = |condition: &Condition| match condition.variable {
+            Variable::Node(id) => Some(id),
+            Variable::Time | Variable::ClockTime => None,
+        };
+        if let Some(items) = &self.controls {
+            controls.extend(items.iter().map(|c| {
+                (
+                    format!("control \"{}\"", c),
+                    vec![c.action.link],
+                    node_of(&c.condition).into_iter().collect(),
+                )
+            }));
+        }
+        if let Some(items) = &self.rules {
+            controls.extend(items.iter().map(|r| {
+                (
+                    format!("rule {}", r.id),
+                    r.then_actions
+                        .iter()
+                        .chain(r.else_actions.iter())
+                        .map(|a| a.link)
+                        .collect(),
+                    r.premises
+                        .iter()
+                        .filter_map(|p| node_of(&p.condition))
+                        .collect(),
+                )
+            }));
+        }
+
+        for (name, links, nodes) in controls {
+            let mut elements: Vec<ElementId> = links
+                .into_iter()
+                .filter(|id| self.index().link(*id).is_none())
+                .map(ElementId::Link)
+                .collect();
+            elements.extend(
+                nodes
+                    .into_iter()
+                    .filter(|id| self.index().node(*id).is_none())
+                    .map(ElementId::Node),
+            );
+            if !elements.is_empty() {
+                report.push(
+                    Severity::Warning,
+                    IssueKind::UnknownControlElement,
+                    elements.clone(),
+                    format!("{} : unknown element(s) {:?}", name, elements),
+                );
+            }
+        }
+    }
+
+    fn validate_link_size(report: &mut ValidationReport, id: usize, length: f64, diameter: f64) {
+        if length <= 0.0 || length.is_nan() {
+            report.push(
+                Severity::Error,
+                IssueKind::InvalidLength,
+                vec![ElementId::Link(id)],
+                format!("link {} : length {} must be positive", id, length),
+            );
+        }
+        if diameter <= 0.0 || diameter.is_nan() {
+            report.push(
+                Severity::Error,
+                IssueKind::InvalidDiameter,
+                vec![ElementId::Link(id)],
+                format!("link {} : diameter {} must be positive", id, diameter),
+            );
+        }
+    }
+
+    ///
+    /// Isolated nodes and groups of connected junctions without tank or reservoir : the solver
+    /// removes their junctions from the head system and reports them as disconnected.
+    ///
+    fn validate_connectivity(
+        report: &mut ValidationReport,
+        nodes: &HashMap<usize, bool>,
+        adjacency: &HashMap<usize, Vec<usize>>,
+    ) {
+        let mut ids: Vec<usize> = nodes.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut visited: HashSet<usize> = HashSet::new();
+        for id in ids {
+            if visited.contains(&id) {
+                continue;
+            }
+            if !adjacency.contains_key(&id) {
+                visited.insert(id);
+                report.push(
+                    Severity::Warning,
+                    IssueKind::IsolatedNode,
+                    vec![ElementId::Node(id)],
+                    format!("node {} is not connected to any link", id),
+                );
+                continue;
+            }
+
+            // breadth first search of the connected group
+            let mut group: Vec<usize> = vec![id];
+            visited.insert(id);
+            let mut k = 0;
+            while k < group.len() {
+                for next in adjacency[&group[k]].iter() {
+                    if visited.insert(*next) {
+                        group.push(*next);
+                    }
+                }
+                k += 1;
+            }
+
+            if !group.iter().any(|n| nodes[n]) {
+                group.sort_unstable();
+                report.push(
+                    Severity::Warning,
+                    IssueKind::IslandWithoutFixedHead,
+                    group.iter().map(|n| ElementId::Node(*n)).collect(),
+                    format!(
+                        "nodes {:?} are not connected to any tank or reservoir",
+                        group
+                    ),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::link::pipe::PipeBuilder;
+
+    fn junction(id: usize) -> Junction {
+        JunctionBuilder::new().set_id(id).set_demand(1.0).build()
+    }
+
+    fn pipe(id: usize, start: usize, end: usize) -> Pipe {
+        PipeBuilder::new()
+            .set_id(id)
+            .set_start(start)
+            .set_end(end)
+            .set_length(100.0)
+            .set_diameter(100.0)
+            .build()
+    }
+
+    ///
+    /// Reservoir 100 ---> 1 ---> 2.
+    ///
+    fn network() -> Network {
+        NetworkBuilder::new()
+            .set_reservoirs(Some(vec![
+                ReservoirBuilder::new().set_id(100).set_head(50.0).build(),
+            ]))
+            .set_junctions(Some(vec![junction(1), junction(2)]))
+            .set_pipes(Some(vec![pipe(1, 100, 1), pipe(2, 1, 2)]))
+            .build()
+    }
+
+    ///
+    /// The single issue of the report.
+    ///
+    fn single_issue(network: &Network) -> ValidationIssue {
+        let report = network.validate();
+        assert_eq!(report.issues.len(), 1, "{}", report);
+        report.issues[0].clone()
+    }
+
+    #[test]
+    fn accepts_a_valid_network() {
+        let report = network().validate();
+        assert!(report.issues.is_empty(), "{}", report);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn reports_a_dangling_link() {
+        let mut wdn = network();
+        wdn.pipes.as_mut().unwrap().push(pipe(3, 2, 9));
+        let issue = single_issue(&wdn);
+        assert_eq!(
+            (issue.severity, issue.kind),
+            (Severity::Error, IssueKind::DanglingLink)
+        );
+        assert_eq!(issue.elements, vec![ElementId::Link(3), ElementId::Node(9)]);
+    }
+
+    #[test]
+    fn reports_duplicate_ids() {
+        let mut wdn = network();
+        wdn.junctions.as_mut().unwrap().push(junction(2));
+        let issue = single_issue(&wdn);
+        assert_eq!(
+            (issue.severity, issue.kind),
+            (Severity::Error, IssueKind::DuplicateNodeId)
+        );
+        assert_eq!(issue.elements, vec![ElementId::Node(2)]);
+
+        let mut wdn = network();
+        wdn.pipes.as_mut().unwrap().push(pipe(2, 100, 2));
+        let issue = single_issue(&wdn);
+        assert_eq!(
+            (issue.severity, issue.kind),
+            (Severity::Error, IssueKind::DuplicateLinkId)
+        );
+        assert_eq!(issue.elements, vec![ElementId::Link(2)]);
+    }
+
+    #[test]
+    fn reports_an_island_without_fixed_head() {
+        let mut wdn = network();
+        wdn.junctions
+            .as_mut()
+            .unwrap()
+            .extend([junction(4), junction(3)]);
+        wdn.pipes.as_mut().unwrap().push(pipe(3, 4, 3));
+        let report = wdn.validate();
+        assert!(report.is_valid());
+        let issue = single_issue(&wdn);
+        assert_eq!(
+            (issue.severity, issue.kind),
+            (Severity::Warning, IssueKind::IslandWithoutFixedHead)
+        );
+        assert_eq!(issue.elements, vec![ElementId::Node(3), ElementId::Node(4)]);
+    }
+
+    #[test]
+    fn reports_a_null_length() {
+        let mut wdn = network();
+        wdn.pipes.as_mut().unwrap()[1].length = 0.0;
+        let issue = single_issue(&wdn);
+        assert_eq!(
+            (issue.severity, issue.kind),
+            (Severity::Error, IssueKind::InvalidLength)
+        );
+        assert_eq!(issue.elements, vec![ElementId::Link(2)]);
+    }
+
+    #[test]
+    fn reports_a_null_diameter() {
+        let mut wdn = network();
+        wdn.pipes.as_mut().unwrap()[0].diameter = 0.0;
+        let issue = single_issue(&wdn);
+        assert_eq!(
+            (issue.severity, issue.kind),
+            (Severity::Error, IssueKind::InvalidDiameter)
+        );
+        assert_eq!(issue.elements, vec![ElementId::Link(1)]);
+    }
+}
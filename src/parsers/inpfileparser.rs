@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 
@@ -10,12 +11,22 @@ use crate::network::link::pump::*;
 use crate::network::node::junction::*;
 use crate::network::node::reservoir::*;
 use crate::network::node::tank::*;
-use crate::network::{FlowUnits, HeadlossFormula, Options, OptionsBuilder, link::Link, node::Node};
+use crate::network::{
+    Action, Control, DEFAULT_ACCURACY, DemandModel, EnergyOptions, FlowUnits, HeadlossFormula,
+    LinkAction, Options, OptionsBuilder, Pattern, Rule, TimeOptions, Unbalanced, link::Link,
+    node::Node, parse_time,
+};
+use crate::units::parse_flow_unit;
 
 ///
 /// This is a parser for "*.inp" files (Epanet file format)
 /// Look at : http://wateranalytics.org/EPANET/_inp_file.html
 ///
+/// Besides the Epanet sections, an optional [LEAKAGE] section gives the FAVAD leakage of pipes,
+/// one row per pipe : `pipe_id  area  expansion`, in SI units whatever the flow unit :
+/// the leak area in mm2 per 100 m of pipe and its expansion in mm2 per m of pressure per 100 m
+/// of pipe (optional, 0 by default). Invalid rows are ignored with a warning.
+///
 pub struct InpFileParser<'a> {
     pub file_path: &'a str,
 }
@@ -41,7 +52,26 @@ impl<'a> InpFileParser<'a> {
                 let mut reservoirs = self.get_reservoirs(&lines);
                 let mut pipes = self.get_pipes(&lines);
                 let mut pumps = self.get_pumps(&lines);
-                let options = self.get_options(&lines).unwrap_or_default();
+                let mut options = self.get_options(lines).unwrap_or_default();
+                let curves = self.get_curves(lines);
+                let (energy, efficiency_curves) = self.get_energy(lines);
+                options.energy = energy;
+                options.times = self.get_times(lines);
+                let patterns = self.get_patterns(lines);
+                let pattern_id = |label: &str| {
+                    patterns
+                        .iter()
+                        .find(|p| p.name.as_deref() == Some(label))
+                        .map(|p| p.id)
+                };
+                // default demand pattern : the "Pattern" option, else the pattern "1"
+                options.pattern = self
+                    .get_section(lines, "[OPTIONS]")
+                    .iter()
+                    .find(|row| row.len() == 2 && row[0].eq_ignore_ascii_case("Pattern"))
+                    .map_or(Some("1"), |row| Some(row[1]))
+                    .and_then(pattern_id)
+                    .unwrap_or(0);
 
                 let node_positions = self.get_coordinates(&lines);
                 let link_vertices = self.get_vertices(lines);
@@ -113,6 +143,60 @@ impl<'a> InpFileParser<'a> {
                         };
                     }
                 }
+                // set junctions emitters
+                if let Some(nodes) = junctions.as_mut() {
+                    for (node_id, coefficient) in self.get_emitters(lines).iter() {
+                        if let Some(jn) = nodes.iter_mut().find(|j| j.id == *node_id) {
+                            jn.emitter_coefficient = f64::max(0.0, *coefficient);
+                        }
+                    }
+                }
+
+                // set junctions demand patterns
+                if let Some(nodes) = junctions.as_mut() {
+                    for row in self.get_section(lines, "[JUNCTIONS]").iter() {
+                        if row.len() > 3
+                            && let Some(jn) = nodes.iter_mut().find(|j| row[0] == j.id.to_string())
+                        {
+                            jn.pattern = pattern_id(row[3]);
+                        }
+                    }
+                }
+
+                // set reservoirs head patterns
+                if let Some(nodes) = reservoirs.as_mut() {
+                    for row in self.get_section(lines, "[RESERVOIRS]").iter() {
+                        if row.len() > 2
+                            && let Some(rsrvr) =
+                                nodes.iter_mut().find(|r| row[0] == r.id.to_string())
+                        {
+                            rsrvr.pattern = pattern_id(row[2]);
+                        }
+                    }
+                }
+
+                // set tanks levels and diameters
+                if let Some(nodes) = tanks.as_mut() {
+                    self.set_tanks_levels(lines, nodes)?;
+                }
+
+                // set pipes leakages
+                if let Some(pps) = pipes.as_mut() {
+                    for (pipe_id, leakage) in self.get_leakages(lines).iter() {
+                        if let Some(pipe) = pps.iter_mut().find(|p| p.id == *pipe_id) {
+                            pipe.leakage = Some(*leakage);
+                        }
+                    }
+                }
+
+                // set pumps efficiency curves
+                if let Some(pmps) = pumps.as_mut() {
+                    for (pump_id, curve_id) in efficiency_curves.iter() {
+                        if let Some(pmp) = pmps.iter_mut().find(|p| p.id == *pump_id) {
+                            pmp.efficiency_curve = curves.get(curve_id).cloned();
+                        }
+                    }
+                }
                 /*
                         match pumps {
                             None => (),
@@ -155,6 +239,8 @@ impl<'a> InpFileParser<'a> {
                         .for_each(|lnk| lnk.set_flow_unit(options.flow_unit));
                 };
 
+                let controls = self.get_controls(lines, &pipes);
+                let rules = self.get_rules(lines, &pipes);
                 let wdn = NetworkBuilder::new()
                     .set_title(title)
                     .set_junctions(junctions)
@@ -163,6 +249,13 @@ impl<'a> InpFileParser<'a> {
                     .set_pipes(pipes)
                     .set_pumps(pumps)
                     .set_valves(None)
+                    .set_patterns(if patterns.is_empty() {
+                        None
+                    } else {
+                        Some(patterns)
+                    })
+                    .set_controls(Some(controls).filter(|c| !c.is_empty()))
+                    .set_rules(Some(rules).filter(|r| !r.is_empty()))
                     .set_options(options)
                     .build();
 
@@ -411,6 +504,7 @@ impl<'a> InpFileParser<'a> {
 
                         let mut min_loss: f64 = 0.0;
                         let mut status: LinkStatus = LinkStatus::Open;
+                        let mut check_valve: bool = false;
 
                         if row.len() > 6 {
                             min_loss = match row[6].parse::<f64>() {
@@ -418,15 +512,11 @@ impl<'a> InpFileParser<'a> {
                                 Ok(value) => value,
                             };
                             if row.len() > 7 {
-                                status = match row[7].parse::<String>() {
-                                    Err(_eror) => LinkStatus::Open,
-                                    Ok(value) => {
-                                        if value == "Open" {
-                                            LinkStatus::Open
-                                        } else {
-                                            LinkStatus::Closed
-                                        }
-                                    }
+                                // status : Open, Closed or CV (check valve, initially open).
+                                match row[7].to_uppercase().as_str() {
+                                    "CLOSED" => status = LinkStatus::Closed,
+                                    "CV" => check_valve = true,
+                                    _ => status = LinkStatus::Open,
                                 };
                             };
                         };
@@ -443,7 +533,7 @@ impl<'a> InpFileParser<'a> {
                                 .set_roughness(roughness)
                                 .set_minorloss(min_loss)
                                 .set_status(status)
-                                //.set_check_valve(check_valve)
+                                .set_check_valve(check_valve)
                                 .build();
 
                             pipes.push(pip);
@@ -517,6 +607,14 @@ impl<'a> InpFileParser<'a> {
         let mut index = 0;
         let mut flow_unit: FlowUnits = FlowUnits::Cms;
         let mut headlossformula = HeadlossFormula::Hw;
+        let mut pda: bool = false;
+        let mut minimum_pressure: f64 = 0.0;
+        let mut required_pressure: f64 = 0.1;
+        let mut pressure_exponent: f64 = 0.5;
+        let mut emitter_exponent: f64 = 0.5;
+        let mut trials: usize = 40;
+        let mut accuracy: f64 = DEFAULT_ACCURACY;
+        let mut unbalanced = Unbalanced::StopIter;
 
         for lin in lines.iter() {
             if lin.trim().eq("[OPTIONS]") {
@@ -532,13 +630,50 @@ impl<'a> InpFileParser<'a> {
 
                     //println!("***//////////-------- {:?}", row[0]);
 
-                    if row[0].trim().eq("Units") {
-                        if row[1].trim().eq("LPS") {
-                            flow_unit = FlowUnits::Lps;
+                    if row[0].trim().eq_ignore_ascii_case("Units")
+                        && row.len() > 1
+                        && let Some(unit) = parse_flow_unit(row[1])
+                    {
+                        flow_unit = unit;
+                    };
+
+                    if row.len() > 1 {
+                        if row[0].eq_ignore_ascii_case("Trials")
+                            && let Ok(value) = row[1].parse::<usize>()
+                        {
+                            trials = value;
+                        } else if row[0].eq_ignore_ascii_case("Accuracy")
+                            && let Ok(value) = row[1].parse::<f64>()
+                        {
+                            accuracy = value;
+                        } else if row[0].eq_ignore_ascii_case("Unbalanced") {
+                            // STOP or CONTINUE n
+                            unbalanced = match row.get(2).map(|n| n.parse::<usize>()) {
+                                Some(Ok(n)) if row[1].eq_ignore_ascii_case("Continue") => {
+                                    Unbalanced::ContinueIter(n)
+                                }
+                                _ => Unbalanced::StopIter,
+                            };
                         }
+                    };
+
+                    if row[0].eq_ignore_ascii_case("Demand")
+                        && row.len() > 2
+                        && row[1].eq_ignore_ascii_case("Model")
+                    {
+                        pda = row[2].eq_ignore_ascii_case("PDA");
+                    };
 
-                        if row[1].trim().eq("CMH") {
-                            flow_unit = FlowUnits::Cmh;
+                    if row.len() > 2
+                        && let Ok(value) = row[2].parse::<f64>()
+                    {
+                        let param = (row[0].to_uppercase(), row[1].to_uppercase());
+                        match (param.0.as_str(), param.1.as_str()) {
+                            ("MINIMUM", "PRESSURE") => minimum_pressure = value,
+                            ("REQUIRED", "PRESSURE") => required_pressure = value,
+                            ("PRESSURE", "EXPONENT") => pressure_exponent = value,
+                            ("EMITTER", "EXPONENT") => emitter_exponent = value,
+                            _ => {}
                         }
                     };
 
@@ -565,13 +700,345 @@ impl<'a> InpFileParser<'a> {
         //for jn in junctions.iter() {
         //   println!("* {:?}", jn.to_string());
         //}
+        let demand_model = if pda {
+            DemandModel::Pda {
+                minimum_pressure,
+                required_pressure,
+                exponent: pressure_exponent,
+            }
+        } else {
+            DemandModel::Dda
+        };
+
         let optns: Options = OptionsBuilder::new()
             .set_flow_unit(flow_unit)
             .set_headlossformula(headlossformula)
+            .set_demand_model(demand_model)
+            .set_emitter_exponent(emitter_exponent)
+            .set_trials(trials)
+            .set_accuracy(accuracy)
+            .set_unbalanced(unbalanced)
             .build();
         Some(optns)
     }
 
+    ///
+    /// Get the rows of a section (without blank and comment lines).
+    ///
+    fn get_section<'b>(&self, lines: &'b [String], header: &str) -> Vec<Vec<&'b str>> {
+        let mut rows: Vec<Vec<&str>> = Vec::new();
+        let mut inside: bool = false;
+
+        for lin in lines.iter() {
+            let trimmed = lin.trim();
+            if trimmed.starts_with('[') {
+                inside = trimmed.eq_ignore_ascii_case(header);
+                continue;
+            }
+            if !inside || trimmed.is_empty() || trimmed.starts_with(';') {
+                continue;
+            }
+            // remove the end of line comment
+            let content = match trimmed.find(';') {
+                None => trimmed,
+                Some(pos) => &trimmed[..pos],
+            };
+            let row: Vec<&str> = content.split_whitespace().collect();
+            if !row.is_empty() {
+                rows.push(row);
+            }
+        }
+        rows
+    }
+
+    ///
+    /// Get the curves ([CURVES] section) as (x, y) points by curve id.
+    ///
+    fn get_curves(&self, lines: &[String]) -> HashMap<String, Vec<(f64, f64)>> {
+        let mut curves: HashMap<String, Vec<(f64, f64)>> = HashMap::new();
+
+        for row in self.get_section(lines, "[CURVES]").iter() {
+            if row.len() < 3 {
+                continue;
+            }
+            if let (Ok(x), Ok(y)) = (row[1].parse::<f64>(), row[2].parse::<f64>()) {
+                curves.entry(row[0].to_string()).or_default().push((x, y));
+            }
+        }
+        curves
+    }
+
+    ///
+    /// Get the junctions emitter coefficients ([EMITTERS] section).
+    ///
+    fn get_emitters(&self, lines: &[String]) -> Vec<(usize, f64)> {
+        self.get_section(lines, "[EMITTERS]")
+            .iter()
+            .filter(|row| row.len() > 1)
+            .filter_map(
+                |row| match (row[0].parse::<usize>(), row[1].parse::<f64>()) {
+                    (Ok(id), Ok(coefficient)) => Some((id, coefficient)),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    ///
+    /// Set the minimum and maximum levels and the diameters of the tanks ([TANKS] section :
+    /// id, elevation, initial level, minimum level, maximum level, diameter).
+    /// A row without the three values gives a tank of fixed level (with a warning) ;
+    /// a row with some of them only or with an invalid one is rejected.
+    ///
+    fn set_tanks_levels(&self, lines: &[String], tanks: &mut [Tank]) -> Result<(), std::io::Error> {
+        for row in self.get_section(lines, "[TANKS]").iter() {
+            let Some(tnk) = tanks.iter_mut().find(|t| row[0] == t.id.to_string()) else {
+                continue;
+            };
+            if row.len() <= 3 {
+                log::warn!(
+                    "[TANKS] tank {} has no levels and no diameter : its level is fixed",
+                    tnk.id
+                );
+                continue;
+            }
+            let value = |k: usize| row.get(k).and_then(|v| v.parse::<f64>().ok());
+            match (value(3), value(4), value(5)) {
+                (Some(min_level), Some(max_level), Some(diameter)) if diameter >= 0.0 => {
+                    tnk.min_level = min_level;
+                    tnk.max_level = max_level;
+                    tnk.diameter = diameter;
+                }
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!(
+                            "[TANKS] tank {} : invalid or missing minimum level, maximum level or diameter",
+                            tnk.id
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Get the pipes FAVAD leakages ([LEAKAGE] section : pipe id, leak area in mm2 per 100 m,
+    /// leak expansion in mm2 per m of pressure per 100 m). Invalid rows are ignored with a warning.
+    ///
+    fn get_leakages(&self, lines: &[String]) -> Vec<(usize, PipeLeakage)> {
+        self.get_section(lines, "[LEAKAGE]")
+            .iter()
+            .filter_map(|row| {
+                let id = row[0].parse::<usize>().ok();
+                let area = row.get(1).and_then(|v| v.parse::<f64>().ok());
+                let expansion = match row.get(2) {
+                    None => Some(0.0),
+                    Some(v) => v.parse::<f64>().ok(),
+                };
+                match (id, area, expansion) {
+                    (Some(id), Some(area), Some(expansion)) if area >= 0.0 && expansion >= 0.0 => {
+                        Some((id, PipeLeakage::Favad { area, expansion }))
+                    }
+                    _ => {
+                        log::warn!("[LEAKAGE] invalid row {:?}, ignored", row.join(" "));
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    ///
+    /// Get the energy options and the pump efficiency curves ids ([ENERGY] section) :
+    /// GLOBAL EFFICIENCY, GLOBAL PRICE, DEMAND CHARGE and PUMP id EFFIC curve.
+    /// The price patterns (GLOBAL PATTERN, PUMP id PATTERN) and the pump prices (PUMP id PRICE)
+    /// are not supported : the costs follow a single tariff (see energy::Tariff). They are
+    /// ignored with a warning.
+    ///
+    fn get_energy(&self, lines: &[String]) -> (EnergyOptions, Vec<(usize, String)>) {
+        let mut energy = EnergyOptions::default();
+        let mut efficiency_curves: Vec<(usize, String)> = Vec::new();
+
+        for row in self.get_section(lines, "[ENERGY]").iter() {
+            let keyword = row[0].to_uppercase();
+            if keyword == "PUMP" && row.len() > 3 {
+                match (row[1].parse::<usize>(), row[2].to_uppercase().as_str()) {
+                    (Ok(pump_id), "EFFIC" | "EFFICIENCY") => {
+                        efficiency_curves.push((pump_id, row[3].to_string()))
+                    }
+                    _ => log::warn!("[ENERGY] {} is not supported, ignored", row.join(" ")),
+                }
+                continue;
+            }
+            if keyword == "GLOBAL" && row.len() > 2 && row[1].eq_ignore_ascii_case("PATTERN") {
+                log::warn!("[ENERGY] {} is not supported, ignored", row.join(" "));
+                continue;
+            }
+
+            let value: f64 = match row.last().map(|v| v.parse::<f64>()) {
+                Some(Ok(value)) => value,
+                _ => continue,
+            };
+            if keyword == "GLOBAL" && row.len() > 2 {
+                let param = row[1].to_uppercase();
+                if param.starts_with("EFFIC") {
+                    energy.global_efficiency = value;
+                } else if param == "PRICE" {
+                    energy.global_price = value;
+                }
+            } else if keyword == "DEMAND" {
+                energy.demand_charge = value;
+            }
+        }
+        (energy, efficiency_curves)
+    }
+
+    ///
+    /// Get the patterns ([PATTERNS] section). A pattern is named by its label ; a label which is
+    /// not a number gets the next free id.
+    ///
+    fn get_patterns(&self, lines: &[String]) -> Vec<Pattern> {
+        let mut patterns: Vec<Pattern> = Vec::new();
+
+        for row in self.get_section(lines, "[PATTERNS]").iter() {
+            let multipliers = row[1..].iter().filter_map(|v| v.parse::<f64>().ok());
+            match patterns
+                .iter_mut()
+                .find(|p| p.name.as_deref() == Some(row[0]))
+            {
+                Some(pattern) => pattern.multipliers.extend(multipliers),
+                None => {
+                    let mut pattern = Pattern::new(0, multipliers.collect());
+                    pattern.name = Some(row[0].to_string());
+                    patterns.push(pattern);
+                }
+            }
+        }
+
+        let mut next_id = patterns
+            .iter()
+            .filter_map(|p| p.name.as_deref()?.parse::<usize>().ok())
+            .max()
+            .unwrap_or(0);
+        for pattern in patterns.iter_mut() {
+            pattern.id = match pattern.name.as_deref().map(|n| n.parse::<usize>()) {
+                Some(Ok(id)) => id,
+                _ => {
+                    next_id += 1;
+                    next_id
+                }
+            };
+        }
+        patterns
+    }
+
+    ///
+    /// Get the times of the extended period simulation ([TIMES] section).
+    ///
+    fn get_times(&self, lines: &[String]) -> TimeOptions {
+        let mut times = TimeOptions::default();
+
+        for row in self.get_section(lines, "[TIMES]").iter() {
+            let keyword = row[0].to_uppercase();
+            let (param, value) = if keyword == "DURATION" {
+                (keyword, &row[1..])
+            } else if row.len() > 2 {
+                (format!("{} {}", keyword, row[1].to_uppercase()), &row[2..])
+            } else {
+                continue;
+            };
+            let Some(seconds) = parse_time(value) else {
+                continue;
+            };
+            match param.as_str() {
+                "DURATION" => times.duration = seconds,
+                "HYDRAULIC TIMESTEP" => times.hydraulic_step = seconds,
+                "PATTERN TIMESTEP" => times.pattern_step = seconds,
+                "PATTERN START" => times.pattern_start = seconds,
+                "START CLOCKTIME" => times.start_clocktime = seconds,
+                _ => {}
+            }
+        }
+        times
+    }
+
+    ///
+    /// Get the simple controls ([CONTROLS] section). Invalid controls and settings of pipes
+    /// (pipes have no setting) are ignored with a warning.
+    ///
+    fn get_controls(&self, lines: &[String], pipes: &Option<Vec<Pipe>>) -> Vec<Control> {
+        self.get_section(lines, "[CONTROLS]")
+            .iter()
+            .filter_map(|row| match row.join(" ").parse::<Control>() {
+                Ok(control) => Some(control),
+                Err(error) => {
+                    log::warn!("[CONTROLS] {}, ignored", error);
+                    None
+                }
+            })
+            .filter(|control| {
+                let valid = !Self::is_pipe_setting(&control.action, pipes);
+                if !valid {
+                    log::warn!(
+                        "[CONTROLS] pipe {} has no setting : the control is ignored",
+                        control.action.link
+                    );
+                }
+                valid
+            })
+            .collect()
+    }
+
+    fn is_pipe_setting(action: &LinkAction, pipes: &Option<Vec<Pipe>>) -> bool {
+        matches!(action.action, Action::Setting(_))
+            && pipes
+                .as_ref()
+                .is_some_and(|pipes| pipes.iter().any(|p| p.id == action.link))
+    }
+
+    ///
+    /// Get the rule-based controls ([RULES] section). Invalid rules and rules setting a pipe
+    /// (pipes have no setting) are ignored with a warning.
+    ///
+    fn get_rules(&self, lines: &[String], pipes: &Option<Vec<Pipe>>) -> Vec<Rule> {
+        let mut texts: Vec<String> = Vec::new();
+        for row in self.get_section(lines, "[RULES]").iter() {
+            if row[0].eq_ignore_ascii_case("RULE") || texts.is_empty() {
+                texts.push(String::new());
+            }
+            if let Some(text) = texts.last_mut() {
+                text.push_str(&row.join(" "));
+                text.push('\n');
+            }
+        }
+        texts
+            .iter()
+            .filter_map(|text| match text.parse::<Rule>() {
+                Ok(rule) => Some(rule),
+                Err(error) => {
+                    log::warn!("[RULES] {}, ignored", error);
+                    None
+                }
+            })
+            .filter(|rule| {
+                let valid = !rule
+                    .then_actions
+                    .iter()
+                    .chain(rule.else_actions.iter())
+                    .any(|action| Self::is_pipe_setting(action, pipes));
+                if !valid {
+                    log::warn!(
+                        "[RULES] rule {} sets a pipe, which has no setting : the rule is ignored",
+                        rule.id
+                    );
+                }
+                valid
+            })
+            .collect()
+    }
+
     fn get_coordinates(&self, lines: &Vec<String>) -> Option<Vec<(usize, Position)>> {
         let mut index = 0;
         let mut positions: Vec<(usize, Position)> = Vec::new();
@@ -652,3 +1119,151 @@ impl<'a> InpFileParser<'a> {
         Some(positions)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(|l| l.to_string()).collect()
+    }
+
+    fn pipes() -> Option<Vec<Pipe>> {
+        Some(vec![
+            PipeBuilder::new().set_id(1).set_start(1).set_end(2).build(),
+        ])
+    }
+
+    #[test]
+    fn reads_the_energy_options() {
+        let parser = InpFileParser::new("");
+        let text = lines(
+            "[ENERGY]\n\
+             Global Efficiency  70\n\
+             Global Price       0.15\n\
+             Global Pattern     2\n\
+             Demand Charge      4.5\n\
+             Pump 9 Efficiency  E1\n\
+             Pump 9 Price       0.2\n\
+             Pump 9 Pattern     3\n",
+        );
+        let (energy, efficiency_curves) = parser.get_energy(&text);
+        assert_eq!(
+            (
+                energy.global_efficiency,
+                energy.global_price,
+                energy.demand_charge
+            ),
+            (70.0, 0.15, 4.5)
+        );
+        assert_eq!(efficiency_curves, vec![(9, "E1".to_string())]);
+    }
+
+    #[test]
+    fn reads_the_tanks_levels() {
+        let parser = InpFileParser::new("");
+        let tanks = || vec![Tank::new(1, 100.0, 3.0), Tank::new(2, 100.0, 3.0)];
+        let text = lines(
+            "[TANKS]\n\
+             ;id  elevation  initial  min  max  diameter\n\
+             1    100        3        1    5    12.5\n\
+             2    100        3\n",
+        );
+        let mut nodes = tanks();
+        parser.set_tanks_levels(&text, &mut nodes).unwrap();
+        assert_eq!(
+            (nodes[0].min_level, nodes[0].max_level, nodes[0].diameter),
+            (1.0, 5.0, 12.5)
+        );
+        // fixed level
+        assert_eq!(
+            (nodes[1].min_level, nodes[1].max_level, nodes[1].diameter),
+            (0.0, 3.0, 0.0)
+        );
+
+        for row in [
+            "1 100 3 1 5",
+            "1 100 3 1",
+            "1 100 3 1 x 12.5",
+            "1 100 3 1 5 -2",
+        ] {
+            let text = lines(&format!("[TANKS]\n{}\n", row));
+            assert!(
+                parser.set_tanks_levels(&text, &mut tanks()).is_err(),
+                "{}",
+                row
+            );
+        }
+    }
+
+    #[test]
+    fn reads_the_pipes_leakages() {
+        let parser = InpFileParser::new("");
+        let text = lines(
+            "[LEAKAGE]\n\
+             ;pipe  area  expansion\n\
+             1      2.5   0.1\n\
+             2      1.5          ; no expansion\n\
+             3\n\
+             4      -1    0.1\n\
+             5      1.0   x\n\
+             \n\
+             [PIPES]\n\
+             6      1.0   0.1\n",
+        );
+        assert_eq!(
+            parser.get_leakages(&text),
+            vec![
+                (
+                    1,
+                    PipeLeakage::Favad {
+                        area: 2.5,
+                        expansion: 0.1
+                    }
+                ),
+                (
+                    2,
+                    PipeLeakage::Favad {
+                        area: 1.5,
+                        expansion: 0.0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_invalid_controls_and_the_settings_of_pipes() {
+        let parser = InpFileParser::new("");
+        let text = lines(
+            "[CONTROLS]\n\
+             LINK 1 CLOSED AT TIME 2\n\
+             LINK 1 0.5 AT TIME 3\n\
+             LINK 9 0.5 AT TIME 4\n\
+             LINK 1 OPEN WHEN\n\
+             \n\
+             [RULES]\n\
+             RULE 1\n\
+             IF SYSTEM TIME >= 2\n\
+             THEN LINK 1 STATUS IS CLOSED\n\
+             RULE 2\n\
+             IF SYSTEM TIME >= 3\n\
+             THEN LINK 9 SETTING IS 0.5\n\
+             ELSE LINK 1 SETTING IS 0.5\n\
+             RULE 3\n\
+             IF TANK 2 VOLUME ABOVE 3\n\
+             THEN LINK 1 STATUS IS OPEN\n",
+        );
+        let controls = parser.get_controls(&text, &pipes());
+        assert_eq!(controls.len(), 2);
+        assert_eq!(
+            controls[0].action.action,
+            Action::Status(LinkStatus::Closed)
+        );
+        assert_eq!(controls[1].action.link, 9);
+
+        let rules = parser.get_rules(&text, &pipes());
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].id, "1");
+    }
+}
@@ -0,0 +1,691 @@
+//!
+//! Extended period simulation : a sequence of steady state computations over the duration of
+//! the network times options. Between two computations, the demands and the reservoir heads
+//! follow their patterns and the tank levels are integrated from their net inflows.
+//! The simple and rule-based controls of the network change the links at each time.
+//!
+use std::fmt::Display;
+use std::sync::Arc;
+
+use crate::network::link::LinkStatus;
+use crate::network::{Action, Condition, LinkAction, LinkRef, Network, NodeRef, Variable};
+use crate::solver::{
+    HydraulicResults, LinkResult, Method, NodeResult, Solver, SolverError, Topology,
+};
+use crate::units::Quantity;
+
+///
+/// Number of computations of a time step closing the links which fill a full tank
+/// or drain an empty one.
+///
+const MAX_TANK_PASSES: usize = 4;
+
+///
+/// Tolerance on the tank levels limits (network length unit).
+///
+const LEVEL_EPSILON: f64 = 1.0e-6;
+
+///
+/// Number of computations of a time step after a change made by the controls.
+///
+const MAX_CONTROL_PASSES: usize = 4;
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+///
+/// Results of the computation at a time of the simulation. The pressure of a tank is its level.
+///
+#[derive(Debug, Clone)]
+pub struct TimeStep {
+    ///
+    /// Time (s) from the start of the simulation.
+    ///
+    pub time: u64,
+    pub results: HydraulicResults,
+}
+
+///
+/// A link changed by a control.
+///
+#[derive(Debug, Clone)]
+pub struct ControlEvent {
+    pub time: u64,
+    pub link: usize,
+    pub action: Action,
+
+    ///
+    /// The control ("LINK 9 OPEN IF NODE 2 BELOW 110") or the rule ("RULE 1 THEN") which fired.
+    ///
+    pub reason: String,
+}
+
+///
+/// Results of an extended period simulation, by increasing time.
+///
+#[derive(Debug, Clone, Default)]
+pub struct TimeSeries {
+    pub steps: Vec<TimeStep>,
+
+    ///
+    /// Changes made by the controls, by increasing time.
+    ///
+    pub events: Vec<ControlEvent>,
+}
+
+impl TimeSeries {
+    pub fn times(&self) -> Vec<u64> {
+        self.steps.iter().map(|s| s.time).collect()
+    }
+
+    ///
+    /// Results of a node (junction, tank or reservoir) at each time.
+    ///
+    pub fn node(&self, id: usize) -> Vec<(u64, &NodeResult)> {
+        self.steps
+            .iter()
+            .filter_map(|s| Some((s.time, s.results.node(id)?)))
+            .collect()
+    }
+
+    ///
+    /// Results of a link (pipe, pump or valve) at each time.
+    ///
+    pub fn link(&self, id: usize) -> Vec<(u64, &LinkResult)> {
+        self.steps
+            .iter()
+            .filter_map(|s| Some((s.time, s.results.link(id)?)))
+            .collect()
+    }
+}
+
+///
+/// Failure of the computation at a time of the simulation.
+///
+#[derive(Debug, Clone)]
+pub struct SimulationError {
+    pub time: u64,
+    pub error: SolverError,
+}
+
+impl Display for SimulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at {} s : {}", self.time, self.error)
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+pub struct Simulation<'a> {
+    network: &'a Network,
+    method: Method,
+}
+
+impl<'a> Simulation<'a> {
+    ///
+    /// The simulation works on a copy of the network : the network itself is not changed.
+    ///
+    pub fn new(network: &'a Network) -> Self {
+        Simulation {
+            network,
+            method: Method::default(),
+        }
+    }
+
+    ///
+    /// Method of the computations (see Solver::set_method).
+    ///
+    pub fn set_method(&mut self, method: Method) {
+        self.method = method;
+    }
+
+    ///
+    /// Run the simulation from the time 0 to Options::times.duration.
+    /// The topology of the network is analysed once : the controls only change link statuses and settings.
+    ///
+    pub fn run(&self) -> Result<TimeSeries, SimulationError> {
+        let mut wdn = self.network.clone();
+        let times = self.network.options.times;
+        let topology = Arc::new(Topology::analyse(&wdn));
+
+        let mut levels: Vec<f64> = wdn.tanks.as_ref().map_or(Vec::new(), |tanks| {
+            tanks.iter().map(|t| t.initial_level).collect()
+        });
+
+        let mut series = TimeSeries::default();
+        let mut time: u64 = 0;
+        loop {
+            self.apply_patterns(&mut wdn, time);
+            if let Some(tanks) = &mut wdn.tanks {
+                for (tnk, level) in tanks.iter_mut().zip(levels.iter()) {
+                    tnk.level = Some(*level);
+                }
+            }
+
+            // controls on times and tank levels, then on the computed pressures
+            self.apply_controls(&mut wdn, time, None, &mut series.events);
+            let (mut results, mut inflows) = self
+                .compute_step(&mut wdn, &topology)
+                .map_err(|error| SimulationError { time, error })?;
+            for _ in 0..MAX_CONTROL_PASSES {
+                if !self.apply_controls(&mut wdn, time, Some(&results), &mut series.events) {
+                    break;
+                }
+                (results, inflows) = self
+                    .compute_step(&mut wdn, &topology)
+                    .map_err(|error| SimulationError { time, error })?;
+            }
+            series.steps.push(TimeStep { time, results });
+
+            if time >= times.duration {
+                break;
+            }
+            let dt = self.step_length(&wdn, time, &levels, &inflows);
+            Self::integrate_levels(&wdn, &mut levels, &inflows, dt);
+            time += dt;
+        }
+        Ok(series)
+    }
+
+    ///
+    /// Demands and reservoir heads of the original network multiplied by their pattern at the time.
+    ///
+    fn apply_patterns(&self, wdn: &mut Network, time: u64) {
+        let times = &self.network.options.times;
+        if let (Some(junctions), Some(base)) = (&mut wdn.junctions, &self.network.junctions) {
+            for (jn, base_jn) in junctions.iter_mut().zip(base.iter()) {
+                let multiplier = self
+                    .network
+                    .demand_pattern(base_jn)
+                    .map_or(1.0, |p| p.multiplier_at(time, times));
+                jn.demand = base_jn.demand * multiplier;
+            }
+        }
+        if let (Some(reservoirs), Some(base)) = (&mut wdn.reservoirs, &self.network.reservoirs) {
+            for (rsrvr, base_rsrvr) in reservoirs.iter_mut().zip(base.iter()) {
+                let multiplier = self
+                    .network
+                    .head_pattern(base_rsrvr)
+                    .map_or(1.0, |p| p.multiplier_at(time, times));
+                rsrvr.head = base_rsrvr.head * multiplier;
+            }
+        }
+    }
+
+    ///
+    /// Apply the actions of the controls and rules which hold at the time. A link changed by
+    /// several of them takes the action of highest priority (simple controls : priority 0),
+    /// the first one for equal priorities. A control or a rule depending on an unknown value
+    /// (pressure without the results of a computation at the time) is skipped.
+    /// Returns true if a link is changed.
+    ///
+    fn apply_controls(
+        &self,
+        wdn: &mut Network,
+        time: u64,
+        results: Option<&HydraulicResults>,
+        events: &mut Vec<ControlEvent>,
+    ) -> bool {
+        let clocktime = (self.network.options.times.start_clocktime + time) % SECONDS_PER_DAY;
+        let value = |condition: &Condition| -> Option<f64> {
+            match condition.variable {
+                Variable::Time => Some(time as f64),
+                Variable::ClockTime => Some(clocktime as f64),
+                Variable::Node(id) => match wdn.index().node(id)? {
+                    NodeRef::Junction(_) => results?.pressure(id),
+                    NodeRef::Tank(i) => {
+                        let tnk = wdn.tanks.as_ref()?.get(i)?;
+                        Some(tnk.level.unwrap_or(tnk.initial_level))
+                    }
+                    NodeRef::Reservoir(_) => None,
+                },
+            }
+        };
+
+        // (priority, action, reason)
+        let mut candidates: Vec<(f64, LinkAction, String)> = Vec::new();
+        if let Some(controls) = &self.network.controls {
+            for control in controls.iter() {
+                if value(&control.condition).is_some_and(|v| control.condition.holds(v)) {
+                    candidates.push((0.0, control.action, control.to_string()));
+                }
+            }
+        }
+        if let Some(rules) = &self.network.rules {
+            for rule in rules.iter() {
+                if rule.premises.iter().any(|p| value(&p.condition).is_none()) {
+                    continue;
+                }
+                let (actions, clause) = if rule.evaluate(|c| value(c).is_some_and(|v| c.holds(v))) {
+                    (&rule.then_actions, "THEN")
+                } else {
+                    (&rule.else_actions, "ELSE")
+                };
+                candidates.extend(
+                    actions
+                        .iter()
+                        .map(|a| (rule.priority, *a, format!("RULE {} {}", rule.id, clause))),
+                );
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut changed = false;
+        let mut done: Vec<usize> = Vec::new();
+        for (_, action, reason) in candidates {
+            if done.contains(&action.link) {
+                continue;
+            }
+            done.push(action.link);
+            if Self::apply_action(wdn, &action).unwrap_or(false) {
+                events.push(ControlEvent {
+                    time,
+                    link: action.link,
+                    action: action.action,
+                    reason,
+                });
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    ///
+    /// Change a link. A setting is the relative speed of a pump (a null speed closes the pump)
+    /// or the setting of a valve (see `Valve::apply_setting`) : pipes have no setting.
+    /// Returns true if the link is changed.
+    ///
+    fn apply_action(wdn: &mut Network, action: &LinkAction) -> Option<bool> {
+        let setting = match action.action {
+            Action::Status(status) => {
+                return Some(Self::set_link_status(wdn, action.link, status)? != status);
+            }
+            Action::Setting(v) => v,
+        };
+        let status = if setting > 0.0 {
+            LinkStatus::Open
+        } else {
+            LinkStatus::Closed
+        };
+        match wdn.index().link(action.link)? {
+            LinkRef::Pipe(_) => {
+                log::warn!(
+                    "pipe {} has no setting : the setting is ignored",
+                    action.link
+                );
+                Some(false)
+            }
+            LinkRef::Pump(k) => {
+                let pump = wdn.pumps.as_mut()?.get_mut(k)?;
+                let changed = pump.speed != setting || pump.status != status;
+                pump.set_speed(setting);
+                Some(changed)
+            }
+            LinkRef::Valve(k) => {
+                let valve = wdn.valves.as_mut()?.get_mut(k)?;
+                Some(valve.apply_setting(setting))
+            }
+        }
+    }
+
+    fn is_in_effect(wdn: &Network, action: &LinkAction) -> bool {
+        let (status, setting) = match wdn.index().link(action.link) {
+            // a pipe setting has no effect
+            Some(LinkRef::Pipe(_)) if matches!(action.action, Action::Setting(_)) => return true,
+            Some(LinkRef::Pipe(k)) => (
+                wdn.pipes.as_ref().and_then(|p| p.get(k)).map(|p| p.status),
+                None,
+            ),
+            Some(LinkRef::Pump(k)) => {
+                let pump = wdn.pumps.as_ref().and_then(|p| p.get(k));
+                (pump.map(|p| p.status), pump.map(|p| p.speed))
+            }
+            Some(LinkRef::Valve(k)) => {
+                let valve = wdn.valves.as_ref().and_then(|v| v.get(k));
+                if let (Some(valve), Action::Setting(_)) = (valve, action.action)
+                    && !valve.has_loss_setting()
+                {
+                    return valve.status == LinkStatus::Open;
+                }
+                (valve.map(|v| v.status), valve.map(|v| v.k_value))
+            }
+            None => return true,
+        };
+        match action.action {
+            Action::Status(s) => status == Some(s),
+            Action::Setting(v) => setting == Some(v),
+        }
+    }
+
+    ///
+    /// Solve the network at the time. The links filling a full tank or draining an empty tank
+    /// are closed for this time only, and the network is solved again.
+    /// Returns the results and the net inflows of the tanks.
+    ///
+    fn compute_step(
+        &self,
+        wdn: &mut Network,
+        topology: &Arc<Topology>,
+    ) -> Result<(HydraulicResults, Vec<f64>), SolverError> {
+        let mut closed: Vec<(usize, LinkStatus)> = Vec::new();
+        let mut pass = 0;
+        let results = loop {
+            let mut solver = Solver::with_topology(wdn, None, Arc::clone(topology));
+            solver.set_method(self.method);
+            let results = match solver.compute() {
+                Ok(results) => results,
+                Err(error) => {
+                    Self::restore_statuses(wdn, &closed);
+                    return Err(error);
+                }
+            };
+
+            pass += 1;
+            let blocking = Self::blocking_links(wdn, &results);
+            if blocking.is_empty() || pass >= MAX_TANK_PASSES {
+                break results;
+            }
+            for id in blocking {
+                if let Some(status) = Self::set_link_status(wdn, id, LinkStatus::Closed) {
+                    closed.push((id, status));
+                }
+            }
+        };
+
+        let inflows = Self::tank_inflows(wdn, &results);
+        Self::restore_statuses(wdn, &closed);
+        Ok((results, inflows))
+    }
+
+    ///
+    /// Open links with a flow into a full tank or out of an empty tank.
+    ///
+    fn blocking_links(wdn: &Network, results: &HydraulicResults) -> Vec<usize> {
+        let mut links: Vec<usize> = Vec::new();
+        let Some(tanks) = &wdn.tanks else {
+            return links;
+        };
+        for tnk in tanks.iter().filter(|t| t.diameter > 0.0) {
+            let level = tnk.level.unwrap_or(tnk.initial_level);
+            let full = level >= tnk.max_level - LEVEL_EPSILON;
+            let empty = level <= tnk.min_level + LEVEL_EPSILON;
+            if !full && !empty {
+                continue;
+            }
+            for id in wdn.links_of(tnk.id).iter() {
+                let Some(inflow) = Self::link_inflow(wdn, results, *id, tnk.id) else {
+                    continue;
+                };
+                if (full && inflow > 0.0) || (empty && inflow < 0.0) {
+                    links.push(*id);
+                }
+            }
+        }
+        links
+    }
+
+    ///
+    /// Flow of a link into one of its end nodes (None for a closed link).
+    ///
+    fn link_inflow(
+        wdn: &Network,
+        results: &HydraulicResults,
+        link_id: usize,
+        node_id: usize,
+    ) -> Option<f64> {
+        let LinkResult { flow, status, .. } = results.link(link_id)?;
+        if *status == LinkStatus::Closed {
+            return None;
+        }
+        let link = wdn.link(link_id)?;
+        if link.get_end_node() == node_id {
+            Some(*flow)
+        } else if link.get_start_node() == node_id {
+            Some(-flow)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Set the status of a link, returning its previous status.
+    ///
+    fn set_link_status(
+        wdn: &mut Network,
+        link_id: usize,
+        status: LinkStatus,
+    ) -> Option<LinkStatus> {
+        let previous = match wdn.index().link(link_id)? {
+            LinkRef::Pipe(k) => &mut wdn.pipes.as_mut()?.get_mut(k)?.status,
+            LinkRef::Pump(k) => &mut wdn.pumps.as_mut()?.get_mut(k)?.status,
+            LinkRef::Valve(k) => &mut wdn.valves.as_mut()?.get_mut(k)?.status,
+        };
+        Some(std::mem::replace(previous, status))
+    }
+
+    fn restore_statuses(wdn: &mut Network, closed: &[(usize, LinkStatus)]) {
+        for (id, status) in closed.iter().rev() {
+            Self::set_link_status(wdn, *id, *status);
+        }
+    }
+
+    ///
+    /// Net inflows (m3/s) of the tanks.
+    ///
+    fn tank_inflows(wdn: &Network, results: &HydraulicResults) -> Vec<f64> {
+        let units = wdn.options.units();
+        wdn.tanks.as_ref().map_or(Vec::new(), |tanks| {
+            tanks
+                .iter()
+                .map(|tnk| {
+                    let inflow: f64 = wdn
+                        .links_of(tnk.id)
+                        .iter()
+                        .filter_map(|id| Self::link_inflow(wdn, results, *id, tnk.id))
+                        .sum();
+                    units.to_si(Quantity::Flow, inflow)
+                })
+                .collect()
+        })
+    }
+
+    ///
+    /// Length (s) of the step from the time : up to the next hydraulic step, the next pattern
+    /// step, the end of the simulation, the filling or emptying of a tank, or the next time
+    /// a control may fire (control time, tank level of a control).
+    ///
+    fn step_length(&self, wdn: &Network, time: u64, levels: &[f64], inflows: &[f64]) -> u64 {
+        let times = &self.network.options.times;
+        let hydraulic_step = u64::max(times.hydraulic_step, 1);
+        let pattern_step = u64::max(times.pattern_step, 1);
+
+        let next_hydraulic = (time / hydraulic_step + 1) * hydraulic_step;
+        let next_pattern =
+            ((time + times.pattern_start) / pattern_step + 1) * pattern_step - times.pattern_start;
+        let mut dt = [next_hydraulic, next_pattern, times.duration]
+            .into_iter()
+            .min()
+            .unwrap_or(times.duration)
+            .saturating_sub(time);
+
+        if let Some(tanks) = &wdn.tanks {
+            for ((tnk, level), inflow) in tanks.iter().zip(levels.iter()).zip(inflows.iter()) {
+                if tnk.diameter <= 0.0 || *inflow == 0.0 {
+                    continue;
+                }
+                let room = if *inflow > 0.0 {
+                    tnk.max_level - level
+                } else {
+                    level - tnk.min_level
+                };
+                if room <= LEVEL_EPSILON {
+                    continue;
+                }
+                let volume = tnk.area() * tnk.units().to_si(Quantity::Length, room);
+                let seconds = (volume / inflow.abs()).ceil();
+                if seconds < dt as f64 {
+                    dt = seconds as u64;
+                }
+            }
+        }
+        if let Some(seconds) = self.next_control_time(wdn, time, levels, inflows) {
+            dt = u64::min(dt, seconds);
+        }
+        u64::max(dt, 1)
+    }
+
+    ///
+    /// Time (s) from the time to the next change of state of a control condition : time and
+    /// clock time of the conditions, time for a tank to reach the level of a condition.
+    /// The simple controls whose action is already in effect are ignored.
+    ///
+    fn next_control_time(
+        &self,
+        wdn: &Network,
+        time: u64,
+        levels: &[f64],
+        inflows: &[f64],
+    ) -> Option<u64> {
+        let mut conditions: Vec<&Condition> = Vec::new();
+        if let Some(controls) = &self.network.controls {
+            conditions.extend(
+                controls
+                    .iter()
+                    .filter(|c| !Self::is_in_effect(wdn, &c.action))
+                    .map(|c| &c.condition),
+            );
+        }
+        if let Some(rules) = &self.network.rules {
+            conditions.extend(
+                rules
+                    .iter()
+                    .flat_map(|r| r.premises.iter().map(|p| &p.condition)),
+            );
+        }
+
+        let clocktime = (self.network.options.times.start_clocktime + time) % SECONDS_PER_DAY;
+        conditions
+            .into_iter()
+            .filter_map(|condition| match condition.variable {
+                Variable::Time => {
+                    let at = condition.value.max(0.0) as u64;
+                    (at > time).then(|| at - time)
+                }
+                Variable::ClockTime => {
+                    let at = condition.value.max(0.0) as u64 % SECONDS_PER_DAY;
+                    let seconds = (at + SECONDS_PER_DAY - clocktime) % SECONDS_PER_DAY;
+                    Some(if seconds == 0 {
+                        SECONDS_PER_DAY
+                    } else {
+                        seconds
+                    })
+                }
+                Variable::Node(id) => {
+                    let NodeRef::Tank(i) = wdn.index().node(id)? else {
+                        return None;
+                    };
+                    let tnk = wdn.tanks.as_ref()?.get(i)?;
+                    let (level, inflow) = (*levels.get(i)?, *inflows.get(i)?);
+                    let rise = condition.value - level;
+                    if tnk.diameter <= 0.0 || rise * inflow <= 0.0 {
+                        return None;
+                    }
+                    let volume = tnk.area() * tnk.units().to_si(Quantity::Length, rise.abs());
+                    // just beyond the level of the condition
+                    Some((volume / inflow.abs()).floor() as u64 + 1)
+                }
+            })
+            .min()
+    }
+
+    ///
+    /// Tank levels after dt seconds of the inflows, bounded to the tank levels limits.
+    ///
+    fn integrate_levels(wdn: &Network, levels: &mut [f64], inflows: &[f64], dt: u64) {
+        let Some(tanks) = &wdn.tanks else {
+            return;
+        };
+        for ((tnk, level), inflow) in tanks.iter().zip(levels.iter_mut()).zip(inflows.iter()) {
+            if tnk.diameter <= 0.0 {
+                continue;
+            }
+            let rise = tnk
+                .units()
+                .from_si(Quantity::Length, inflow * dt as f64 / tnk.area());
+            *level = (*level + rise).clamp(tnk.min_level, tnk.max_level);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::link::pipe::PipeBuilder;
+    use crate::network::{
+        FlowUnits, JunctionBuilder, NetworkBuilder, OptionsBuilder, Pattern, TankBuilder,
+        TimeOptions,
+    };
+
+    #[test]
+    fn fills_and_drains_a_tank() {
+        // the tank supplies the junction alone : its net inflow is minus the junction demand
+        let options = OptionsBuilder::new()
+            .set_flow_unit(FlowUnits::Lps)
+            .set_times(TimeOptions {
+                duration: 4 * 3600,
+                ..TimeOptions::default()
+            })
+            .build();
+        let network = NetworkBuilder::new()
+            .set_options(options)
+            .set_tanks(Some(vec![
+                TankBuilder::new()
+                    .set_id(10)
+                    .set_elevation(100.0)
+                    .set_initial_level(2.0)
+                    .set_levels(0.5, 5.0)
+                    .set_diameter(10.0)
+                    .build(),
+            ]))
+            .set_junctions(Some(vec![
+                JunctionBuilder::new()
+                    .set_id(1)
+                    .set_elevation(80.0)
+                    .set_demand(10.0)
+                    .set_pattern(Some(1))
+                    .build(),
+            ]))
+            .set_pipes(Some(vec![
+                PipeBuilder::new()
+                    .set_id(1)
+                    .set_start(10)
+                    .set_end(1)
+                    .set_length(500.0)
+                    .set_diameter(200.0)
+                    .set_roughness(100.0)
+                    .build(),
+            ]))
+            .set_patterns(Some(vec![Pattern::new(1, vec![-1.0, -0.5, 1.0, 0.5])]))
+            .build();
+
+        let series = Simulation::new(&network).run().unwrap();
+        assert_eq!(series.times(), vec![0, 3600, 7200, 10800, 14400]);
+
+        // volumes (m3) : 10 l/s during 1 h is 36 m3
+        let area = std::f64::consts::PI * 10.0 * 10.0 / 4.0;
+        let volumes = [
+            2.0 * area,
+            2.0 * area + 36.0,
+            2.0 * area + 54.0,
+            2.0 * area + 18.0,
+            2.0 * area,
+        ];
+        for ((time, tank), volume) in series.node(10).iter().zip(volumes) {
+            assert!(
+                (tank.pressure - volume / area).abs() < 1e-9,
+                "level {} at {} s",
+                tank.pressure,
+                time
+            );
+        }
+    }
+}
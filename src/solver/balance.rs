@@ -0,0 +1,223 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::{LinkResult, NodeResult};
+use crate::network::FlowUnits;
+use crate::network::node::NodeType;
+
+///
+/// Flow supplied to the network by a reservoir or a tank (negative when the network fills it).
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeSupply {
+    pub id: usize,
+    pub node_type: NodeType,
+    pub flow: f64,
+}
+
+///
+/// Water balance of a computation, in the network flow unit :
+/// total supply = total demand + emitter outflow + leakage outflow + continuity error.
+///
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MassBalance {
+    pub flow_unit: FlowUnits,
+
+    ///
+    /// Supply of the reservoirs then of the tanks.
+    ///
+    pub supplies: Vec<NodeSupply>,
+    pub total_supply: f64,
+
+    ///
+    /// Delivered demands of the junctions.
+    ///
+    pub total_demand: f64,
+    pub emitter_outflow: f64,
+    pub leakage_outflow: f64,
+
+    ///
+    /// Net inflow of the tanks (positive when they fill).
+    ///
+    pub storage_change: f64,
+
+    ///
+    /// Total supply minus the outflows of the junctions.
+    ///
+    pub continuity_error: f64,
+
+    ///
+    /// Largest flow imbalance of a junction (inflow minus outflows) and the junction id.
+    ///
+    pub max_imbalance: f64,
+    pub max_imbalance_node: Option<usize>,
+}
+
+impl MassBalance {
+    ///
+    /// Balance of the computed nodes and links. The imbalances are those of the junctions,
+    /// in the order of the junction nodes.
+    ///
+    pub fn new(
+        flow_unit: FlowUnits,
+        nodes: &[NodeResult],
+        links: &[LinkResult],
+        imbalances: &[f64],
+    ) -> Self {
+        let mut supplies: Vec<NodeSupply> = Vec::new();
+        for node_type in [NodeType::Reservoir, NodeType::Tank] {
+            supplies.extend(nodes.iter().filter(|n| n.node_type == node_type).map(|n| {
+                NodeSupply {
+                    id: n.id,
+                    node_type: n.node_type.clone(),
+                    flow: -n.demand,
+                }
+            }));
+        }
+        let total_supply = supplies.iter().fold(0.0f64, |acc, s| acc + s.flow);
+        let storage_change = supplies
+            .iter()
+            .filter(|s| s.node_type == NodeType::Tank)
+            .fold(0.0f64, |acc, s| acc - s.flow);
+
+        let junctions = || nodes.iter().filter(|n| n.node_type == NodeType::Junction);
+        let total_demand = junctions().fold(0.0f64, |acc, n| acc + n.demand);
+        let emitter_outflow = junctions().fold(0.0f64, |acc, n| acc + n.emitter_flow);
+        let leakage_outflow = links.iter().fold(0.0f64, |acc, l| acc + l.leakage_flow);
+
+        let (max_imbalance, max_imbalance_node) = junctions()
+            .zip(imbalances.iter())
+            .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+            .map_or((0.0, None), |(n, imbalance)| (*imbalance, Some(n.id)));
+
+        MassBalance {
+            flow_unit,
+            supplies,
+            total_supply,
+            total_demand,
+            emitter_outflow,
+            leakage_outflow,
+            storage_change,
+            continuity_error: total_supply - total_demand - emitter_outflow - leakage_outflow,
+            max_imbalance,
+            max_imbalance_node,
+        }
+    }
+
+    ///
+    /// Continuity error relative to the total supply (0 without supply).
+    ///
+    pub fn relative_error(&self) -> f64 {
+        if self.total_supply.abs() > 0.0 {
+            self.continuity_error / self.total_supply
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Display for MassBalance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Mass balance (flow unit: {})", self.flow_unit)?;
+        writeln!(f, "{:>8} {:>10} {:>14}", "Node", "Type", "Supply")?;
+        for s in self.supplies.iter() {
+            writeln!(
+                f,
+                "{:>8} {:>10} {:>14.4}",
+                s.id,
+                format!("{:?}", s.node_type),
+                s.flow
+            )?;
+        }
+        writeln!(f, "Total supply: {:.4}", self.total_supply)?;
+        writeln!(f, "Total demand: {:.4}", self.total_demand)?;
+        writeln!(f, "Emitter outflow: {:.4}", self.emitter_outflow)?;
+        writeln!(f, "Leakage outflow: {:.4}", self.leakage_outflow)?;
+        writeln!(f, "Tank storage change: {:.4}", self.storage_change)?;
+        writeln!(
+            f,
+            "Continuity error: {:.3e} ({:.3e} %)",
+            self.continuity_error,
+            self.relative_error() * 100.0
+        )?;
+        match self.max_imbalance_node {
+            Some(id) => write!(
+                f,
+                "Largest nodal imbalance: {:.3e} at node {}",
+                self.max_imbalance, id
+            ),
+            None => write!(f, "Largest nodal imbalance: none"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::link::{LinkStatus, LinkType};
+
+    fn node(id: usize, node_type: NodeType, demand: f64, emitter_flow: f64) -> NodeResult {
+        NodeResult {
+            id,
+            node_type,
+            head: 0.0,
+            pressure: 0.0,
+            demand,
+            emitter_flow,
+            disconnected: false,
+        }
+    }
+
+    fn pipe(id: usize, flow: f64, leakage_flow: f64) -> LinkResult {
+        LinkResult {
+            id,
+            link_type: LinkType::Pipe,
+            flow,
+            velocity: 0.0,
+            headloss: 0.0,
+            unit_headloss: 0.0,
+            friction_factor: 0.0,
+            status: LinkStatus::Open,
+            leakage_flow,
+        }
+    }
+
+    #[test]
+    fn balances_supplies_and_outflows() {
+        let nodes = [
+            node(1, NodeType::Junction, 5.0, 0.5),
+            node(2, NodeType::Junction, 1.5, 0.0),
+            node(3, NodeType::Tank, 2.0, 0.0),
+            node(4, NodeType::Reservoir, -10.0, 0.0),
+        ];
+        let links = [pipe(1, 10.0, 0.5), pipe(2, 4.0, 0.3)];
+        let balance = MassBalance::new(FlowUnits::Lps, &nodes, &links, &[0.01, -0.03]);
+
+        let supplies: Vec<(usize, f64)> = balance.supplies.iter().map(|s| (s.id, s.flow)).collect();
+        assert_eq!(supplies, vec![(4, 10.0), (3, -2.0)]);
+        assert_eq!(balance.total_supply, 8.0);
+        assert_eq!(balance.storage_change, 2.0);
+        assert_eq!(balance.total_demand, 6.5);
+        assert_eq!(balance.emitter_outflow, 0.5);
+        assert_eq!(balance.leakage_outflow, 0.8);
+        assert!((balance.continuity_error - 0.2).abs() < 1e-12);
+        assert!((balance.relative_error() - 0.025).abs() < 1e-12);
+        assert_eq!(balance.max_imbalance, -0.03);
+        assert_eq!(balance.max_imbalance_node, Some(2));
+    }
+
+    #[test]
+    fn balances_a_network_without_supply() {
+        let nodes = [node(1, NodeType::Junction, 0.0, 0.0)];
+        let balance = MassBalance::new(FlowUnits::Lps, &nodes, &[], &[]);
+        assert_eq!(balance.total_supply, 0.0);
+        assert_eq!(balance.relative_error(), 0.0);
+        assert_eq!(balance.max_imbalance_node, None);
+        assert!(
+            balance
+                .to_string()
+                .ends_with("Largest nodal imbalance: none")
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+
+use super::{HydraulicResults, LinkEnd, Solver};
+use crate::network::link::LinkStatus;
+use crate::units::Quantity;
+
+///
+/// Flows of the links at the start of a computation.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum InitialFlows {
+    ///
+    /// Total demand Qmax in every link.
+    ///
+    #[default]
+    TotalDemand,
+
+    ///
+    /// Given flows of the links (pipes, pumps then valves) in the network flow unit, as the flows
+    /// of a previous computation of the same network (see From<&HydraulicResults>).
+    /// Falls back to TotalDemand when there is not one flow per link.
+    ///
+    Flows(Vec<f64>),
+
+    ///
+    /// Demands allocated along a spanning tree grown from the tanks and reservoirs : each tree link
+    /// carries the demand of the junctions it supplies, the other links carry Qmax / m.
+    /// Pumps and check valve pipes are only followed from their start node.
+    ///
+    SpanningTree,
+
+    ///
+    /// Flow of the given velocity (network velocity unit, m/s or ft/s) in the pipes and valves,
+    /// half of the maximal flow of the pump curves (Qmax for constant power pumps).
+    ///
+    Velocity(f64),
+}
+
+impl From<&HydraulicResults> for InitialFlows {
+    fn from(results: &HydraulicResults) -> Self {
+        InitialFlows::Flows(results.links.iter().map(|link| link.flow).collect())
+    }
+}
+
+impl Solver<'_> {
+    ///
+    /// Initial flows (m3/s) of the links from the demands (m3/s) of the junctions.
+    ///
+    pub(super) fn initial_link_flows(&self, demands: &[f64], qmax: f64) -> Vec<f64> {
+        let np = self.topology.links.len();
+        match &self.initial_flows {
+            InitialFlows::Flows(flows) if flows.len() == np => flows
+                .iter()
+                .map(|flow| flow * self.flow_unit_multiplayer)
+                .collect(),
+            InitialFlows::Flows(flows) => {
+                log::warn!(
+                    "{} initial flows for {} links : the total demand is used",
+                    flows.len(),
+                    np
+                );
+                vec![qmax; np]
+            }
+            InitialFlows::TotalDemand => vec![qmax; np],
+            InitialFlows::SpanningTree => self.spanning_tree_flows(demands, qmax),
+            InitialFlows::Velocity(velocity) => self.velocity_flows(*velocity, qmax),
+        }
+    }
+
+    ///
+    /// Demands allocated along a breadth-first spanning tree of the open links from the fixed head nodes.
+    ///
+    fn spanning_tree_flows(&self, demands: &[f64], qmax: f64) -> Vec<f64> {
+        let nn = self.junction_count;
+        let links = &self.topology.links;
+        let node_of = |end: LinkEnd| match end {
+            LinkEnd::Junction(i) => Some(i),
+            LinkEnd::FixedHead(j) => Some(nn + j),
+            LinkEnd::Missing => None,
+        };
+        let one_way = self.one_way_links();
+
+        // adjacency : (link, other node, link followed from its start node)
+        let mut adjacency: Vec<Vec<(usize, usize, bool)>> =
+            vec![Vec::new(); nn + self.tank_count + self.reservoir_count];
+        for (k, ends) in links.iter().enumerate() {
+            if self.link_status[k] == LinkStatus::Closed {
+                continue;
+            }
+            if let (Some(start), Some(end)) = (node_of(ends.0), node_of(ends.1))
+                && start != end
+            {
+                adjacency[start].push((k, end, true));
+                if !one_way[k] {
+                    adjacency[end].push((k, start, false));
+                }
+            }
+        }
+
+        // tree link of each junction (link, from its start node) in the order of the traversal
+        let mut visited = vec![false; adjacency.len()];
+        let mut parent: Vec<Option<(usize, bool)>> = vec![None; nn];
+        let mut order: Vec<usize> = Vec::with_capacity(nn);
+        let mut queue: VecDeque<usize> = (nn..adjacency.len()).collect();
+        visited[nn..].fill(true);
+        while let Some(node) = queue.pop_front() {
+            for &(k, next, forward) in &adjacency[node] {
+                if !visited[next] {
+                    visited[next] = true;
+                    parent[next] = Some((k, forward));
+                    order.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut flows = vec![0.0f64; links.len()];
+        for (k, flow) in flows.iter_mut().enumerate() {
+            if self.link_status[k] == LinkStatus::Open {
+                *flow = qmax / self.m;
+            }
+        }
+        // demand supplied through the tree link of each junction, from the leaves
+        let mut supplied: Vec<f64> = demands.to_vec();
+        for &i in order.iter().rev() {
+            if let Some((k, forward)) = parent[i] {
+                flows[k] = if forward { supplied[i] } else { -supplied[i] };
+                let upstream = if forward { links[k].0 } else { links[k].1 };
+                if let LinkEnd::Junction(j) = upstream {
+                    supplied[j] += supplied[i];
+                }
+            }
+        }
+        flows
+    }
+
+    ///
+    /// Flows of the given velocity (network unit) in the open pipes and valves, pumps at half of
+    /// their maximal flow.
+    ///
+    fn velocity_flows(&self, velocity: f64, qmax: f64) -> Vec<f64> {
+        let velocity = self.units.to_si(Quantity::Velocity, velocity);
+        let diameter_factor = self.units.factor(Quantity::Diameter);
+        let area = |diameter: f64| {
+            let d = diameter * diameter_factor;
+            std::f64::consts::PI * d * d / 4.0
+        };
+
+        let mut flows: Vec<f64> = Vec::with_capacity(self.topology.links.len());
+        if let Some(pipes) = &self.network.pipes {
+            flows.extend(pipes.iter().map(|p| velocity * area(p.diameter)));
+        }
+        if let Some(pumps) = &self.network.pumps {
+            flows.extend(pumps.iter().map(|pump| {
+                if pump.alpha == 0.0 {
+                    return qmax;
+                }
+                // head gain x q^2 + y q + z = 0 at the maximal flow of the curve
+                let (x, y, z) = self.pump_curve_si(pump);
+                let root = (-y - f64::sqrt(f64::max(y * y - 4.0 * x * z, 0.0))) / (2.0 * x);
+                if root.is_finite() && root > 0.0 {
+                    root / 2.0
+                } else {
+                    qmax
+                }
+            }));
+        }
+        if let Some(valves) = &self.network.valves {
+            flows.extend(valves.iter().map(|v| velocity * area(v.diameter)));
+        }
+
+        for (flow, status) in flows.iter_mut().zip(self.link_status.iter()) {
+            if *status == LinkStatus::Closed {
+                *flow = 0.0;
+            }
+        }
+        flows
+    }
+
+    ///
+    /// Links that only carry flow from their start node : pumps and check valve pipes.
+    ///
+    fn one_way_links(&self) -> Vec<bool> {
+        let mut one_way: Vec<bool> = Vec::with_capacity(self.topology.links.len());
+        if let Some(pipes) = &self.network.pipes {
+            one_way.extend(pipes.iter().map(|p| p.check_valve));
+        }
+        one_way.extend(std::iter::repeat_n(true, self.pump_count));
+        one_way.extend(std::iter::repeat_n(false, self.valve_count));
+        one_way
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::link::pipe::PipeBuilder;
+    use crate::network::{JunctionBuilder, Network, NetworkBuilder, ReservoirBuilder};
+
+    ///
+    /// Reservoir 100 supplying the junctions 1 to 4 through the pipes (id, start, end, check valve).
+    ///
+    fn network(pipes: &[(usize, usize, usize, bool)]) -> Network {
+        let junctions = (1..=4)
+            .map(|id| JunctionBuilder::new().set_id(id).build())
+            .collect();
+        let reservoir = ReservoirBuilder::new().set_id(100).set_head(50.0).build();
+        let pipes = pipes
+            .iter()
+            .map(|&(id, start, end, check_valve)| {
+                PipeBuilder::new()
+                    .set_id(id)
+                    .set_start(start)
+                    .set_end(end)
+                    .set_length(100.0)
+                    .set_diameter(200.0)
+                    .set_roughness(100.0)
+                    .set_status(LinkStatus::Open)
+                    .set_check_valve(check_valve)
+                    .build()
+            })
+            .collect();
+        NetworkBuilder::new()
+            .set_junctions(Some(junctions))
+            .set_reservoirs(Some(vec![reservoir]))
+            .set_pipes(Some(pipes))
+            .build()
+    }
+
+    fn spanning_tree_flows(network: &Network, demands: &[f64], qmax: f64) -> Vec<f64> {
+        let mut solver = Solver::new(network, None);
+        solver.set_initial_flows(InitialFlows::SpanningTree);
+        solver.link_status = solver.initial_link_statuses();
+        solver.initial_link_flows(demands, qmax)
+    }
+
+    #[test]
+    fn allocates_the_demands_along_a_tree() {
+        // the pipe 3 is drawn from the junction 3 to the junction 2
+        let wdn = network(&[
+            (1, 100, 1, false),
+            (2, 1, 2, false),
+            (3, 3, 2, false),
+            (4, 1, 4, false),
+        ]);
+        let flows = spanning_tree_flows(&wdn, &[1.0, 2.0, 3.0, 4.0], 10.0);
+        assert_eq!(flows, vec![10.0, 5.0, -3.0, 4.0]);
+    }
+
+    #[test]
+    fn follows_a_check_valve_from_its_start_only() {
+        // the check valve 4 can not supply the junction 4 from the junction 1 : the junction 4 is
+        // supplied by the pipe 5 and the pipe 4 is a chord
+        let wdn = network(&[
+            (1, 100, 1, false),
+            (2, 1, 2, false),
+            (3, 3, 2, false),
+            (4, 4, 1, true),
+            (5, 3, 4, false),
+        ]);
+        let flows = spanning_tree_flows(&wdn, &[1.0, 2.0, 3.0, 4.0], 10.0);
+        assert_eq!(flows, vec![10.0, 9.0, -7.0, 0.1, 4.0]);
+    }
+}
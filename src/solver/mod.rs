@@ -0,0 +1,1928 @@
+// Moosavian N, 2017. Multilinear method for hydraulic analysis of pipe networks.
+// Journal of Irrigation and Drainage Engineering. Volume 143, number 8, pages={04017020, 2017,
+// publisher: American Society of Civil Engineers.
+// Todini E, Pilati S, 1988. A gradient algorithm for the analysis of pipe networks.
+// Computer Applications in Water Supply, Volume 1, pages 1-20. (Method::Newton)
+//***********************************************************************************************
+// Developped by : Saad Dahmani <sd.dahmani2000@gmail.com; s.dahmani@univ-bouira.dz>
+//***********************************************************************************************
+
+// no : number of fixed head nodes (tanks + reservoirs)
+// self.junction_count : number of node - no (exclude fixed head)
+// np : number of links = pipe + pumps + valves
+// a21 : incidence matrix (self.junction_count x np), a12 = transpose(a21), a10 (np x no) :
+// stored as the ends of each link (see LinkEnd). A11 (np x np) is diagonal.
+// V = A21 x A11^-1 x A12 is assembled as a sparse symmetric matrix and solved by LDLt factorization.
+
+mod balance;
+mod initial;
+mod observer;
+mod report;
+mod results;
+mod session;
+mod sparse;
+mod topology;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::units::{Quantity, Units};
+
+// use super::network::node::*;
+//use super::network::link::{pipe::Pipe, pump::Pump, valve::Valve};
+//use super::network::node::{junction::Junction, reservoir::Reservoir, tank::Tank};
+use super::energy::{EnergySummary, PumpEnergy, SPECIFIC_WEIGHT, Tariff};
+use super::leakage::LeakageReport;
+use super::network::link::{LinkStatus, LinkType, ValveType};
+use super::network::node::NodeType;
+use super::network::{DemandModel, Network, NodeRef, Pump, Unbalanced, ValidationReport};
+use crate::network::{CLOSED_LINK_RESISTANCE, FLOW_EPSILON};
+pub use balance::{MassBalance, NodeSupply};
+pub use initial::InitialFlows;
+use observer::NoObserver;
+pub use observer::{IterationProgress, SolverObserver};
+pub use report::{SolverError, SolverReport, SolverStatus, StopCriterion};
+pub use results::{HydraulicResults, LinkResult, NodeResult};
+pub use session::SolverSession;
+use sparse::FactorError;
+pub(crate) use topology::Topology;
+
+/// Minimal pressure range (m) and exponent of the pressure dependent demands.
+const PRESSURE_EPSILON: f64 = 0.001;
+
+/// Head loss gradients bounds of the virtual links (pressure dependent outflows)
+/// and of the links under the Newton method.
+const BIG_GRADIENT: f64 = 1.0e8;
+const SMALL_GRADIENT: f64 = 1.0e-7;
+
+/// Minimal head difference (m) re-opening a closed check valve pipe.
+const REOPEN_HEAD_EPSILON: f64 = 0.0005;
+
+pub struct Solver<'a> {
+    pub network: &'a Network,
+
+    ///
+    /// Structure of the head system, analysed with the solver or shared by a session.
+    ///
+    topology: Arc<Topology>,
+    /*
+       junctions: &'a mut Vec<Junction>,
+       tanks: &'a mut Vec<Tank>,
+       reservoirs: &'a mut Vec<Reservoir>,
+       pipes: &'a mut Vec<Pipe>,
+       pumps: &'a mut Vec<Pump>,
+       valves: &'a mut Vec<Valve>,
+    */
+    junction_count: usize,
+    tank_count: usize,
+    reservoir_count: usize,
+    pipe_count: usize,
+    pump_count: usize,
+    valve_count: usize,
+
+    ///
+    /// Units of the network : values are converted to SI for the computation.
+    ///
+    units: Units,
+    flow_unit_multiplayer: f64,
+    ///
+    /// non-zero & strict positive m-value. Default value : m = 100.
+    ///
+    m: f64,
+    n: f64,
+    method: Method,
+    iterations: Option<usize>,
+    final_error: Option<(f64, f64)>,
+    time_analysis: Option<Duration>,
+
+    ///
+    /// Accuracy overriding the network options (None : Options::accuracy).
+    ///
+    objective_error: Option<f64>,
+    pump_energy: Option<Vec<PumpEnergy>>,
+
+    ///
+    /// Actual status of the links (pipes, pumps, valves) during and after the iterations.
+    ///
+    link_status: Vec<LinkStatus>,
+    status_changes: Vec<StatusChange>,
+
+    ///
+    /// Delivered demands (m3/s) of the junctions.
+    ///
+    delivered_demand: Option<Vec<f64>>,
+
+    ///
+    /// Emitters outflows (m3/s) of the junctions.
+    ///
+    emitter_flow: Option<Vec<f64>>,
+
+    ///
+    /// Leakage outflows (m3/s) of the pipes.
+    ///
+    leakage_flow: Option<Vec<f64>>,
+
+    ///
+    /// Junctions without a path of open links to a tank or a reservoir (actual link statuses).
+    ///
+    disconnected: Vec<bool>,
+
+    ///
+    /// Initial flows of the links (default : the total demand Qmax for every link).
+    ///
+    initial_flows: InitialFlows,
+
+    validation: Option<ValidationReport>,
+}
+
+///
+/// Linearization of the link head losses between two iterations.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Method {
+    ///
+    /// Multilinear method (Moosavian, 2017) : secant of the head loss over an interval of width Qmax / m.
+    ///
+    #[default]
+    Multilinear,
+
+    ///
+    /// Global gradient algorithm (Todini & Pilati, 1988) : Newton-Raphson iterations using the
+    /// analytic derivatives of the head losses. The new flows are relaxed :
+    /// Q = Q0 + relaxation x (Qnewton - Q0), relaxation in ]0, 1] (1 : full Newton step).
+    ///
+    Newton { relaxation: f64 },
+}
+
+///
+/// Part of a pipe leakage (q = C x p^n, C in m3/s) assigned to one of its end junctions.
+///
+#[derive(Debug, Clone, Copy)]
+struct LeakageTerm {
+    pipe: usize,
+    junction: usize,
+    coefficient: f64,
+    exponent: f64,
+}
+
+///
+/// End node of a link in the unknowns of the solver (column of A21 or of A10).
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LinkEnd {
+    Junction(usize),
+
+    ///
+    /// Index in h0 (tanks then reservoirs).
+    ///
+    FixedHead(usize),
+    Missing,
+}
+
+///
+/// A link status change made by the solver during the iterations (check valve pipes, pumps non-return).
+///
+#[derive(Debug, Clone, Copy)]
+pub struct StatusChange {
+    pub iteration: usize,
+    pub link_type: LinkType,
+    pub link_id: usize,
+    pub from: LinkStatus,
+    pub to: LinkStatus,
+}
+
+impl<'a> Solver<'a> {
+    ///
+    /// use this function to build new Solver
+    ///
+    /// objective_error : minimal error flow and head computation (stopping criterion). If None, the accuracy of the network options will be used.
+    /// The maximum number of iterations and the unbalanced policy are those of the network options.
+    ///
+    pub fn new(wdn: &'a Network, objective_error: Option<f64>) -> Self {
+        // the element vectors may have been edited since the network index was built.
+        Self::with_topology(wdn, objective_error, Arc::new(Topology::analyse(wdn)))
+    }
+
+    ///
+    /// Solver on a network of an already analysed topology (see SolverSession).
+    ///
+    pub(crate) fn with_topology(
+        wdn: &'a Network,
+        objective_error: Option<f64>,
+        topology: Arc<Topology>,
+    ) -> Self {
+        let njunction: usize = match &wdn.junctions {
+            None => 0,
+            Some(items) => items.len(),
+        };
+
+        let ntank: usize = match &wdn.tanks {
+            None => 0,
+            Some(items) => items.len(),
+        };
+
+        let nreservoir: usize = match &wdn.reservoirs {
+            None => 0,
+            Some(items) => items.len(),
+        };
+
+        let npipe: usize = match &wdn.pipes {
+            None => 0,
+            Some(items) => items.len(),
+        };
+
+        let npump: usize = match &wdn.pumps {
+            None => 0,
+            Some(items) => items.len(),
+        };
+        let nvalve: usize = match &wdn.valves {
+            None => 0,
+            Some(items) => items.len(),
+        };
+
+        let obj_err: Option<f64> = objective_error.map(|objerr| f64::max(objerr, 0.0000000000001));
+
+        let units = wdn.options.units();
+        let flow_unit_multiplayer = units.factor(Quantity::Flow);
+
+        let solver = Solver {
+            network: wdn,
+            topology,
+            junction_count: njunction,
+            tank_count: ntank,
+            reservoir_count: nreservoir,
+            pipe_count: npipe,
+            pump_count: npump,
+            valve_count: nvalve,
+            m: 100.0f64,
+            n: 1.852f64,
+            method: Method::default(),
+            iterations: None,
+            final_error: None,
+            objective_error: obj_err,
+            time_analysis: None,
+            units,
+            flow_unit_multiplayer,
+            pump_energy: None,
+            link_status: Vec::new(),
+            status_changes: Vec::new(),
+            delivered_demand: None,
+            emitter_flow: None,
+            leakage_flow: None,
+            disconnected: Vec::new(),
+            initial_flows: InitialFlows::default(),
+            validation: None,
+        };
+        //solver.convert_2is();
+        //
+        solver
+    }
+
+    ///
+    /// Set non-zero & strict positive m-value. Default value : m = 100.
+    ///
+    pub fn set_m_parameter(&mut self, m_value: f64) {
+        self.m = f64::max(m_value, 1.0);
+    }
+
+    ///
+    /// Set the method of the next computations. Default : Method::Multilinear.
+    /// The relaxation factor of the Newton method is bounded to [0.01, 1].
+    ///
+    pub fn set_method(&mut self, method: Method) {
+        self.method = match method {
+            Method::Newton { relaxation } if relaxation.is_finite() => Method::Newton {
+                relaxation: relaxation.clamp(0.01, 1.0),
+            },
+            Method::Newton { .. } => Method::Newton { relaxation: 1.0 },
+            Method::Multilinear => Method::Multilinear,
+        };
+    }
+
+    ///
+    /// Set the initial flows of the next computations. Default : InitialFlows::TotalDemand.
+    ///
+    pub fn set_initial_flows(&mut self, initial_flows: InitialFlows) {
+        self.initial_flows = initial_flows;
+    }
+
+    pub fn get_method(&self) -> Method {
+        self.method
+    }
+
+    ///
+    /// Set non-zero & strict positive, overriding the accuracy of the network options.
+    ///
+    pub fn set_objective_error(&mut self, err_value: f64) {
+        self.objective_error = Some(f64::max(err_value, 0.0000000000001));
+    }
+
+    pub fn get_final_iterations(&self) -> Option<usize> {
+        self.iterations
+    }
+
+    pub fn get_final_errors(&self) -> Option<(f64, f64)> {
+        self.final_error
+    }
+
+    pub fn get_version(&self) -> &'static str {
+        "0.1.2"
+    }
+
+    pub fn get_time_analysis(&self) -> Option<Duration> {
+        self.time_analysis
+    }
+
+    ///
+    /// Final status (Open/Closed) of the links, ordered as pipes, pumps then valves.
+    ///
+    pub fn get_link_statuses(&self) -> Option<&[LinkStatus]> {
+        if self.iterations.is_some() {
+            Some(&self.link_status)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Status changes made during the last computation (check valves, pumps).
+    ///
+    pub fn get_status_changes(&self) -> &[StatusChange] {
+        &self.status_changes
+    }
+
+    ///
+    /// Energy results (power, efficiency) of the pumps for the last computed operating point.
+    ///
+    pub fn get_pump_energies(&self) -> Option<&[PumpEnergy]> {
+        self.pump_energy.as_deref()
+    }
+
+    ///
+    /// Energy summary of the pumps operating during `hours` from `start_hour` (time of day).
+    /// If tariff is None, the tariff of the network energy options is used.
+    ///
+    pub fn energy_summary(
+        &self,
+        start_hour: f64,
+        hours: f64,
+        tariff: Option<&Tariff>,
+    ) -> Option<EnergySummary> {
+        let pumps = self.pump_energy.as_ref()?;
+        let summary = match tariff {
+            Some(trf) => EnergySummary::new(pumps, start_hour, hours, trf),
+            None => EnergySummary::new(
+                pumps,
+                start_hour,
+                hours,
+                &Tariff::from_options(&self.network.options.energy),
+            ),
+        };
+        Some(summary)
+    }
+    ///
+    /// Leakage flows and volumes of the pipes over a period given in hours (None before compute).
+    ///
+    pub fn leakage_report(&self, hours: f64) -> Option<LeakageReport> {
+        let leakage = self.leakage_flow.as_ref()?;
+        let pipes = self.network.pipes.as_ref()?;
+        let flows: Vec<(usize, Option<String>, f64)> = pipes
+            .iter()
+            .zip(leakage.iter())
+            .filter(|(p, _)| p.leakage.is_some())
+            .map(|(p, ql)| (p.id, p.name.clone(), *ql))
+            .collect();
+        Some(LeakageReport::new(&flows, hours))
+    }
+
+    ///
+    /// Validation report of the network, made at the start of the last computation.
+    ///
+    pub fn get_validation_report(&self) -> Option<&ValidationReport> {
+        self.validation.as_ref()
+    }
+
+    ///
+    /// Solve the network : the network is not changed, the results are returned. The network is
+    /// validated first (see get_validation_report()). Reaching the maximum number of iterations
+    /// is not an error : see the status of the report.
+    ///
+    pub fn compute(&mut self) -> Result<HydraulicResults, SolverError> {
+        self.compute_observed(&mut NoObserver)
+    }
+
+    ///
+    /// Solve the network (see compute), reporting the progress of each iteration and the link
+    /// status changes to the observer.
+    ///
+    pub fn compute_observed(
+        &mut self,
+        observer: &mut dyn SolverObserver,
+    ) -> Result<HydraulicResults, SolverError> {
+        let chronos = Instant::now();
+        self.iterations = None;
+        self.final_error = None;
+
+        let report = self.network.validate();
+        log::debug!("{}", report);
+        self.validation = Some(report.clone());
+        if !report.is_valid() {
+            return Err(SolverError::InvalidNetwork(report));
+        }
+
+        let topology = Arc::clone(&self.topology);
+        let links = &topology.links;
+        let (h0, q) = self.get_network();
+
+        let nn = self.junction_count;
+        let np = links.len();
+
+        if nn < 1 || np < 1 {
+            return Err(SolverError::EmptyNetwork {
+                junctions: nn,
+                links: np,
+            });
+        }
+
+        // Stopping rules : accuracy, trials and extra trials of the unbalanced policy.
+        let mut iter: usize = 0;
+        let itermax: usize = usize::max(self.network.options.trials, 1);
+        let extra_trials: usize = match self.network.options.unbalanced {
+            Unbalanced::StopIter => 0,
+            Unbalanced::ContinueIter(n) => n,
+        };
+        let objective_err: f64 = self
+            .objective_error
+            .unwrap_or(f64::max(self.network.options.accuracy, 0.0000000000001));
+        let mut final_err_q: f64;
+        let mut final_err_h: f64;
+        let mut final_err_balance: f64;
+
+        let mut _a: Vec<f64> = self.initilize_a_matrix(); // diagonal of A
+        let mut _b = vec![0.0f64; np]; // B
+        let mut _c = vec![0.0f64; np];
+        let mut _flowsq = vec![0.0f64; np];
+        let mut _previous_q = vec![0.0f64; np];
+        let mut _headsh = vec![0.0f64; nn];
+        let mut _previous_h = vec![0.0f64; nn];
+        let mut _delivered = q.clone();
+        let mut _emitter_flows = self.initial_emitter_flows();
+        let leakage_terms = self.leakage_terms();
+        let mut _leakage_flows: Vec<f64> = leakage_terms.iter().map(|t| t.coefficient).collect();
+
+        // V on the pattern of the topology (ordering and factor structure analysed once).
+        let mut _v = topology.pattern.clone();
+        let link_slot = &topology.link_slot;
+        let symbolic = &topology.symbolic;
+
+        self.link_status = self.initial_link_statuses();
+
+        // junctions cut off from the tanks and reservoirs : removed from the head system
+        self.disconnected = self.disconnected_junctions(&[]);
+
+        // step 0 : compute Qmax (demands of the connected junctions)
+        let qmax: f64 = q
+            .iter()
+            .zip(self.disconnected.iter())
+            .filter(|(_, disconnected)| !**disconnected)
+            .map(|(qd, _)| qd)
+            .sum();
+        /*
+        for i in 0..q.len() {
+           qmax+=q[i];
+        } */
+
+        // compute delta Q
+        let deltaq = qmax / self.m;
+
+        // initial flows : Qmax, given flows (warm start), spanning tree or velocity
+        _flowsq = self.initial_link_flows(&q, qmax);
+        self.status_changes.clear();
+
+        let stopped_by: StopCriterion = loop {
+            //Updating A (eq13) & B (eq14), or the tangents of the head losses (Newton) :
+            match self.method {
+                Method::Multilinear => {
+                    self.update_matrices_a_b(&mut _a, &mut _b, &_flowsq, deltaq, self.n)
+                }
+                Method::Newton { .. } => self.update_gradients_a_b(&mut _a, &mut _b, &_flowsq),
+            }
+
+            log::trace!("iteration {} : A = {:?}", iter, _a);
+            log::trace!("iteration {} : B = {:?}", iter, _b);
+
+            // Step 2 : Compute C = -B - A10 x H0, V = A21 x A-1 x A12 and A21 x A-1 x C
+            let inva: Vec<f64> = _a.iter().map(|a| 1.0 / a).collect();
+            let mut rhs = vec![0.0f64; nn];
+            _v.clear();
+            for (k, ends) in links.iter().enumerate() {
+                if Self::is_cut_off(ends, &self.disconnected) {
+                    continue;
+                }
+                _c[k] = -_b[k];
+                for (end, sign) in [(ends.0, -1.0f64), (ends.1, 1.0f64)] {
+                    match end {
+                        LinkEnd::Junction(i) => {
+                            _v.add_to_diagonal(i, inva[k]);
+                        }
+                        LinkEnd::FixedHead(j) => _c[k] -= sign * h0[j],
+                        LinkEnd::Missing => {}
+                    }
+                }
+                if let Some(slot) = link_slot[k] {
+                    _v.add_to_slot(slot, -inva[k]);
+                }
+                for (end, sign) in [(ends.0, -1.0f64), (ends.1, 1.0f64)] {
+                    if let LinkEnd::Junction(i) = end {
+                        rhs[i] += sign * inva[k] * _c[k];
+                    }
+                }
+            }
+
+            //print_vector(&_c, "C : ");
+
+            // Nodal outflows (delivered demands, emitters) linearized around their actual values :
+            // q = q0 + G H  ==>  (V + G) H = V1 C - q0
+            let (mut intercepts, mut slopes) = self.linearize_demands(&q, &_delivered);
+            let (mut em_intercepts, mut em_slopes) = self.linearize_emitters(&_emitter_flows);
+            let (mut lk_intercepts, mut lk_slopes) =
+                self.linearize_leakages(&leakage_terms, &_leakage_flows);
+            for i in (0..nn).filter(|i| self.disconnected[*i]) {
+                (intercepts[i], slopes[i]) = (0.0, 0.0);
+                (em_intercepts[i], em_slopes[i]) = (0.0, 0.0);
+            }
+            for (t, term) in leakage_terms.iter().enumerate() {
+                if self.disconnected[term.junction] {
+                    (lk_intercepts[t], lk_slopes[t]) = (0.0, 0.0);
+                }
+            }
+            for i in 0..nn {
+                _v.add_to_diagonal(i, slopes[i] + em_slopes[i]);
+            }
+            for (t, term) in leakage_terms.iter().enumerate() {
+                _v.add_to_diagonal(term.junction, lk_slopes[t]);
+            }
+
+            for i in 0..nn {
+                rhs[i] -= intercepts[i] + em_intercepts[i];
+            }
+            for (t, term) in leakage_terms.iter().enumerate() {
+                rhs[term.junction] -= lk_intercepts[t];
+            }
+
+            // the head of a disconnected junction is its elevation (H = z)
+            if let Some(junctions) = &self.network.junctions {
+                for i in (0..nn).filter(|i| self.disconnected[*i]) {
+                    _v.add_to_diagonal(i, 1.0);
+                    rhs[i] = self.units.to_si(Quantity::Head, junctions[i].elevation);
+                }
+            }
+
+            // Step 3 : Compute H (eq.29)
+            let factor = symbolic
+                .factorize(&_v)
+                .map_err(|error| self.factor_error(error, iter))?;
+            _headsh = factor.solve(&rhs);
+
+            for i in 0..nn {
+                _delivered[i] = intercepts[i] + slopes[i] * _headsh[i];
+                _emitter_flows[i] = em_intercepts[i] + em_slopes[i] * _headsh[i];
+            }
+            for (t, term) in leakage_terms.iter().enumerate() {
+                _leakage_flows[t] = lk_intercepts[t] + lk_slopes[t] * _headsh[term.junction];
+            }
+
+            log::trace!("iteration {} : H = {:?}", iter, _headsh);
+            // Step 4 : Compute flowws Q (eq30) : Q = A-1 x (C - A12 x H)
+            // (relaxed from the linearization flows under the Newton method)
+            for (k, ends) in links.iter().enumerate() {
+                if Self::is_cut_off(ends, &self.disconnected) {
+                    _flowsq[k] = 0.0;
+                    continue;
+                }
+                let mut a12h = 0.0f64;
+                for (end, sign) in [(ends.0, -1.0f64), (ends.1, 1.0f64)] {
+                    if let LinkEnd::Junction(i) = end {
+                        a12h += sign * _headsh[i];
+                    }
+                }
+                let flow = inva[k] * (_c[k] - a12h);
+                _flowsq[k] = match self.method {
+                    Method::Multilinear => flow,
+                    Method::Newton { relaxation } => _flowsq[k] + relaxation * (flow - _flowsq[k]),
+                };
+            }
+
+            log::trace!("iteration {} : Q = {:?}", iter, _flowsq);
+
+            if !(_headsh.iter().all(|h| h.is_finite()) && _flowsq.iter().all(|q| q.is_finite())) {
+                return Err(SolverError::NotFinite { iteration: iter });
+            }
+
+            //Check convergence : changes of the flows and heads, flow balance of the junctions.
+            let check_q_err = Solver::check_convergence(&_flowsq, &_previous_q, objective_err);
+            let check_h_err = Solver::check_convergence(&_headsh, &_previous_h, objective_err);
+            let mut outflows: Vec<f64> = _delivered
+                .iter()
+                .zip(_emitter_flows.iter())
+                .map(|(qd, qe)| qd + qe)
+                .collect();
+            for (term, ql) in leakage_terms.iter().zip(_leakage_flows.iter()) {
+                outflows[term.junction] += ql;
+            }
+            final_err_q = check_q_err.1;
+            final_err_h = check_h_err.1;
+            let residuals = Solver::balance_residuals(links, &_flowsq, &outflows);
+            final_err_balance = Solver::balance_error(&_flowsq, &residuals);
+            let mut converged =
+                check_q_err.0 && check_h_err.0 && final_err_balance <= objective_err;
+
+            // Check valves & pumps status (the first solution is based on the initial flows),
+            // the statuses are frozen during the extra trials :
+            let mut changes: usize = 0;
+            if iter > 0 && iter < itermax {
+                changes =
+                    self.update_link_statuses(&_headsh, &h0, &mut _flowsq, iter, qmax / self.m);
+                if changes > 0 {
+                    converged = false;
+                    self.disconnected = self.disconnected_junctions(&self.disconnected);
+                }
+            }
+            for change in &self.status_changes[self.status_changes.len() - changes..] {
+                observer.on_status_change(change);
+            }
+
+            //Copy data
+            for i in 0..np {
+                _previous_q[i] = _flowsq[i];
+            }
+
+            for j in 0..nn {
+                _previous_h[j] = _headsh[j];
+            }
+
+            let (max_imbalance, max_imbalance_junction) = self.max_imbalance(&residuals);
+            let progress = IterationProgress {
+                iteration: iter,
+                flow_change: final_err_q,
+                head_change: final_err_h,
+                balance_error: final_err_balance,
+                max_imbalance,
+                max_imbalance_junction,
+                status_changes: changes,
+            };
+            log::debug!(
+                "iteration {} : flow change {:e}, head change {:e}, balance error {:e}, max imbalance {:e} (junction {:?}), {} status changes",
+                progress.iteration,
+                progress.flow_change,
+                progress.head_change,
+                progress.balance_error,
+                progress.max_imbalance,
+                progress.max_imbalance_junction,
+                progress.status_changes
+            );
+            observer.on_iteration(&progress);
+
+            iter += 1;
+
+            if converged {
+                break StopCriterion::Accuracy;
+            }
+            if iter >= itermax + extra_trials {
+                break if extra_trials > 0 {
+                    StopCriterion::ExtraTrials
+                } else {
+                    StopCriterion::Trials
+                };
+            }
+        };
+
+        // pressure driven demands within [0, demand] ; negative demands (inflows) are not pressure driven.
+        self.delivered_demand = Some(match self.network.options.demand_model {
+            DemandModel::Dda => _delivered,
+            DemandModel::Pda { .. } => _delivered
+                .iter()
+                .zip(q.iter())
+                .map(|(qd, d)| if *d > 0.0 { qd.clamp(0.0, *d) } else { *d })
+                .collect(),
+        });
+        self.emitter_flow = Some(_emitter_flows.iter().map(|qe| qe.max(0.0)).collect());
+        let mut leakage = vec![0.0f64; self.pipe_count];
+        let mut junction_leakage = vec![0.0f64; nn];
+        for (term, ql) in leakage_terms.iter().zip(_leakage_flows.iter()) {
+            leakage[term.pipe] += ql.max(0.0);
+            junction_leakage[term.junction] += ql.max(0.0);
+        }
+        self.leakage_flow = Some(leakage);
+        self.pump_energy = Some(self.compute_pump_energy(&_headsh, &h0, &_flowsq));
+        self.iterations = Some(iter);
+        self.final_error = Some((final_err_q, final_err_h));
+
+        let time = chronos.elapsed();
+        self.time_analysis = Some(time);
+        /*  let wdn = NetworkBuilder::new()
+        .set_junctions(Some(self.junctions.clone()))
+        .set_pipes(Some(self.pipes.clone()))
+        .set_pumps(Some(self.pumps.clone()))
+        .set_valves(Some(self.valves.clone()))
+        .build();  */
+        let report = SolverReport {
+            status: if stopped_by == StopCriterion::Accuracy {
+                SolverStatus::Converged
+            } else {
+                SolverStatus::MaxIterations
+            },
+            stopped_by,
+            method: self.method,
+            iterations: iter,
+            accuracy: objective_err,
+            flow_error: final_err_q,
+            head_error: final_err_h,
+            balance_error: final_err_balance,
+            status_changes: self.status_changes.len(),
+            time,
+        };
+        Ok(self.results(report, &_headsh, &h0, &_flowsq, &junction_leakage))
+    }
+
+    ///
+    /// Solver error of a failed factorization of the head system.
+    ///
+    fn factor_error(&self, error: FactorError, iteration: usize) -> SolverError {
+        match error {
+            FactorError::SizeMismatch { expected, found } => {
+                SolverError::DimensionMismatch { expected, found }
+            }
+            FactorError::NullPivot(i) => SolverError::SingularMatrix {
+                iteration,
+                junction_id: self
+                    .network
+                    .junctions
+                    .as_ref()
+                    .and_then(|junctions| junctions.get(i))
+                    .map_or(i, |jn| jn.id),
+            },
+        }
+    }
+
+    ///
+    /// Results in the network units from the computed heads, flows and junctions leakages (SI).
+    ///
+    fn results(
+        &self,
+        report: SolverReport,
+        heads_h: &[f64],
+        h0: &[f64],
+        flows_q: &[f64],
+        junction_leakage: &[f64],
+    ) -> HydraulicResults {
+        let qf = self.flow_unit_multiplayer;
+        let head_of = |node_id: usize| self.node_head(node_id, heads_h, h0).unwrap_or(0.0);
+
+        // net inflows (m3/s) of the nodes
+        let mut inflows =
+            vec![0.0f64; self.junction_count + self.tank_count + self.reservoir_count];
+        for ((start, end), q) in self.topology.ends.iter().zip(flows_q.iter()) {
+            for (node_id, sign) in [(*start, -1.0f64), (*end, 1.0f64)] {
+                if let Some(i) = self.node_position(node_id) {
+                    inflows[i] += sign * q;
+                }
+            }
+        }
+
+        let mut nodes: Vec<NodeResult> = Vec::with_capacity(inflows.len());
+        if let Some(junctions) = &self.network.junctions {
+            for (i, jn) in junctions.iter().enumerate() {
+                let head = heads_h[i];
+                nodes.push(NodeResult {
+                    id: jn.id,
+                    node_type: NodeType::Junction,
+                    head: self.units.from_si(Quantity::Head, head),
+                    pressure: self.units.from_si(
+                        Quantity::Pressure,
+                        head - self.units.to_si(Quantity::Head, jn.elevation),
+                    ),
+                    demand: self
+                        .delivered_demand
+                        .as_ref()
+                        .map_or(0.0, |delivered| delivered[i] / qf),
+                    emitter_flow: self
+                        .emitter_flow
+                        .as_ref()
+                        .map_or(0.0, |emitters| emitters[i] / qf),
+                    disconnected: self.disconnected.get(i).copied().unwrap_or(false),
+                });
+            }
+        }
+        if let Some(tanks) = &self.network.tanks {
+            for (i, tnk) in tanks.iter().enumerate() {
+                nodes.push(NodeResult {
+                    id: tnk.id,
+                    node_type: NodeType::Tank,
+                    head: tnk.head(),
+                    pressure: tnk.level.unwrap_or(tnk.initial_level),
+                    demand: inflows[self.junction_count + i] / qf,
+                    emitter_flow: 0.0,
+                    disconnected: false,
+                });
+            }
+        }
+        if let Some(reservoirs) = &self.network.reservoirs {
+            for (i, rsrvr) in reservoirs.iter().enumerate() {
+                nodes.push(NodeResult {
+                    id: rsrvr.id,
+                    node_type: NodeType::Reservoir,
+                    head: rsrvr.head,
+                    pressure: 0.0,
+                    demand: inflows[self.junction_count + self.tank_count + i] / qf,
+                    emitter_flow: 0.0,
+                    disconnected: false,
+                });
+            }
+        }
+
+        let mut link_types: Vec<(usize, LinkType)> = Vec::with_capacity(flows_q.len());
+        if let Some(pipes) = &self.network.pipes {
+            link_types.extend(pipes.iter().map(|p| (p.id, LinkType::Pipe)));
+        }
+        if let Some(pumps) = &self.network.pumps {
+            link_types.extend(pumps.iter().map(|p| (p.id, LinkType::Pump)));
+        }
+        if let Some(valves) = &self.network.valves {
+            link_types.extend(valves.iter().map(|v| (v.id, LinkType::Valve(v.valvetype))));
+        }
+        let mut links: Vec<LinkResult> = link_types
+            .into_iter()
+            .zip(self.topology.ends.iter())
+            .enumerate()
+            .map(|(k, ((id, link_type), &(start, end)))| LinkResult {
+                id,
+                link_type,
+                flow: flows_q[k] / qf,
+                velocity: 0.0,
+                headloss: self
+                    .units
+                    .from_si(Quantity::Head, head_of(start) - head_of(end)),
+                unit_headloss: 0.0,
+                friction_factor: 0.0,
+                status: self.link_status.get(k).copied().unwrap_or(LinkStatus::Open),
+                leakage_flow: 0.0,
+            })
+            .collect();
+
+        if let Some(pipes) = &self.network.pipes {
+            for (k, pipe) in pipes.iter().enumerate() {
+                let result = &mut links[k];
+                let q = flows_q[k];
+                let length = self.units.to_si(Quantity::Length, pipe.length);
+                let diameter = self.units.to_si(Quantity::Diameter, pipe.diameter);
+                let velocity = 4.0 * q / (std::f64::consts::PI * diameter * diameter);
+                result.velocity = self.units.from_si(Quantity::Velocity, velocity);
+                result.unit_headloss = result.headloss * 1000.0 / pipe.length;
+                if q.abs() > FLOW_EPSILON {
+                    let headloss = self.units.to_si(Quantity::Head, result.headloss).abs();
+                    result.friction_factor =
+                        headloss * 2.0 * 9.81 * diameter / (length * velocity * velocity);
+                }
+                result.leakage_flow = self
+                    .leakage_flow
+                    .as_ref()
+                    .map_or(0.0, |leakage| leakage[k] / qf);
+            }
+        }
+        if let Some(valves) = &self.network.valves {
+            for (i, valve) in valves.iter().enumerate() {
+                let k = self.pipe_count + self.pump_count + i;
+                let diameter = self.units.to_si(Quantity::Diameter, valve.diameter);
+                if diameter > 0.0 {
+                    let velocity = 4.0 * flows_q[k] / (std::f64::consts::PI * diameter * diameter);
+                    links[k].velocity = self.units.from_si(Quantity::Velocity, velocity);
+                }
+            }
+        }
+
+        // flow balance of the junctions : inflow - delivered demand - emitter and leakage outflows
+        let imbalances: Vec<f64> = (0..self.junction_count)
+            .map(|i| {
+                let outflow = self.delivered_demand.as_ref().map_or(0.0, |qd| qd[i])
+                    + self.emitter_flow.as_ref().map_or(0.0, |qe| qe[i])
+                    + junction_leakage.get(i).copied().unwrap_or(0.0);
+                (inflows[i] - outflow) / qf
+            })
+            .collect();
+        let flow_unit = self.network.options.flow_unit;
+        let balance = MassBalance::new(flow_unit, &nodes, &links, &imbalances);
+        HydraulicResults::new(report, flow_unit, nodes, links, balance)
+    }
+
+    ///
+    /// Linearize the delivered demands (m3/s) of the junctions around their actual values : q = q0 + G x H.
+    /// Return (q0, G). Under demand driven analysis, the delivered demands are the demands (G = 0).
+    ///
+    /// Pressure driven demands are handled as virtual links to a fixed head (elevation + minimum pressure)
+    /// with the head loss of the Wagner relation and barrier functions outside [0, demand].
+    ///
+    fn linearize_demands(&self, demands: &[f64], outflows: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let mut intercepts = demands.to_vec();
+        let mut slopes = vec![0.0f64; demands.len()];
+
+        if let DemandModel::Pda {
+            minimum_pressure,
+            required_pressure,
+            exponent,
+        } = self.network.options.demand_model
+            && let Some(junctions) = &self.network.junctions
+        {
+            let minimum_pressure = self.units.to_si(Quantity::Pressure, minimum_pressure);
+            let required_pressure = self.units.to_si(Quantity::Pressure, required_pressure);
+            let dp = f64::max(required_pressure - minimum_pressure, PRESSURE_EPSILON);
+            let n = 1.0 / f64::max(exponent, PRESSURE_EPSILON);
+            for (i, jn) in junctions.iter().enumerate() {
+                if demands[i] <= 0.0 {
+                    continue;
+                }
+                let (hloss, hgrad) = Self::demand_headloss(outflows[i], demands[i], dp, n);
+                slopes[i] = 1.0 / hgrad;
+                let elevation = self.units.to_si(Quantity::Head, jn.elevation);
+                intercepts[i] = outflows[i] - (hloss + elevation + minimum_pressure) / hgrad;
+            }
+        }
+        (intercepts, slopes)
+    }
+
+    ///
+    /// Emitters initial outflows (m3/s) : the discharge under a pressure of 1 m.
+    ///
+    fn initial_emitter_flows(&self) -> Vec<f64> {
+        match &self.network.junctions {
+            None => Vec::new(),
+            Some(junctions) => junctions
+                .iter()
+                .map(|jn| self.emitter_coefficient_si(jn.emitter_coefficient))
+                .collect(),
+        }
+    }
+
+    ///
+    /// Emitter coefficient in SI (m3/s per m^n) from the network units (flow unit per pressure unit^n).
+    ///
+    fn emitter_coefficient_si(&self, coefficient: f64) -> f64 {
+        coefficient * self.flow_unit_multiplayer
+            / self
+                .units
+                .factor(Quantity::Pressure)
+                .powf(self.network.options.emitter_exponent)
+    }
+
+    ///
+    /// Linearize the emitters outflows (m3/s) around their actual values : q = q0 + G x H. Return (q0, G).
+    ///
+    /// An emitter (q = C x p^n) is handled as a virtual link to a fixed head (the junction elevation)
+    /// with the head loss (q/C)^(1/n) and a barrier function against backflow.
+    ///
+    fn linearize_emitters(&self, emitter_flows: &[f64]) -> (Vec<f64>, Vec<f64>) {
+        let mut intercepts = vec![0.0f64; emitter_flows.len()];
+        let mut slopes = vec![0.0f64; emitter_flows.len()];
+
+        if let Some(junctions) = &self.network.junctions {
+            let n = 1.0 / f64::max(self.network.options.emitter_exponent, PRESSURE_EPSILON);
+            for (i, jn) in junctions.iter().enumerate() {
+                if jn.emitter_coefficient <= 0.0 {
+                    continue;
+                }
+                let coefficient = self.emitter_coefficient_si(jn.emitter_coefficient);
+                let (hloss, hgrad) = Self::power_outflow_headloss(emitter_flows[i], coefficient, n);
+                let elevation = self.units.to_si(Quantity::Head, jn.elevation);
+                slopes[i] = 1.0 / hgrad;
+                intercepts[i] = emitter_flows[i] - (hloss + elevation) / hgrad;
+            }
+        }
+        (intercepts, slopes)
+    }
+
+    ///
+    /// Leakage terms of the pipes : the leakage of each half of a pipe is assigned to its end junction.
+    /// A pipe from a junction to a tank or a reservoir leaks entirely under the pressure of the junction,
+    /// so that its whole length leaks ; a pipe between two fixed head nodes does not leak.
+    ///
+    fn leakage_terms(&self) -> Vec<LeakageTerm> {
+        let mut terms: Vec<LeakageTerm> = Vec::new();
+        if let Some(pipes) = &self.network.pipes {
+            for (k, pipe) in pipes.iter().enumerate() {
+                let Some(leakage) = &pipe.leakage else {
+                    continue;
+                };
+                let junctions: Vec<usize> = [pipe.start, pipe.end]
+                    .iter()
+                    .filter_map(|&node_id| match self.topology.index.node(node_id) {
+                        Some(NodeRef::Junction(junction)) => Some(junction),
+                        _ => None,
+                    })
+                    .collect();
+                if junctions.is_empty() {
+                    continue;
+                }
+                let length =
+                    self.units.to_si(Quantity::Length, pipe.length) / junctions.len() as f64;
+                let end_terms = leakage.terms(
+                    length,
+                    self.flow_unit_multiplayer,
+                    self.units.factor(Quantity::Pressure),
+                );
+                for junction in junctions {
+                    for (coefficient, exponent) in end_terms.iter() {
+                        if *coefficient > 0.0 {
+                            terms.push(LeakageTerm {
+                                pipe: k,
+                                junction,
+                                coefficient: *coefficient,
+                                exponent: *exponent,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        terms
+    }
+
+    ///
+    /// Linearize the leakage terms outflows (m3/s) around their actual values : q = q0 + G x H. Return (q0, G).
+    /// Each term is handled as an emitter of its junction.
+    ///
+    fn linearize_leakages(
+        &self,
+        terms: &[LeakageTerm],
+        leakage_flows: &[f64],
+    ) -> (Vec<f64>, Vec<f64>) {
+        let mut intercepts = vec![0.0f64; terms.len()];
+        let mut slopes = vec![0.0f64; terms.len()];
+
+        if let Some(junctions) = &self.network.junctions {
+            for (t, term) in terms.iter().enumerate() {
+                let n = 1.0 / f64::max(term.exponent, PRESSURE_EPSILON);
+                let q = leakage_flows[t];
+                let (hloss, hgrad) = Self::power_outflow_headloss(q, term.coefficient, n);
+                slopes[t] = 1.0 / hgrad;
+                let elevation = self
+                    .units
+                    .to_si(Quantity::Head, junctions[term.junction].elevation);
+                intercepts[t] = q - (hloss + elevation) / hgrad;
+            }
+        }
+        (intercepts, slopes)
+    }
+
+    ///
+    /// Head loss (and its gradient) of the virtual link of a pressure dependent outflow q = C x p^(1/n) :
+    /// hloss = (q/C)^n, with a barrier function against backflow.
+    ///
+    fn power_outflow_headloss(q: f64, coefficient: f64, n: f64) -> (f64, f64) {
+        if q <= 0.0 {
+            return (BIG_GRADIENT * q, BIG_GRADIENT);
+        }
+        let hgrad = n * coefficient.powf(-n) * q.powf(n - 1.0);
+        if hgrad < SMALL_GRADIENT {
+            (SMALL_GRADIENT * q, SMALL_GRADIENT)
+        } else {
+            (hgrad * q / n, hgrad)
+        }
+    }
+
+    ///
+    /// Head loss (and its gradient) of the virtual demand link for a delivered demand d (full demand dfull) :
+    /// hloss = dp x (d/dfull)^n, with barrier functions for d < 0 and d > dfull.
+    ///
+    fn demand_headloss(d: f64, dfull: f64, dp: f64, n: f64) -> (f64, f64) {
+        let r = d / dfull;
+        if r <= 0.0 {
+            (BIG_GRADIENT * d, BIG_GRADIENT)
+        } else if r < 1.0 {
+            let hgrad = n * dp * r.powf(n - 1.0) / dfull;
+            if hgrad < SMALL_GRADIENT {
+                (SMALL_GRADIENT * d, SMALL_GRADIENT)
+            } else {
+                (hgrad * d / n, hgrad)
+            }
+        } else {
+            (dp + BIG_GRADIENT * (d - dfull), BIG_GRADIENT)
+        }
+    }
+
+    ///
+    /// Position of the node in the results : junctions, tanks then reservoirs.
+    ///
+    fn node_position(&self, node_id: usize) -> Option<usize> {
+        match self.topology.index.node(node_id)? {
+            NodeRef::Junction(i) => Some(i),
+            NodeRef::Tank(i) => Some(self.junction_count + i),
+            NodeRef::Reservoir(i) => Some(self.junction_count + self.tank_count + i),
+        }
+    }
+
+    ///
+    /// Head of the node (junction, tank or reservoir) from the computed heads and the fixed heads.
+    ///
+    fn node_head(&self, node_id: usize, heads_h: &[f64], h0: &[f64]) -> Option<f64> {
+        match self.topology.index.node(node_id)? {
+            NodeRef::Junction(i) => heads_h.get(i).copied(),
+            NodeRef::Tank(i) => h0.get(i).copied(),
+            NodeRef::Reservoir(i) => h0.get(i + self.tank_count).copied(),
+        }
+    }
+
+    ///
+    /// Compute the hydraulic power, the efficiency and the absorbed power of each pump.
+    ///
+    fn compute_pump_energy(&self, heads_h: &[f64], h0: &[f64], flows_q: &[f64]) -> Vec<PumpEnergy> {
+        let mut result: Vec<PumpEnergy> = Vec::new();
+        let energy_options = &self.network.options.energy;
+
+        if let Some(pumps) = &self.network.pumps {
+            for (i, pump) in pumps.iter().enumerate() {
+                let q = flows_q[self.pipe_count + i];
+                let h_start = self.node_head(pump.start, heads_h, h0).unwrap_or(0.0);
+                let h_end = self.node_head(pump.end, heads_h, h0).unwrap_or(0.0);
+                let head = h_end - h_start;
+
+                let (hydraulic_power, efficiency, power) =
+                    if self.link_status[self.pipe_count + i] == LinkStatus::Closed || q <= 0.0 {
+                        (0.0, 0.0, 0.0)
+                    } else {
+                        let hydraulic_power = Pump::hydraulic_power(q, head);
+                        let efficiency = pump.efficiency(
+                            q / self.flow_unit_multiplayer,
+                            energy_options.global_efficiency,
+                        );
+                        let power = if efficiency > 0.0 {
+                            hydraulic_power * 100.0 / efficiency
+                        } else {
+                            0.0
+                        };
+                        (hydraulic_power, efficiency, power)
+                    };
+
+                result.push(PumpEnergy {
+                    id: pump.id,
+                    name: pump.name.clone(),
+                    flow: q,
+                    head,
+                    hydraulic_power,
+                    efficiency,
+                    power,
+                });
+            }
+        }
+        result
+    }
+
+    ///
+    /// Pump curve coefficients (alpha, beta, gamma) in SI : head in m for a flow in m3/s,
+    /// at the pump speed (affinity laws).
+    ///
+    fn pump_curve_si(&self, pump: &Pump) -> (f64, f64, f64) {
+        let qf = self.flow_unit_multiplayer;
+        let hf = self.units.factor(Quantity::Head);
+        let speed = pump.speed;
+        (
+            pump.alpha * hf / (qf * qf),
+            pump.beta * speed * hf / qf,
+            pump.gamma * speed * speed * hf,
+        )
+    }
+
+    ///
+    /// Status of the links as set in the network (pipes, pumps, valves).
+    ///
+    fn initial_link_statuses(&self) -> Vec<LinkStatus> {
+        let mut statuses: Vec<LinkStatus> =
+            Vec::with_capacity(self.pipe_count + self.pump_count + self.valve_count);
+        if let Some(pipes) = &self.network.pipes {
+            statuses.extend(pipes.iter().map(|p| p.status));
+        }
+        if let Some(pumps) = &self.network.pumps {
+            statuses.extend(pumps.iter().map(|p| p.status));
+        }
+        if let Some(valves) = &self.network.valves {
+            statuses.extend(valves.iter().map(|v| v.status));
+        }
+        statuses
+    }
+
+    ///
+    /// Check the status of check valve pipes and pumps from the computed flows and heads :
+    /// - a check valve pipe closes on reverse flow and re-opens when the head difference exceeds
+    ///   REOPEN_HEAD_EPSILON,
+    /// - a pump closes on reverse flow or when the head gain exceeds its shutoff head, and re-opens otherwise.
+    ///
+    /// Links closed in the network are never re-opened, nor links from a disconnected junction.
+    /// A re-opened link restarts from a small positive flow (reopen_flow).
+    /// Return the number of status changes.
+    ///
+    fn update_link_statuses(
+        &mut self,
+        heads_h: &[f64],
+        h0: &[f64],
+        flows_q: &mut [f64],
+        iteration: usize,
+        reopen_flow: f64,
+    ) -> usize {
+        let mut changes: Vec<StatusChange> = Vec::new();
+
+        if let Some(pipes) = &self.network.pipes {
+            for (i, pipe) in pipes.iter().enumerate() {
+                if !pipe.check_valve || pipe.status == LinkStatus::Closed {
+                    continue;
+                }
+                let actual = self.link_status[i];
+                let new_status = match actual {
+                    LinkStatus::Open if flows_q[i] < -FLOW_EPSILON => LinkStatus::Closed,
+                    LinkStatus::Closed if self.is_disconnected(pipe.start) => actual,
+                    LinkStatus::Closed => {
+                        let h_start = self.node_head(pipe.start, heads_h, h0).unwrap_or(0.0);
+                        let h_end = self.node_head(pipe.end, heads_h, h0).unwrap_or(0.0);
+                        if h_start - h_end > REOPEN_HEAD_EPSILON {
+                            LinkStatus::Open
+                        } else {
+                            LinkStatus::Closed
+                        }
+                    }
+                    _ => actual,
+                };
+                if new_status != actual {
+                    changes.push(StatusChange {
+                        iteration,
+                        link_type: LinkType::Pipe,
+                        link_id: pipe.id,
+                        from: actual,
+                        to: new_status,
+                    });
+                }
+            }
+        }
+
+        if let Some(pumps) = &self.network.pumps {
+            for (i, pump) in pumps.iter().enumerate() {
+                if pump.status == LinkStatus::Closed {
+                    continue;
+                }
+                let k = self.pipe_count + i;
+                let actual = self.link_status[k];
+                let h_start = self.node_head(pump.start, heads_h, h0).unwrap_or(0.0);
+                let h_end = self.node_head(pump.end, heads_h, h0).unwrap_or(0.0);
+                let head_gain = h_end - h_start;
+                // shutoff head (Q = 0) of the pump curve, none for constant power pumps.
+                let shutoff_head = if pump.alpha != 0.0 {
+                    self.pump_curve_si(pump).2
+                } else {
+                    f64::MAX
+                };
+
+                let new_status = match actual {
+                    LinkStatus::Open if flows_q[k] < -FLOW_EPSILON || head_gain > shutoff_head => {
+                        LinkStatus::Closed
+                    }
+                    LinkStatus::Closed
+                        if head_gain < shutoff_head && !self.is_disconnected(pump.start) =>
+                    {
+                        LinkStatus::Open
+                    }
+                    _ => actual,
+                };
+                if new_status != actual {
+                    changes.push(StatusChange {
+                        iteration,
+                        link_type: LinkType::Pump,
+                        link_id: pump.id,
+                        from: actual,
+                        to: new_status,
+                    });
+                }
+            }
+        }
+
+        let count = changes.len();
+        for change in changes.into_iter() {
+            let k = match change.link_type {
+                LinkType::Pipe => self
+                    .network
+                    .pipes
+                    .as_ref()
+                    .and_then(|pipes| pipes.iter().position(|p| p.id == change.link_id)),
+                _ => self.network.pumps.as_ref().and_then(|pumps| {
+                    pumps
+                        .iter()
+                        .position(|p| p.id == change.link_id)
+                        .map(|i| i + self.pipe_count)
+                }),
+            };
+            if let Some(k) = k {
+                self.link_status[k] = change.to;
+                if change.to == LinkStatus::Open {
+                    flows_q[k] = reopen_flow;
+                }
+            }
+
+            log::debug!(
+                "iteration {} : {:?} {} {} -> {}",
+                change.iteration,
+                change.link_type,
+                change.link_id,
+                change.from,
+                change.to
+            );
+            self.status_changes.push(change);
+        }
+        count
+    }
+
+    fn link_sizes(&self) -> (usize, usize, usize) {
+        (self.pipe_count, self.pump_count, self.valve_count)
+    }
+
+    ///
+    /// Get network : fixed heads H0 (tanks then reservoirs) and nodal demands.
+    ///
+    fn get_network(&self) -> (Vec<f64>, Vec<f64>) {
+        let nt = self.tank_count;
+        let nr = self.reservoir_count;
+        let no = self.tank_count + self.reservoir_count;
+        log::debug!(
+            "junctions: {}, tanks: {}, reservoirs: {}, pipes: {}, pumps: {}, valves: {}",
+            self.junction_count,
+            self.tank_count,
+            self.reservoir_count,
+            self.pipe_count,
+            self.pump_count,
+            self.valve_count
+        );
+
+        // nodal demand
+        let mut q = vec![0.0f64; self.junction_count];
+        //H0 : reservoirs + tanks
+        let mut _h0 = vec![0.0f64; no];
+
+        //nodal demand
+        if let Some(junctions) = &self.network.junctions {
+            for i in 0..self.junction_count {
+                q[i] = junctions[i].demand
+                    * self.network.options.demand_multiplier
+                    * self.flow_unit_multiplayer;
+            }
+        }
+
+        //Fixed head : tanks then reservoirs
+        if let Some(tanks) = &self.network.tanks {
+            for k in 0..nt {
+                _h0[k] = self.units.to_si(Quantity::Head, tanks[k].head());
+            }
+        }
+        if let Some(reservoirs) = &self.network.reservoirs {
+            for k in 0..nr {
+                _h0[k + nt] = self.units.to_si(Quantity::Head, reservoirs[k].head);
+            }
+        }
+
+        log::trace!("H0 = {:?}", _h0);
+        log::trace!("q = {:?}", q);
+
+        (_h0, q)
+    }
+
+    fn check_convergence(actual: &[f64], previous: &[f64], objective: f64) -> (bool, f64) {
+        let sum_err = actual
+            .iter()
+            .zip(previous.iter())
+            .fold(0.0f64, |acc, (a, b)| acc + f64::abs(a - b));
+
+        let sumq = actual.iter().fold(0.0f64, |acc, q| acc + q.abs());
+
+        // absolute change when all the values are null (disconnected network parts)
+        let computed_err = if sumq > 0.0 { sum_err / sumq } else { sum_err };
+
+        if computed_err <= objective {
+            (true, computed_err)
+        } else {
+            (false, computed_err)
+        }
+    }
+
+    ///
+    /// Junctions without a path of open links to a tank or a reservoir. The changes from the
+    /// previous ones are logged.
+    ///
+    fn disconnected_junctions(&self, previous: &[bool]) -> Vec<bool> {
+        let nn = self.junction_count;
+        let links = &self.topology.links;
+        let node_of = |end: LinkEnd| match end {
+            LinkEnd::Junction(i) => Some(i),
+            LinkEnd::FixedHead(j) => Some(nn + j),
+            LinkEnd::Missing => None,
+        };
+
+        let mut adjacency: Vec<Vec<usize>> =
+            vec![Vec::new(); nn + self.tank_count + self.reservoir_count];
+        for (ends, status) in links.iter().zip(self.link_status.iter()) {
+            if *status == LinkStatus::Open
+                && let (Some(start), Some(end)) = (node_of(ends.0), node_of(ends.1))
+            {
+                adjacency[start].push(end);
+                adjacency[end].push(start);
+            }
+        }
+
+        let mut reached = vec![false; adjacency.len()];
+        reached[nn..].fill(true);
+        let mut stack: Vec<usize> = (nn..adjacency.len()).collect();
+        while let Some(node) = stack.pop() {
+            for &next in &adjacency[node] {
+                if !reached[next] {
+                    reached[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+
+        let disconnected: Vec<bool> = reached[..nn].iter().map(|r| !r).collect();
+        if disconnected.as_slice() != previous && disconnected.iter().any(|d| *d) {
+            log::warn!(
+                "{} junction(s) not connected to a tank or a reservoir : {:?}",
+                disconnected.iter().filter(|d| **d).count(),
+                self.network.junctions.as_ref().map(|junctions| {
+                    junctions
+                        .iter()
+                        .zip(disconnected.iter())
+                        .filter(|(_, d)| **d)
+                        .map(|(jn, _)| jn.id)
+                        .collect::<Vec<usize>>()
+                })
+            );
+        }
+        disconnected
+    }
+
+    ///
+    /// True if the node is a disconnected junction.
+    ///
+    fn is_disconnected(&self, node_id: usize) -> bool {
+        match self.topology.index.node(node_id) {
+            Some(NodeRef::Junction(i)) => self.disconnected.get(i).copied().unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    ///
+    /// True if a link has an end at a disconnected junction (no flow, out of the head system).
+    ///
+    fn is_cut_off(ends: &(LinkEnd, LinkEnd), disconnected: &[bool]) -> bool {
+        [ends.0, ends.1]
+            .iter()
+            .any(|end| matches!(end, LinkEnd::Junction(i) if disconnected[*i]))
+    }
+
+    ///
+    /// Flow balance residuals of the junctions : A21 x Q - outflows.
+    ///
+    fn balance_residuals(
+        links: &[(LinkEnd, LinkEnd)],
+        flows: &[f64],
+        outflows: &[f64],
+    ) -> Vec<f64> {
+        let mut residuals: Vec<f64> = outflows.iter().map(|q| -q).collect();
+        for (ends, q) in links.iter().zip(flows.iter()) {
+            if let LinkEnd::Junction(i) = ends.0 {
+                residuals[i] -= q;
+            }
+            if let LinkEnd::Junction(i) = ends.1 {
+                residuals[i] += q;
+            }
+        }
+        residuals
+    }
+
+    ///
+    /// Relative flow balance residual of the junctions : sum of |A21 x Q - outflows| / sum of |Q|.
+    ///
+    fn balance_error(flows: &[f64], residuals: &[f64]) -> f64 {
+        let sumq = flows.iter().fold(0.0f64, |acc, q| acc + q.abs());
+        let sum_residuals = residuals.iter().fold(0.0f64, |acc, r| acc + r.abs());
+        if sumq > 0.0 {
+            sum_residuals / sumq
+        } else {
+            sum_residuals
+        }
+    }
+
+    ///
+    /// Largest absolute balance residual (network flow unit) and the id of its junction.
+    ///
+    fn max_imbalance(&self, residuals: &[f64]) -> (f64, Option<usize>) {
+        let Some((i, r)) = residuals
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+        else {
+            return (0.0, None);
+        };
+        let junction_id = self
+            .network
+            .junctions
+            .as_ref()
+            .and_then(|junctions| junctions.get(i))
+            .map(|jn| jn.id);
+        (r.abs() / self.flow_unit_multiplayer, junction_id)
+    }
+
+    fn initilize_a_matrix(&self) -> Vec<f64> {
+        // let self.junction_count : usize = match self.junctions {
+        //     Some(junctions) => junctions.len(),
+        //     None => 0,
+        // };
+
+        // let nt = match self.tanks{
+        //     Some(tanks) => tanks.len(),
+        //     None => 0,
+        // };
+
+        // let nr = match self.reservoirs{
+        //     Some(reservoirs) => reservoirs.len(),
+        //     None => 0,
+        // };
+        let (npip, npmp, nvlv) = self.link_sizes();
+
+        let np = npip + npmp + nvlv;
+
+        let mut result_a = vec![0.0f64; np];
+
+        //let np = npip+npmp;
+        let rspipes = self.network.get_pipes_resistances().unwrap_or_default();
+
+        let qmax = match &self.network.junctions {
+            None => 0.0f64,
+            Some(junctions) => junctions.iter().fold(0.0f64, |acc, j| acc + j.demand),
+        };
+
+        // Pipes resistances
+        for i in 0..npip {
+            result_a[i] = rspipes[i] * qmax;
+        }
+
+        // Pumps resistances
+        match &self.network.pumps {
+            None => {}
+            Some(pumps) => {
+                for i in 0..npmp {
+                    // result_a[i+npip][i+npip]= network.pumps[i].alpha*qmax + network.pumps[i].beta + network.pumps[i].gamma/qmax;
+                    result_a[i + npip] = pumps[i].get_r_of_q(qmax, self.flow_unit_multiplayer);
+                }
+            }
+        };
+
+        // Valves resistances
+        match &self.network.valves {
+            None => {}
+            Some(valves) => {
+                for i in 0..nvlv {
+                    result_a[i + npip + npmp] = valves[i].get_rq(qmax);
+                }
+            }
+        };
+
+        result_a
+    }
+
+    fn update_matrices_a_b(
+        &self,
+        a: &mut [f64],
+        b: &mut Vec<f64>,
+        flowsq: &Vec<f64>,
+        deltaq: f64,
+        n: f64,
+    ) {
+        let mut _intpart: f64 = 0.0;
+        let mut _coef_a: f64 = 0.0;
+        let mut _coef_b: f64 = 0.0;
+
+        let (npip, npmp, nvlv) = self.link_sizes();
+
+        if let Some(pipes) = &self.network.pipes {
+            //update A & B matrices for pipes :
+            for i in 0..npip {
+                _intpart = flowsq[i].abs() / deltaq;
+
+                _coef_a = f64::trunc(_intpart) * deltaq;
+                _coef_b = _coef_a + deltaq;
+
+                //Updating A (eq13):
+                // A(i,i) = R(i)*(b(i)^n-a(i)^n)/(b(i)-a(i));
+
+                _intpart = (f64::powf(_coef_b, n) - f64::powf(_coef_a, n)) / (_coef_b - _coef_a);
+                let r = match self.link_status[i] {
+                    LinkStatus::Closed => CLOSED_LINK_RESISTANCE,
+                    LinkStatus::Open => pipes[i].get_r_of_q(flowsq[i]),
+                };
+                a[i] = r * _intpart;
+
+                //Updating B (eq14):
+
+                //B(i) = sign(Q(i))*R(i)*((b(i)^n-a(i)^n)/(b(i)-a(i))*a(i)-a(i)^n);
+                b[i] = -1.0
+                    * f64::signum(flowsq[i])
+                    * r
+                    * ((_intpart * _coef_a) - f64::powf(_coef_a, n));
+
+                // println!("P: {}, _intpart = {}, a = {}, b = {}, A = {}, B = {} ", i, _intpart, _coef_a, _coef_b, a[i][i], b[i]);
+            }
+        };
+
+        //update A & B matrices for pumps :
+        if let Some(pumps) = &self.network.pumps {
+            //update A & B matrices for pipes :
+            for i in 0..npmp {
+                let (x, y, z) = self.pump_curve_si(&pumps[i]);
+
+                let k = i + npip;
+                if self.link_status[k] == LinkStatus::Closed {
+                    a[k] = CLOSED_LINK_RESISTANCE;
+                    b[k] = 0.0;
+                    continue;
+                }
+                _intpart = flowsq[k].abs() / deltaq;
+
+                _coef_a = f64::trunc(_intpart) * deltaq;
+                _coef_b = _coef_a + deltaq;
+
+                //Updating A (eq36):
+                // A(i,i) = R(i)*(b(i)^n-a(i)^n)/(b(i)-a(i));
+
+                _intpart = (f64::powf(_coef_b, n) - f64::powf(_coef_a, n)) / (_coef_b - _coef_a);
+                a[k] = -(x * _intpart + y);
+
+                //Updating B (eq37):
+
+                //B(i) = sign(Q(i))*R(i)*((b(i)^n-a(i)^n)/(b(i)-a(i))*a(i)-a(i)^n);
+                b[k] = (x * (_intpart * _coef_a - _coef_a.powi(2))) - z;
+            }
+        }
+        /*
+                //println!("pump state {:?}, R = {}", network.pumps[0].state, network.pumps[0].get_rq(0.01));
+                match &self.network.pumps {
+                    None => {}
+                    Some(pumps) => {
+                        for i in 0..npmp {
+                            _intpart = flowsq[i + npip] / deltaq;
+                            _coef_a = f64::trunc(_intpart) * deltaq;
+                            _coef_b = _coef_a + deltaq; // f64::trunc(_intpart + f64::signum(flowsq[i + npip])) * deltaq;
+
+                            //Updating A (eq13):
+                            _intpart =
+                                (f64::powf(_coef_b, n) - f64::powf(_coef_a, n)) / (_coef_b - _coef_a);
+                            a[i + npip][i + npip] = f64::signum(flowsq[i + npip])
+                                * _intpart
+                                * pumps[i].get_rq(flowsq[i + npip]);
+
+                            //Updating B (eq14):
+                            b[i + npip] = -1.0
+                                * f64::signum(flowsq[i + npip])
+                                * pumps[i].get_rq(flowsq[i + npip])
+                                * ((_intpart * _coef_a) - f64::powf(_coef_a, n));
+                        }
+                    }
+                };
+        */
+        //update A & B matrices for valves :
+
+        let _k: usize = npip + npmp;
+
+        match &self.network.valves {
+            None => {}
+            Some(valves) => {
+                for i in 0..nvlv {
+                    _intpart = flowsq[i + _k] / deltaq;
+                    _coef_a = f64::trunc(_intpart) * deltaq;
+                    _coef_b = f64::trunc(_intpart + f64::signum(flowsq[i + _k])) * deltaq;
+
+                    //Updating A (eq13):
+                    _intpart =
+                        (f64::powf(_coef_b, n) - f64::powf(_coef_a, n)) / (_coef_b - _coef_a);
+                    a[i + _k] =
+                        f64::signum(flowsq[i + _k]) * _intpart * valves[i].get_rq(flowsq[i + _k]);
+
+                    //Updating B (eq14):
+                    b[i + _k] = -1.0
+                        * f64::signum(flowsq[i + _k])
+                        * valves[i].get_rq(flowsq[i + _k])
+                        * ((_intpart * _coef_a) - f64::powf(_coef_a, n));
+                }
+            }
+        }
+    }
+
+    ///
+    /// Newton method : tangents of the head losses at the actual flows, h(q) = A x q + B with
+    /// A = dh/dq(q0) and B = h(q0) - A x q0. The gradients are bounded below by SMALL_GRADIENT
+    /// (the tangent still goes through h(q0) : a pump at a null flow keeps its shutoff head).
+    ///
+    fn update_gradients_a_b(&self, a: &mut [f64], b: &mut [f64], flowsq: &[f64]) {
+        let n = self.n;
+        let (npip, npmp, _) = self.link_sizes();
+
+        if let Some(pipes) = &self.network.pipes {
+            // h = r x |q|^(n-1) x q
+            for (i, pipe) in pipes.iter().enumerate() {
+                let q = flowsq[i];
+                let r = match self.link_status[i] {
+                    LinkStatus::Closed => CLOSED_LINK_RESISTANCE,
+                    LinkStatus::Open => pipe.get_r_of_q(q),
+                };
+                let hgrad = n * r * q.abs().powf(n - 1.0);
+                let hloss = hgrad * q / n;
+                (a[i], b[i]) = Self::tangent(q, hloss, hgrad);
+            }
+        }
+
+        if let Some(pumps) = &self.network.pumps {
+            // h = -(alpha x q^2 + beta x q + gamma), or -P / (gamma_w x q) for constant power pumps.
+            for (i, pump) in pumps.iter().enumerate() {
+                let k = i + npip;
+                let q = flowsq[k];
+                if self.link_status[k] == LinkStatus::Closed {
+                    (a[k], b[k]) = (CLOSED_LINK_RESISTANCE, 0.0);
+                    continue;
+                }
+                let (hloss, hgrad) = if pump.alpha != 0.0 {
+                    let (x, y, z) = self.pump_curve_si(pump);
+                    (-(x * q * q + y * q + z), -(2.0 * x * q + y))
+                } else {
+                    let q = f64::max(q, FLOW_EPSILON);
+                    let head = pump.power_rating / (SPECIFIC_WEIGHT * q);
+                    (-head, head / q)
+                };
+                (a[k], b[k]) = Self::tangent(q, hloss, hgrad);
+            }
+        }
+
+        if let Some(valves) = &self.network.valves {
+            // h = k x |q|^n x q, as approximated by the multilinear method.
+            for (i, valve) in valves.iter().enumerate() {
+                let k = i + npip + npmp;
+                let q = flowsq[k];
+                let backflow = valve.valvetype == ValveType::FCV && q <= 0.0;
+                if self.link_status[k] == LinkStatus::Closed || backflow {
+                    (a[k], b[k]) = (CLOSED_LINK_RESISTANCE, 0.0);
+                    continue;
+                }
+                let hloss = valve.k_value * q.abs().powf(n) * q;
+                let hgrad = (n + 1.0) * valve.k_value * q.abs().powf(n);
+                (a[k], b[k]) = Self::tangent(q, hloss, hgrad);
+            }
+        }
+    }
+
+    ///
+    /// (A, B) of the tangent h = A x q + B of a head loss at q (gradient SMALL_GRADIENT at least).
+    ///
+    fn tangent(q: f64, hloss: f64, hgrad: f64) -> (f64, f64) {
+        if hgrad < SMALL_GRADIENT {
+            (SMALL_GRADIENT, hloss - SMALL_GRADIENT * q)
+        } else {
+            (hgrad, hloss - hgrad * q)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::link::pipe::PipeBuilder;
+    use crate::network::link::pump::PumpBuilder;
+    use crate::network::{
+        FlowUnits, JunctionBuilder, NetworkBuilder, OptionsBuilder, ReservoirBuilder,
+    };
+
+    ///
+    /// Reservoir 100 (head 50 m) supplying the junctions (id, demand in l/s) at elevation 0
+    /// through 500 m long pipes (id, start, end) of 200 mm.
+    ///
+    fn network(
+        junctions: &[(usize, f64)],
+        pipes: &[(usize, usize, usize)],
+        demand_model: DemandModel,
+    ) -> Network {
+        let junctions = junctions
+            .iter()
+            .map(|&(id, demand)| JunctionBuilder::new().set_id(id).set_demand(demand).build())
+            .collect();
+        let pipes = pipes
+            .iter()
+            .map(|&(id, start, end)| {
+                PipeBuilder::new()
+                    .set_id(id)
+                    .set_start(start)
+                    .set_end(end)
+                    .set_length(500.0)
+                    .set_diameter(200.0)
+                    .set_roughness(100.0)
+                    .set_status(LinkStatus::Open)
+                    .build()
+            })
+            .collect();
+        let options = OptionsBuilder::new()
+            .set_flow_unit(FlowUnits::Lps)
+            .set_demand_model(demand_model)
+            .build();
+        NetworkBuilder::new()
+            .set_options(options)
+            .set_junctions(Some(junctions))
+            .set_reservoirs(Some(vec![
+                ReservoirBuilder::new().set_id(100).set_head(50.0).build(),
+            ]))
+            .set_pipes(Some(pipes))
+            .build()
+    }
+
+    #[test]
+    fn keeps_the_negative_demands() {
+        let pda = DemandModel::Pda {
+            minimum_pressure: 0.0,
+            required_pressure: 20.0,
+            exponent: 0.5,
+        };
+        for demand_model in [DemandModel::Dda, pda] {
+            // the junction 2 injects 4 l/s into the network
+            let wdn = network(
+                &[(1, 10.0), (2, -4.0)],
+                &[(1, 100, 1), (2, 1, 2)],
+                demand_model,
+            );
+            let results = Solver::new(&wdn, Some(1e-9)).compute().unwrap();
+            assert_eq!(results.node(1).unwrap().demand, 10.0);
+            assert_eq!(results.node(2).unwrap().demand, -4.0);
+            assert!((results.flow(2).unwrap() + 4.0).abs() < 1e-6);
+        }
+
+        let wdn = network(
+            &[(1, 10.0), (2, -4.0)],
+            &[(1, 100, 1), (2, 1, 2)],
+            DemandModel::Dda,
+        );
+        let results = Solver::new(&wdn, Some(1e-9)).compute().unwrap();
+        assert!((results.flow(1).unwrap() - 6.0).abs() < 1e-6);
+        assert!(results.mass_balance.max_imbalance.abs() < 1e-6);
+    }
+
+    ///
+    /// Reservoir 100 (head 10 m) -> pump 10 (shutoff head 50 m) -> junction 1 -> pipe 1 -> junction 2 (10 l/s).
+    ///
+    fn pumped_network() -> Network {
+        let mut wdn = network(&[(1, 0.0), (2, 10.0)], &[(1, 1, 2)], DemandModel::Dda);
+        wdn.reservoirs = Some(vec![
+            ReservoirBuilder::new().set_id(100).set_head(10.0).build(),
+        ]);
+        wdn.pumps = Some(vec![
+            PumpBuilder::new()
+                .set_id(10)
+                .set_start(100)
+                .set_end(1)
+                .set_alpha(-0.1)
+                .set_beta(0.0)
+                .set_gamma(50.0)
+                .set_status(LinkStatus::Open)
+                .set_flow_unit(FlowUnits::Lps)
+                .build(),
+        ]);
+        wdn.reindex();
+        wdn
+    }
+
+    #[test]
+    fn keeps_the_shutoff_head_of_a_pump_at_null_flow() {
+        let wdn = pumped_network();
+        let mut solver = Solver::new(&wdn, None);
+        solver.link_status = solver.initial_link_statuses();
+        let (mut a, mut b) = (vec![0.0f64; 2], vec![0.0f64; 2]);
+        solver.update_gradients_a_b(&mut a, &mut b, &[0.0, 0.0]);
+        // the pump head loss at q = 0 is minus its shutoff head
+        assert_eq!(a[1], SMALL_GRADIENT);
+        assert_eq!(b[1], -50.0);
+    }
+
+    #[test]
+    fn starts_a_pump_from_a_null_flow() {
+        let wdn = pumped_network();
+        let mut reference = Solver::new(&wdn, Some(1e-9));
+        let expected = reference.compute().unwrap();
+
+        let mut solver = Solver::new(&wdn, Some(1e-9));
+        solver.set_method(Method::Newton { relaxation: 1.0 });
+        solver.set_initial_flows(InitialFlows::Flows(vec![0.0, 0.0]));
+        let results = solver.compute().unwrap();
+
+        assert!((results.flow(10).unwrap() - 10.0).abs() < 1e-6);
+        // head gain of the pump at 10 l/s : 50 - 0.1 x 10^2 = 40 m
+        assert!((results.head(1).unwrap() - 50.0).abs() < 1e-6);
+        for id in [1, 2] {
+            assert!((results.head(id).unwrap() - expected.head(id).unwrap()).abs() < 1e-6);
+        }
+    }
+}
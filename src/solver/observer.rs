@@ -0,0 +1,61 @@
+use super::StatusChange;
+
+///
+/// State of the computation at the end of an iteration.
+///
+#[derive(Debug, Clone, Copy)]
+pub struct IterationProgress {
+    ///
+    /// Iteration number, from 0.
+    ///
+    pub iteration: usize,
+
+    ///
+    /// Relative change of the flows : sum of |Q - Q previous| / sum of |Q|.
+    ///
+    pub flow_change: f64,
+
+    ///
+    /// Relative change of the junction heads : sum of |H - H previous| / sum of |H|.
+    ///
+    pub head_change: f64,
+
+    ///
+    /// Relative flow balance residual of the junctions (see SolverReport::balance_error).
+    ///
+    pub balance_error: f64,
+
+    ///
+    /// Largest flow imbalance of a junction, in the network flow unit, and the junction id.
+    ///
+    pub max_imbalance: f64,
+    pub max_imbalance_junction: Option<usize>,
+
+    ///
+    /// Number of link status changes made at this iteration.
+    ///
+    pub status_changes: usize,
+}
+
+///
+/// Hook on the iterations of a computation (see Solver::compute_observed) : progress monitoring,
+/// convergence studies, early diagnostics. The default methods do nothing.
+///
+pub trait SolverObserver {
+    ///
+    /// Called at the end of each iteration.
+    ///
+    fn on_iteration(&mut self, _progress: &IterationProgress) {}
+
+    ///
+    /// Called for each link status change made by the solver (check valves, pumps).
+    ///
+    fn on_status_change(&mut self, _change: &StatusChange) {}
+}
+
+///
+/// Observer of the computations without observer.
+///
+pub(super) struct NoObserver;
+
+impl SolverObserver for NoObserver {}
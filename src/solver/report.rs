@@ -0,0 +1,189 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use super::Method;
+use crate::network::ValidationReport;
+
+///
+/// Stopping state of a computation.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolverStatus {
+    ///
+    /// The flows and heads changes are under the objective error and the link statuses are stable.
+    ///
+    Converged,
+
+    ///
+    /// The maximum number of iterations is reached : the results are those of the last iteration.
+    ///
+    MaxIterations,
+}
+
+impl Display for SolverStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Converged => write!(f, "Converged"),
+            Self::MaxIterations => write!(f, "Maximum iterations reached"),
+        }
+    }
+}
+
+///
+/// Stopping rule that ended a computation.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopCriterion {
+    ///
+    /// The changes of the flows and heads and the flow balance residual are under the accuracy.
+    ///
+    Accuracy,
+
+    ///
+    /// Options::trials is reached (Unbalanced::StopIter).
+    ///
+    Trials,
+
+    ///
+    /// Options::trials plus the extra trials of Unbalanced::ContinueIter are reached.
+    ///
+    ExtraTrials,
+}
+
+impl Display for StopCriterion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Accuracy => write!(f, "accuracy"),
+            Self::Trials => write!(f, "trials"),
+            Self::ExtraTrials => write!(f, "extra trials"),
+        }
+    }
+}
+
+///
+/// Summary of a completed computation (see Solver::compute).
+///
+#[derive(Debug, Clone)]
+pub struct SolverReport {
+    pub status: SolverStatus,
+    pub stopped_by: StopCriterion,
+    pub method: Method,
+    pub iterations: usize,
+    pub accuracy: f64,
+
+    ///
+    /// Relative changes of the flows and of the heads at the last iteration.
+    ///
+    pub flow_error: f64,
+    pub head_error: f64,
+
+    ///
+    /// Relative flow balance residual of the junctions at the last iteration.
+    ///
+    pub balance_error: f64,
+
+    ///
+    /// Number of link status changes (check valves, pumps) made during the iterations.
+    ///
+    pub status_changes: usize,
+    pub time: Duration,
+}
+
+impl SolverReport {
+    pub fn is_converged(&self) -> bool {
+        self.status == SolverStatus::Converged
+    }
+}
+
+impl Display for SolverReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} ({:?}), stopped by the {} after {} iteration(s)",
+            self.status, self.method, self.stopped_by, self.iterations
+        )?;
+        writeln!(
+            f,
+            "(DQ, DH, balance) = ({:e}, {:e}, {:e}), accuracy : {:e}, status changes : {}",
+            self.flow_error,
+            self.head_error,
+            self.balance_error,
+            self.accuracy,
+            self.status_changes
+        )?;
+        write!(f, "Time analysis : {:?}", self.time)
+    }
+}
+
+///
+/// Failure of a computation. The network results are left unchanged.
+///
+#[derive(Debug, Clone)]
+pub enum SolverError {
+    ///
+    /// The network has validation errors (see Network::validate).
+    ///
+    InvalidNetwork(ValidationReport),
+
+    ///
+    /// No junction or no link to compute.
+    ///
+    EmptyNetwork { junctions: usize, links: usize },
+
+    ///
+    /// The head system is singular (null pivot), e.g. junctions connected only through closed
+    /// or zero resistance links.
+    ///
+    SingularMatrix {
+        iteration: usize,
+        junction_id: usize,
+    },
+
+    ///
+    /// The size of the head system differs from its analysed structure.
+    ///
+    DimensionMismatch { expected: usize, found: usize },
+
+    ///
+    /// A computed head or flow is not finite (NaN or infinite).
+    ///
+    NotFinite { iteration: usize },
+}
+
+impl Display for SolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidNetwork(report) => write!(
+                f,
+                "invalid network ({} error(s)) :\n{}",
+                report.errors().count(),
+                report
+            ),
+            Self::EmptyNetwork { junctions, links } => write!(
+                f,
+                "nothing to compute : {} junction(s), {} link(s)",
+                junctions, links
+            ),
+            Self::SingularMatrix {
+                iteration,
+                junction_id,
+            } => write!(
+                f,
+                "singular head system at the junction {} (iteration {})",
+                junction_id, iteration
+            ),
+            Self::DimensionMismatch { expected, found } => {
+                write!(f, "head system of size {} instead of {}", found, expected)
+            }
+            Self::NotFinite { iteration } => {
+                write!(
+                    f,
+                    "non finite heads or flows at the iteration {}",
+                    iteration
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use super::{MassBalance, SolverReport};
+use crate::network::FlowUnits;
+use crate::network::link::{LinkStatus, LinkType};
+use crate::network::node::NodeType;
+
+///
+/// Computed state of a node, in the network units.
+///
+#[derive(Debug, Clone)]
+pub struct NodeResult {
+    pub id: usize,
+    pub node_type: NodeType,
+    pub head: f64,
+
+    ///
+    /// Pressure of a junction, level of a tank, 0 for a reservoir.
+    ///
+    pub pressure: f64,
+
+    ///
+    /// Delivered demand of a junction (lower than its demand under pressure driven analysis),
+    /// net inflow of a tank or of a reservoir (negative when it supplies the network).
+    ///
+    pub demand: f64,
+
+    ///
+    /// Emitter outflow of a junction.
+    ///
+    pub emitter_flow: f64,
+
+    ///
+    /// A junction without a path of open links to a tank or a reservoir : its head is its
+    /// elevation, its delivered demand and the flows of its links are 0.
+    ///
+    pub disconnected: bool,
+}
+
+///
+/// Computed state of a link, in the network units.
+///
+#[derive(Debug, Clone)]
+pub struct LinkResult {
+    pub id: usize,
+    pub link_type: LinkType,
+    pub flow: f64,
+
+    ///
+    /// Velocity in the network velocity unit, m/s or ft/s (pipes and valves, 0 for pumps).
+    ///
+    pub velocity: f64,
+
+    ///
+    /// Head of the start node minus head of the end node (negative for the head gain of a pump).
+    ///
+    pub headloss: f64,
+
+    ///
+    /// Head loss per 1000 length units (m/km, ft/kft) of a pipe, 0 for pumps and valves.
+    ///
+    pub unit_headloss: f64,
+
+    ///
+    /// Darcy-Weisbach friction factor equivalent to the head loss of a pipe,
+    /// 0 for pumps, valves and links without flow.
+    ///
+    pub friction_factor: f64,
+
+    ///
+    /// Actual status (check valves and pumps may be closed by the solver).
+    ///
+    pub status: LinkStatus,
+
+    ///
+    /// Leakage outflow along a pipe.
+    ///
+    pub leakage_flow: f64,
+}
+
+///
+/// Results of a computation (see Solver::compute). The network is not changed : the results of
+/// several computations can be kept and compared side by side.
+///
+/// The nodes are ordered as the network elements (junctions, tanks then reservoirs),
+/// as well as the links (pipes, pumps then valves).
+///
+#[derive(Debug, Clone)]
+pub struct HydraulicResults {
+    pub report: SolverReport,
+    pub flow_unit: FlowUnits,
+    pub nodes: Vec<NodeResult>,
+    pub links: Vec<LinkResult>,
+
+    ///
+    /// Water balance of the computation : supplies, outflows, storage change and continuity.
+    ///
+    pub mass_balance: MassBalance,
+    node_positions: HashMap<usize, usize>,
+    link_positions: HashMap<usize, usize>,
+}
+
+impl HydraulicResults {
+    pub fn new(
+        report: SolverReport,
+        flow_unit: FlowUnits,
+        nodes: Vec<NodeResult>,
+        links: Vec<LinkResult>,
+        mass_balance: MassBalance,
+    ) -> Self {
+        let node_positions = nodes.iter().enumerate().map(|(i, n)| (n.id, i)).collect();
+        let link_positions = links.iter().enumerate().map(|(k, l)| (l.id, k)).collect();
+        HydraulicResults {
+            report,
+            flow_unit,
+            nodes,
+            links,
+            mass_balance,
+            node_positions,
+            link_positions,
+        }
+    }
+
+    ///
+    /// Results of the node (junction, tank or reservoir) with the given id.
+    ///
+    pub fn node(&self, id: usize) -> Option<&NodeResult> {
+        self.nodes.get(*self.node_positions.get(&id)?)
+    }
+
+    ///
+    /// Results of the link (pipe, pump or valve) with the given id.
+    ///
+    pub fn link(&self, id: usize) -> Option<&LinkResult> {
+        self.links.get(*self.link_positions.get(&id)?)
+    }
+
+    ///
+    /// Ids of the disconnected junctions (see NodeResult::disconnected).
+    ///
+    pub fn disconnected_nodes(&self) -> Vec<usize> {
+        self.nodes
+            .iter()
+            .filter(|n| n.disconnected)
+            .map(|n| n.id)
+            .collect()
+    }
+
+    pub fn head(&self, node_id: usize) -> Option<f64> {
+        self.node(node_id).map(|n| n.head)
+    }
+
+    pub fn pressure(&self, node_id: usize) -> Option<f64> {
+        self.node(node_id).map(|n| n.pressure)
+    }
+
+    pub fn flow(&self, link_id: usize) -> Option<f64> {
+        self.link(link_id).map(|l| l.flow)
+    }
+}
@@ -0,0 +1,189 @@
+//!
+//! Repeated computations of a network whose topology does not change (design optimization,
+//! calibration, scenarios) : the structure of the head system is analysed once and each
+//! computation starts from the flows of the previous one.
+//!
+use std::sync::Arc;
+
+use super::observer::NoObserver;
+use super::topology::Topology;
+use super::{
+    HydraulicResults, InitialFlows, Method, Solver, SolverError, SolverObserver, SolverStatus,
+};
+use crate::network::Network;
+
+pub struct SolverSession {
+    network: Network,
+    topology: Arc<Topology>,
+    method: Method,
+    m: f64,
+    objective_error: Option<f64>,
+    warm_start: bool,
+
+    ///
+    /// Initial flows of the computations without warm start.
+    ///
+    initial_flows: InitialFlows,
+
+    ///
+    /// Flows of the links (network flow unit) of the last converged computation.
+    ///
+    flows: Option<Vec<f64>>,
+}
+
+impl SolverSession {
+    ///
+    /// Session on the network : its topology is analysed.
+    ///
+    pub fn new(network: Network) -> Self {
+        let topology = Arc::new(Topology::analyse(&network));
+        SolverSession {
+            network,
+            topology,
+            method: Method::default(),
+            m: 100.0,
+            objective_error: None,
+            warm_start: true,
+            initial_flows: InitialFlows::default(),
+            flows: None,
+        }
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    ///
+    /// Network to update between two computations (diameters, roughnesses, statuses, demands, ...).
+    /// A change of the nodes or of the links ends is detected by the next computation :
+    /// the topology is analysed again and the computation starts without warm start.
+    ///
+    pub fn network_mut(&mut self) -> &mut Network {
+        &mut self.network
+    }
+
+    pub fn into_network(self) -> Network {
+        self.network
+    }
+
+    ///
+    /// Method of the computations (see Solver::set_method).
+    ///
+    pub fn set_method(&mut self, method: Method) {
+        self.method = method;
+    }
+
+    ///
+    /// m-value of the multilinear method (see Solver::set_m_parameter).
+    ///
+    pub fn set_m_parameter(&mut self, m_value: f64) {
+        self.m = m_value;
+    }
+
+    ///
+    /// Accuracy overriding the network options (None : Options::accuracy).
+    ///
+    pub fn set_objective_error(&mut self, objective_error: Option<f64>) {
+        self.objective_error = objective_error;
+    }
+
+    ///
+    /// Start each computation from the flows of the last converged one (default : true).
+    ///
+    pub fn set_warm_start(&mut self, warm_start: bool) {
+        self.warm_start = warm_start;
+        if !warm_start {
+            self.flows = None;
+        }
+    }
+
+    ///
+    /// Initial flows of the computations without flows of a previous one (see Solver::set_initial_flows).
+    ///
+    pub fn set_initial_flows(&mut self, initial_flows: InitialFlows) {
+        self.initial_flows = initial_flows;
+    }
+
+    ///
+    /// Solve the network in its actual state.
+    ///
+    pub fn solve(&mut self) -> Result<HydraulicResults, SolverError> {
+        self.solve_observed(&mut NoObserver)
+    }
+
+    ///
+    /// Solve the network in its actual state, reporting the iterations to the observer.
+    ///
+    pub fn solve_observed(
+        &mut self,
+        observer: &mut dyn SolverObserver,
+    ) -> Result<HydraulicResults, SolverError> {
+        if !self.topology.matches(&self.network) {
+            self.topology = Arc::new(Topology::analyse(&self.network));
+            self.flows = None;
+        }
+
+        let mut solver = Solver::with_topology(
+            &self.network,
+            self.objective_error,
+            Arc::clone(&self.topology),
+        );
+        solver.set_method(self.method);
+        solver.set_m_parameter(self.m);
+        solver.set_initial_flows(match &self.flows {
+            Some(flows) if self.warm_start => InitialFlows::Flows(flows.clone()),
+            _ => self.initial_flows.clone(),
+        });
+
+        let results = solver.compute_observed(observer)?;
+        if self.warm_start && results.report.status == SolverStatus::Converged {
+            self.flows = Some(results.links.iter().map(|link| link.flow).collect());
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_the_changed_network_as_a_fresh_solver() {
+        let file = concat!(env!("CARGO_MANIFEST_DIR"), "/src/bin/data/Hanoi.inp");
+        let mut session = SolverSession::new(Network::read_from_file(file).unwrap());
+        session.set_objective_error(Some(1e-9));
+        session.solve().unwrap();
+
+        for trial in 1..6 {
+            let pipes = session.network_mut().pipes.as_mut().unwrap();
+            for (k, pipe) in pipes.iter_mut().enumerate() {
+                if (k + trial) % 3 == 0 {
+                    pipe.diameter *= if trial % 2 == 0 { 1.25 } else { 0.8 };
+                }
+            }
+            let reused = session.solve().unwrap();
+            let fresh = Solver::new(session.network(), Some(1e-9))
+                .compute()
+                .unwrap();
+
+            assert_eq!(reused.nodes.len(), fresh.nodes.len());
+            for (a, b) in reused.nodes.iter().zip(fresh.nodes.iter()) {
+                assert_eq!(a.id, b.id);
+                assert!(
+                    (a.head - b.head).abs() < 1e-6,
+                    "trial {} node {}",
+                    trial,
+                    a.id
+                );
+            }
+            for (a, b) in reused.links.iter().zip(fresh.links.iter()) {
+                assert!(
+                    (a.flow - b.flow).abs() < 1e-6,
+                    "trial {} link {}",
+                    trial,
+                    a.id
+                );
+            }
+        }
+    }
+}
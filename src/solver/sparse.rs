@@ -0,0 +1,594 @@
+//!
+//! Sparse symmetric matrices and their LDLt factorization (square root free Cholesky)
+//! with a minimum degree fill-reducing ordering.
+//!
+//! The ordering and the structure of the factor (symbolic factorization) depend only on the
+//! matrix pattern : they are computed once, the numeric factorization is made at each iteration.
+//!
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+///
+/// Symmetric matrix : the diagonal and the strictly lower part as compressed sparse columns.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct SymmetricMatrix {
+    n: usize,
+    diagonal: Vec<f64>,
+    col_ptr: Vec<usize>,
+    row_idx: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl SymmetricMatrix {
+    ///
+    /// Build a n x n matrix with the off-diagonal entries (i, j), i != j (duplicated entries are merged).
+    /// Return the matrix and the value slot of each entry (see add_to_slot).
+    ///
+    pub fn with_pattern(n: usize, entries: &[(usize, usize)]) -> (Self, Vec<usize>) {
+        // (column, row) of the lower part
+        let mut lower: Vec<(usize, usize)> = entries
+            .iter()
+            .map(|&(i, j)| (usize::min(i, j), usize::max(i, j)))
+            .collect();
+        lower.sort_unstable();
+        lower.dedup();
+
+        let mut col_ptr = vec![0usize; n + 1];
+        for (col, _) in lower.iter() {
+            col_ptr[col + 1] += 1;
+        }
+        for j in 0..n {
+            col_ptr[j + 1] += col_ptr[j];
+        }
+        let row_idx: Vec<usize> = lower.iter().map(|(_, row)| *row).collect();
+
+        let slots = entries
+            .iter()
+            .map(|&(i, j)| {
+                let key = (usize::min(i, j), usize::max(i, j));
+                lower.binary_search(&key).expect("entry of the pattern")
+            })
+            .collect();
+
+        let matrix = SymmetricMatrix {
+            n,
+            diagonal: vec![0.0f64; n],
+            col_ptr,
+            values: vec![0.0f64; row_idx.len()],
+            row_idx,
+        };
+        (matrix, slots)
+    }
+
+    ///
+    /// Set all the values to zero (the pattern is kept).
+    ///
+    pub fn clear(&mut self) {
+        self.diagonal.iter_mut().for_each(|v| *v = 0.0);
+        self.values.iter_mut().for_each(|v| *v = 0.0);
+    }
+
+    pub fn add_to_diagonal(&mut self, i: usize, value: f64) {
+        self.diagonal[i] += value;
+    }
+
+    ///
+    /// Add a value to an off-diagonal entry (and to its symmetric).
+    ///
+    pub fn add_to_slot(&mut self, slot: usize, value: f64) {
+        self.values[slot] += value;
+    }
+}
+
+///
+/// Fill-reducing ordering and structure of the LDLt factor of a symmetric matrix pattern.
+/// All the indices of the factor are in the permuted order.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct SymbolicFactor {
+    n: usize,
+
+    ///
+    /// perm[k] : index in the matrix of the k-th pivot.
+    ///
+    perm: Vec<usize>,
+
+    ///
+    /// Strictly lower part of L as compressed sparse columns (sorted rows).
+    ///
+    l_col_ptr: Vec<usize>,
+    l_row_idx: Vec<usize>,
+
+    ///
+    /// Rows of L : (column k, position in l) of the entries L(j, k), k < j.
+    ///
+    l_row_ptr: Vec<usize>,
+    l_row_entries: Vec<(usize, usize)>,
+
+    ///
+    /// Entries of the matrix for each pivot j : (diagonal index), then (row i > j, value slot).
+    ///
+    a_col_ptr: Vec<usize>,
+    a_entries: Vec<(usize, usize)>,
+}
+
+impl SymbolicFactor {
+    ///
+    /// Minimum degree ordering and symbolic factorization of the matrix pattern.
+    ///
+    /// The elimination graph is updated explicitly : when a node is eliminated, its neighbors
+    /// become a clique, and these neighbors are the structure of its column in L.
+    ///
+    pub fn analyse(matrix: &SymmetricMatrix) -> Self {
+        let n = matrix.n;
+
+        // adjacency of the matrix graph (sorted) and value slots
+        let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut slots: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n];
+        for j in 0..n {
+            for p in matrix.col_ptr[j]..matrix.col_ptr[j + 1] {
+                let i = matrix.row_idx[p];
+                adjacency[i].push(j);
+                adjacency[j].push(i);
+                slots[i].push((j, p));
+                slots[j].push((i, p));
+            }
+        }
+        for neighbors in adjacency.iter_mut() {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+
+        let (perm, columns) = Self::minimum_degree(adjacency);
+
+        let mut iperm = vec![0usize; n];
+        for (k, &v) in perm.iter().enumerate() {
+            iperm[v] = k;
+        }
+
+        // L structure in the permuted order
+        let mut l_col_ptr = vec![0usize; n + 1];
+        let mut l_row_idx: Vec<usize> = Vec::new();
+        for (k, column) in columns.iter().enumerate() {
+            let mut rows: Vec<usize> = column.iter().map(|&u| iperm[u]).collect();
+            rows.sort_unstable();
+            l_row_idx.extend(rows);
+            l_col_ptr[k + 1] = l_row_idx.len();
+        }
+
+        // rows of L
+        let mut l_row_ptr = vec![0usize; n + 1];
+        for &i in l_row_idx.iter() {
+            l_row_ptr[i + 1] += 1;
+        }
+        for j in 0..n {
+            l_row_ptr[j + 1] += l_row_ptr[j];
+        }
+        let mut next = l_row_ptr.clone();
+        let mut l_row_entries = vec![(0usize, 0usize); l_row_idx.len()];
+        let mut k = 0;
+        for (p, &i) in l_row_idx.iter().enumerate() {
+            while p >= l_col_ptr[k + 1] {
+                k += 1;
+            }
+            l_row_entries[next[i]] = (k, p);
+            next[i] += 1;
+        }
+
+        // matrix entries of each pivot column (rows after the pivot)
+        let mut a_col_ptr = vec![0usize; n + 1];
+        let mut a_entries: Vec<(usize, usize)> = Vec::new();
+        for (k, &v) in perm.iter().enumerate() {
+            for &(u, slot) in slots[v].iter() {
+                if iperm[u] > k {
+                    a_entries.push((iperm[u], slot));
+                }
+            }
+            a_col_ptr[k + 1] = a_entries.len();
+        }
+
+        SymbolicFactor {
+            n,
+            perm,
+            l_col_ptr,
+            l_row_idx,
+            l_row_ptr,
+            l_row_entries,
+            a_col_ptr,
+            a_entries,
+        }
+    }
+
+    ///
+    /// Approximate minimum degree ordering on the quotient graph : an eliminated node becomes an
+    /// element (the clique of its neighbors, not built explicitly), elements adjacent to the pivot
+    /// are absorbed. The degree of a node is bounded by |A(i)| + sum of |L(e) \ L(p)|.
+    ///
+    /// Return the pivots order and, for each pivot, its neighbors when eliminated (structure of L).
+    ///
+    fn minimum_degree(mut variables: Vec<Vec<usize>>) -> (Vec<usize>, Vec<Vec<usize>>) {
+        let n = variables.len();
+        let mut elements: Vec<Vec<usize>> = vec![Vec::new(); n]; // E(i) : adjacent elements
+        let mut members: Vec<Vec<usize>> = vec![Vec::new(); n]; // L(e) : nodes of the element e
+        let mut eliminated = vec![false; n];
+        let mut absorbed = vec![false; n];
+        let mut degree: Vec<usize> = variables.iter().map(|v| v.len()).collect();
+
+        // marks with stamps, w(e) = |L(e) \ L(p)|
+        let mut mark = vec![0usize; n];
+        let mut stamp = 0usize;
+        let mut external = vec![usize::MAX; n];
+
+        let mut heap: BinaryHeap<Reverse<(usize, usize)>> =
+            (0..n).map(|v| Reverse((degree[v], v))).collect();
+        let mut perm: Vec<usize> = Vec::with_capacity(n);
+        let mut columns: Vec<Vec<usize>> = Vec::with_capacity(n);
+
+        while let Some(Reverse((d, p))) = heap.pop() {
+            if eliminated[p] || d != degree[p] {
+                continue;
+            }
+            eliminated[p] = true;
+            perm.push(p);
+
+            // L(p) = A(p) U L(e) for e in E(p), minus p ; the elements of E(p) are absorbed.
+            stamp += 1;
+            mark[p] = stamp;
+            let mut pattern: Vec<usize> = Vec::new();
+            for &v in variables[p].iter() {
+                if !eliminated[v] && mark[v] != stamp {
+                    mark[v] = stamp;
+                    pattern.push(v);
+                }
+            }
+            for &e in elements[p].iter() {
+                if absorbed[e] {
+                    continue;
+                }
+                for &v in members[e].iter() {
+                    if !eliminated[v] && mark[v] != stamp {
+                        mark[v] = stamp;
+                        pattern.push(v);
+                    }
+                }
+                absorbed[e] = true;
+                members[e] = Vec::new();
+            }
+            variables[p] = Vec::new();
+            elements[p] = Vec::new();
+
+            // |L(e) \ L(p)| of the elements adjacent to the nodes of L(p)
+            let mut touched: Vec<usize> = Vec::new();
+            for &i in pattern.iter() {
+                for &e in elements[i].iter() {
+                    if absorbed[e] {
+                        continue;
+                    }
+                    if external[e] == usize::MAX {
+                        external[e] = members[e].len();
+                        touched.push(e);
+                    }
+                    external[e] -= 1;
+                }
+            }
+
+            for &i in pattern.iter() {
+                elements[i].retain(|&e| !absorbed[e]);
+                elements[i].push(p);
+                // edges covered by the element p are pruned
+                variables[i].retain(|&v| !eliminated[v] && mark[v] != stamp);
+
+                let mut bound = variables[i].len() + pattern.len() - 1;
+                for &e in elements[i].iter() {
+                    if e != p {
+                        bound += external[e];
+                    }
+                }
+                degree[i] = usize::min(bound, n - perm.len() - 1);
+                heap.push(Reverse((degree[i], i)));
+            }
+            for e in touched {
+                external[e] = usize::MAX;
+            }
+
+            members[p] = pattern.clone();
+            columns.push(pattern);
+        }
+        (perm, columns)
+    }
+
+    ///
+    /// Number of non-zero entries of L (strictly lower part).
+    ///
+    pub fn factor_size(&self) -> usize {
+        self.l_row_idx.len()
+    }
+
+    ///
+    /// Numeric LDLt factorization of a matrix with the analysed pattern.
+    ///
+    pub fn factorize(&self, matrix: &SymmetricMatrix) -> Result<LdlFactor<'_>, FactorError> {
+        if matrix.n != self.n {
+            return Err(FactorError::SizeMismatch {
+                expected: self.n,
+                found: matrix.n,
+            });
+        }
+        let n = self.n;
+        let mut l = vec![0.0f64; self.l_row_idx.len()];
+        let mut d = vec![0.0f64; n];
+        let mut work = vec![0.0f64; n];
+
+        for j in 0..n {
+            // scatter the column j of the permuted matrix
+            work[j] = matrix.diagonal[self.perm[j]];
+            for &(i, slot) in &self.a_entries[self.a_col_ptr[j]..self.a_col_ptr[j + 1]] {
+                work[i] += matrix.values[slot];
+            }
+
+            // updates from the previous columns k with L(j, k) != 0
+            for &(k, pos) in &self.l_row_entries[self.l_row_ptr[j]..self.l_row_ptr[j + 1]] {
+                let ljk_dk = l[pos] * d[k];
+                // rows of the column k are sorted : the rows >= j start at L(j, k)
+                for p in pos..self.l_col_ptr[k + 1] {
+                    work[self.l_row_idx[p]] -= l[p] * ljk_dk;
+                }
+            }
+
+            let pivot = work[j];
+            work[j] = 0.0;
+            if pivot == 0.0 {
+                return Err(FactorError::NullPivot(self.perm[j]));
+            }
+            d[j] = pivot;
+            let (from, to) = (self.l_col_ptr[j], self.l_col_ptr[j + 1]);
+            for (lij, &i) in l[from..to].iter_mut().zip(&self.l_row_idx[from..to]) {
+                *lij = work[i] / pivot;
+                work[i] = 0.0;
+            }
+        }
+
+        Ok(LdlFactor {
+            symbolic: self,
+            l,
+            d,
+        })
+    }
+}
+
+///
+/// Failure of a numeric factorization.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FactorError {
+    ///
+    /// The matrix size differs from the analysed pattern.
+    ///
+    SizeMismatch { expected: usize, found: usize },
+
+    ///
+    /// Null pivot at a row of the (unpermuted) matrix.
+    ///
+    NullPivot(usize),
+}
+
+///
+/// Numeric LDLt factor of a symmetric matrix.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct LdlFactor<'a> {
+    symbolic: &'a SymbolicFactor,
+    l: Vec<f64>,
+    d: Vec<f64>,
+}
+
+impl LdlFactor<'_> {
+    ///
+    /// Solve M x = b.
+    ///
+    pub fn solve(&self, b: &[f64]) -> Vec<f64> {
+        let s = self.symbolic;
+        let mut z: Vec<f64> = s.perm.iter().map(|&v| b[v]).collect();
+
+        // L y = P b
+        for j in 0..s.n {
+            let zj = z[j];
+            if zj != 0.0 {
+                for p in s.l_col_ptr[j]..s.l_col_ptr[j + 1] {
+                    z[s.l_row_idx[p]] -= self.l[p] * zj;
+                }
+            }
+        }
+        // D z = y
+        for (zj, dj) in z.iter_mut().zip(self.d.iter()) {
+            *zj /= dj;
+        }
+        // Lt x = z
+        for j in (0..s.n).rev() {
+            let mut sum = z[j];
+            for p in s.l_col_ptr[j]..s.l_col_ptr[j + 1] {
+                sum -= self.l[p] * z[s.l_row_idx[p]];
+            }
+            z[j] = sum;
+        }
+
+        let mut x = vec![0.0f64; s.n];
+        for (k, &v) in s.perm.iter().enumerate() {
+            x[v] = z[k];
+        }
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///
+    /// Matrix of a network of n junctions linked by the edges (i, j, conductance), each junction
+    /// linked to a fixed head by the given conductance : the pattern of the head system.
+    ///
+    fn network_matrix(n: usize, edges: &[(usize, usize, f64)], to_fixed: f64) -> SymmetricMatrix {
+        let entries: Vec<(usize, usize)> = edges.iter().map(|&(i, j, _)| (i, j)).collect();
+        let (mut matrix, slots) = SymmetricMatrix::with_pattern(n, &entries);
+        for i in 0..n {
+            matrix.add_to_diagonal(i, to_fixed * (1.0 + i as f64 / n as f64));
+        }
+        for (&(i, j, c), &slot) in edges.iter().zip(slots.iter()) {
+            matrix.add_to_diagonal(i, c);
+            matrix.add_to_diagonal(j, c);
+            matrix.add_to_slot(slot, -c);
+        }
+        matrix
+    }
+
+    fn dense(matrix: &SymmetricMatrix) -> Vec<Vec<f64>> {
+        let n = matrix.n;
+        let mut a = vec![vec![0.0f64; n]; n];
+        for (i, row) in a.iter_mut().enumerate() {
+            row[i] = matrix.diagonal[i];
+        }
+        for j in 0..n {
+            for p in matrix.col_ptr[j]..matrix.col_ptr[j + 1] {
+                let i = matrix.row_idx[p];
+                a[i][j] += matrix.values[p];
+                a[j][i] += matrix.values[p];
+            }
+        }
+        a
+    }
+
+    ///
+    /// Gaussian elimination with partial pivoting.
+    ///
+    fn dense_solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Vec<f64> {
+        let n = b.len();
+        for k in 0..n {
+            let p = (k..n)
+                .max_by(|&r, &s| a[r][k].abs().total_cmp(&a[s][k].abs()))
+                .unwrap();
+            a.swap(k, p);
+            b.swap(k, p);
+            for r in k + 1..n {
+                let f = a[r][k] / a[k][k];
+                for c in k..n {
+                    a[r][c] -= f * a[k][c];
+                }
+                b[r] -= f * b[k];
+            }
+        }
+        let mut x = vec![0.0f64; n];
+        for k in (0..n).rev() {
+            let sum: f64 = (k + 1..n).map(|c| a[k][c] * x[c]).sum();
+            x[k] = (b[k] - sum) / a[k][k];
+        }
+        x
+    }
+
+    fn assert_solves(matrix: &SymmetricMatrix) {
+        let n = matrix.n;
+        let b: Vec<f64> = (0..n)
+            .map(|i| 1.0 + (i % 3) as f64 - 0.5 * i as f64)
+            .collect();
+        let symbolic = SymbolicFactor::analyse(matrix);
+        let x = symbolic.factorize(matrix).unwrap().solve(&b);
+        let expected = dense_solve(dense(matrix), b);
+        for (xi, ei) in x.iter().zip(expected.iter()) {
+            assert!(
+                (xi - ei).abs() <= 1e-10 * f64::max(1.0, ei.abs()),
+                "{xi} != {ei}"
+            );
+        }
+    }
+
+    #[test]
+    fn solves_a_path() {
+        let edges: Vec<(usize, usize, f64)> = (0..9).map(|i| (i, i + 1, 1.0 + i as f64)).collect();
+        assert_solves(&network_matrix(10, &edges, 0.1));
+    }
+
+    #[test]
+    fn solves_a_grid() {
+        let (rows, cols) = (4, 5);
+        let mut edges: Vec<(usize, usize, f64)> = Vec::new();
+        for r in 0..rows {
+            for c in 0..cols {
+                let i = r * cols + c;
+                if c + 1 < cols {
+                    edges.push((i, i + 1, 2.0 + c as f64));
+                }
+                if r + 1 < rows {
+                    edges.push((i + cols, i, 0.5 + r as f64));
+                }
+            }
+        }
+        assert_solves(&network_matrix(rows * cols, &edges, 0.01));
+    }
+
+    #[test]
+    fn solves_a_star() {
+        let edges: Vec<(usize, usize, f64)> = (1..8).map(|i| (0, i, i as f64)).collect();
+        assert_solves(&network_matrix(8, &edges, 1.0));
+    }
+
+    #[test]
+    fn merges_duplicated_entries() {
+        // parallel links between the same junctions, in both orders
+        let edges = [
+            (0, 1, 1.0),
+            (1, 0, 2.0),
+            (1, 2, 3.0),
+            (1, 2, 0.5),
+            (2, 3, 1.0),
+        ];
+        let matrix = network_matrix(4, &edges, 0.2);
+        assert_eq!(matrix.row_idx.len(), 3);
+        assert_eq!(dense(&matrix)[0][1], -3.0);
+        assert_solves(&matrix);
+    }
+
+    #[test]
+    fn solves_a_single_junction() {
+        let matrix = network_matrix(1, &[], 4.0);
+        let symbolic = SymbolicFactor::analyse(&matrix);
+        assert_eq!(symbolic.factor_size(), 0);
+        let x = symbolic.factorize(&matrix).unwrap().solve(&[2.0]);
+        assert_eq!(x, vec![0.5]);
+    }
+
+    #[test]
+    fn null_pivot_reports_the_unpermuted_row() {
+        // the leaves of a star are eliminated before its center : the leaf 3 without
+        // diagonal term gives a null first pivot
+        let edges: Vec<(usize, usize, f64)> = (1..6).map(|i| (0, i, 1.0)).collect();
+        let entries: Vec<(usize, usize)> = edges.iter().map(|&(i, j, _)| (i, j)).collect();
+        let (mut matrix, slots) = SymmetricMatrix::with_pattern(6, &entries);
+        for (&(i, j, c), &slot) in edges.iter().zip(slots.iter()) {
+            matrix.add_to_diagonal(i, c);
+            if j != 3 {
+                matrix.add_to_diagonal(j, c);
+            }
+            matrix.add_to_slot(slot, -c);
+        }
+        let symbolic = SymbolicFactor::analyse(&matrix);
+        assert_ne!(symbolic.perm.iter().position(|&v| v == 3), Some(3));
+        assert_eq!(
+            symbolic.factorize(&matrix).err(),
+            Some(FactorError::NullPivot(3))
+        );
+    }
+
+    #[test]
+    fn rejects_a_matrix_of_another_size() {
+        let symbolic = SymbolicFactor::analyse(&network_matrix(3, &[(0, 1, 1.0)], 1.0));
+        let other = network_matrix(4, &[(0, 1, 1.0)], 1.0);
+        assert_eq!(
+            symbolic.factorize(&other).err(),
+            Some(FactorError::SizeMismatch {
+                expected: 3,
+                found: 4
+            })
+        );
+    }
+}
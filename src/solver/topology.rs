@@ -0,0 +1,130 @@
+//!
+//! Structure of the head system of a network. It depends only on the nodes and on the ends of
+//! the links : it is analysed once and shared by the computations on the same topology
+//! (the link parameters, statuses and the demands may change between them).
+//!
+use super::LinkEnd;
+use super::sparse::{SymbolicFactor, SymmetricMatrix};
+use crate::network::Network;
+use crate::network::index::{NetworkIndex, NodeRef};
+
+#[derive(Debug, Clone)]
+pub(crate) struct Topology {
+    ///
+    /// Lookup index of the network elements.
+    ///
+    pub(super) index: NetworkIndex,
+
+    ///
+    /// Ids of the junctions, tanks then reservoirs.
+    ///
+    node_ids: Vec<usize>,
+
+    ///
+    /// (start node id, end node id) of the links, ordered as in the solver : pipes, pumps then valves.
+    ///
+    pub(super) ends: Vec<(usize, usize)>,
+
+    ///
+    /// Ends of the links in the unknowns (A21 & A10) :
+    /// -1 at the start node of a link, +1 at its end node.
+    ///
+    pub(super) links: Vec<(LinkEnd, LinkEnd)>,
+
+    ///
+    /// Slot in V of the off-diagonal entry of each link between two junctions.
+    ///
+    pub(super) link_slot: Vec<Option<usize>>,
+
+    ///
+    /// Pattern of V = A21 x A11^-1 x A12 and its ordering, factor structure.
+    ///
+    pub(super) pattern: SymmetricMatrix,
+    pub(super) symbolic: SymbolicFactor,
+}
+
+impl Topology {
+    pub fn analyse(network: &Network) -> Self {
+        let index = NetworkIndex::build(network);
+        let node_ids = Self::node_ids(network);
+        let ends = Self::link_ends(network);
+        let junction_count = network.junctions.as_ref().map_or(0, |j| j.len());
+        let tank_count = network.tanks.as_ref().map_or(0, |t| t.len());
+
+        let end_of = |node_id: usize| match index.node(node_id) {
+            Some(NodeRef::Junction(i)) => LinkEnd::Junction(i),
+            Some(NodeRef::Tank(j)) => LinkEnd::FixedHead(j),
+            Some(NodeRef::Reservoir(j)) => LinkEnd::FixedHead(j + tank_count),
+            None => LinkEnd::Missing,
+        };
+        let links: Vec<(LinkEnd, LinkEnd)> = ends
+            .iter()
+            .map(|(start, end)| (end_of(*start), end_of(*end)))
+            .collect();
+
+        // links between two junctions : off-diagonal entries of V
+        let mut entries: Vec<(usize, usize)> = Vec::new();
+        let mut link_slot: Vec<Option<usize>> = vec![None; links.len()];
+        for (k, link) in links.iter().enumerate() {
+            if let (LinkEnd::Junction(i), LinkEnd::Junction(j)) = *link
+                && i != j
+            {
+                link_slot[k] = Some(entries.len());
+                entries.push((i, j));
+            }
+        }
+        let (pattern, slots) = SymmetricMatrix::with_pattern(junction_count, &entries);
+        for slot in link_slot.iter_mut().flatten() {
+            *slot = slots[*slot];
+        }
+        let symbolic = SymbolicFactor::analyse(&pattern);
+
+        log::trace!("links ends (A21, A10) : {:?}", links);
+        log::debug!("factor size : {}", symbolic.factor_size());
+
+        Topology {
+            index,
+            node_ids,
+            ends,
+            links,
+            link_slot,
+            pattern,
+            symbolic,
+        }
+    }
+
+    ///
+    /// True if the network has the nodes and the links ends of the analysed topology.
+    ///
+    pub fn matches(&self, network: &Network) -> bool {
+        self.node_ids == Self::node_ids(network) && self.ends == Self::link_ends(network)
+    }
+
+    fn node_ids(network: &Network) -> Vec<usize> {
+        let mut ids: Vec<usize> = Vec::new();
+        if let Some(junctions) = &network.junctions {
+            ids.extend(junctions.iter().map(|jn| jn.id));
+        }
+        if let Some(tanks) = &network.tanks {
+            ids.extend(tanks.iter().map(|tnk| tnk.id));
+        }
+        if let Some(reservoirs) = &network.reservoirs {
+            ids.extend(reservoirs.iter().map(|rsrvr| rsrvr.id));
+        }
+        ids
+    }
+
+    fn link_ends(network: &Network) -> Vec<(usize, usize)> {
+        let mut ends: Vec<(usize, usize)> = Vec::new();
+        if let Some(pipes) = &network.pipes {
+            ends.extend(pipes.iter().map(|p| (p.start, p.end)));
+        }
+        if let Some(pumps) = &network.pumps {
+            ends.extend(pumps.iter().map(|p| (p.start, p.end)));
+        }
+        if let Some(valves) = &network.valves {
+            ends.extend(valves.iter().map(|v| (v.start, v.end)));
+        }
+        ends
+    }
+}
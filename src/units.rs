@@ -0,0 +1,307 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::network::FlowUnits;
+
+//------------------ Flow units to m3/s ------------------
+pub(crate) const CFS_FACTOR: f64 = 0.028316846592;
+pub(crate) const GPM_FACTOR: f64 = 6.30901964e-5;
+pub(crate) const MGD_FACTOR: f64 = 0.0438126364;
+pub(crate) const IMGD_FACTOR: f64 = 0.0526167824;
+pub(crate) const AFD_FACTOR: f64 = 0.0142764101;
+pub(crate) const LPS_FACTOR: f64 = 0.001;
+pub(crate) const LPM_FACTOR: f64 = 0.001 / 60.0;
+pub(crate) const MLD_FACTOR: f64 = 1000.0 / 86400.0;
+pub(crate) const CMH_FACTOR: f64 = 1.0 / 3600.0;
+pub(crate) const CMD_FACTOR: f64 = 1.0 / 86400.0;
+
+//------------------ US customary units to SI ------------------
+/// feet to m.
+pub(crate) const FEET_FACTOR: f64 = 0.3048;
+/// inch to m.
+pub(crate) const INCH_FACTOR: f64 = 0.0254;
+/// psi to m (water column).
+pub(crate) const PSI_FACTOR: f64 = 0.70307;
+/// cubic feet to m3.
+pub(crate) const CUBIC_FEET_FACTOR: f64 = 0.028316846592;
+
+///
+/// Unit system of a network. As in Epanet, the flow unit sets the unit system :
+/// CFS, GPM, MGD, IMGD and AFD are US customary units, the others are SI units.
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum UnitSystem {
+    ///
+    /// length, head and pressure in m, diameter in mm, volume in m3.
+    ///
+    Si,
+
+    ///
+    /// length and head in ft, diameter in inch, pressure in psi, volume in ft3.
+    ///
+    Us,
+}
+
+impl UnitSystem {
+    pub fn from_flow_unit(flow_unit: FlowUnits) -> Self {
+        match flow_unit {
+            FlowUnits::Cfs | FlowUnits::Gpm | FlowUnits::Mgd | FlowUnits::Imgd | FlowUnits::Afd => {
+                UnitSystem::Us
+            }
+            _ => UnitSystem::Si,
+        }
+    }
+}
+
+///
+/// Physical quantities handled by the network.
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Quantity {
+    Flow,
+    Length,
+    Diameter,
+    Pressure,
+    Head,
+    Volume,
+    Velocity,
+}
+
+///
+/// Units of a network : the conversion factors from the network units to the units used by the solver
+/// (flow in m3/s, length, diameter, pressure and head in m, volume in m3, velocity in m/s).
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Units {
+    pub flow_unit: FlowUnits,
+    pub system: UnitSystem,
+}
+
+impl Units {
+    pub fn new(flow_unit: FlowUnits) -> Self {
+        Units {
+            flow_unit,
+            system: UnitSystem::from_flow_unit(flow_unit),
+        }
+    }
+
+    ///
+    /// Multiplier from the network unit of the quantity to SI.
+    ///
+    pub fn factor(&self, quantity: Quantity) -> f64 {
+        match (quantity, self.system) {
+            (Quantity::Flow, _) => flow_factor(self.flow_unit),
+            (Quantity::Diameter, UnitSystem::Si) => 0.001,
+            (Quantity::Diameter, UnitSystem::Us) => INCH_FACTOR,
+            (Quantity::Length | Quantity::Head, UnitSystem::Si) => 1.0,
+            (Quantity::Length | Quantity::Head, UnitSystem::Us) => FEET_FACTOR,
+            (Quantity::Pressure, UnitSystem::Si) => 1.0,
+            (Quantity::Pressure, UnitSystem::Us) => PSI_FACTOR,
+            (Quantity::Volume, UnitSystem::Si) => 1.0,
+            (Quantity::Volume, UnitSystem::Us) => CUBIC_FEET_FACTOR,
+            (Quantity::Velocity, UnitSystem::Si) => 1.0,
+            (Quantity::Velocity, UnitSystem::Us) => FEET_FACTOR,
+        }
+    }
+
+    ///
+    /// Convert a value from the network unit to SI.
+    ///
+    pub fn to_si(&self, quantity: Quantity, value: f64) -> f64 {
+        value * self.factor(quantity)
+    }
+
+    ///
+    /// Convert a value from SI to the network unit.
+    ///
+    pub fn from_si(&self, quantity: Quantity, value: f64) -> f64 {
+        value / self.factor(quantity)
+    }
+
+    ///
+    /// Label of the network unit of the quantity.
+    ///
+    pub fn label(&self, quantity: Quantity) -> String {
+        match (quantity, self.system) {
+            (Quantity::Flow, _) => self.flow_unit.to_string(),
+            (Quantity::Diameter, UnitSystem::Si) => "mm".to_owned(),
+            (Quantity::Diameter, UnitSystem::Us) => "in".to_owned(),
+            (Quantity::Length | Quantity::Head | Quantity::Pressure, UnitSystem::Si) => {
+                "m".to_owned()
+            }
+            (Quantity::Length | Quantity::Head, UnitSystem::Us) => "ft".to_owned(),
+            (Quantity::Pressure, UnitSystem::Us) => "psi".to_owned(),
+            (Quantity::Volume, UnitSystem::Si) => "m3".to_owned(),
+            (Quantity::Volume, UnitSystem::Us) => "ft3".to_owned(),
+            (Quantity::Velocity, UnitSystem::Si) => "m/s".to_owned(),
+            (Quantity::Velocity, UnitSystem::Us) => "ft/s".to_owned(),
+        }
+    }
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units::new(FlowUnits::default())
+    }
+}
+
+impl Display for Units {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "flow: {}, length: {}, diameter: {}, pressure: {}",
+            self.label(Quantity::Flow),
+            self.label(Quantity::Length),
+            self.label(Quantity::Diameter),
+            self.label(Quantity::Pressure)
+        )
+    }
+}
+
+///
+/// Multiplier from the flow unit to m3/s.
+///
+pub fn flow_factor(flow_unit: FlowUnits) -> f64 {
+    match flow_unit {
+        FlowUnits::Cfs => CFS_FACTOR,
+        FlowUnits::Gpm => GPM_FACTOR,
+        FlowUnits::Mgd => MGD_FACTOR,
+        FlowUnits::Imgd => IMGD_FACTOR,
+        FlowUnits::Afd => AFD_FACTOR,
+        FlowUnits::Lps => LPS_FACTOR,
+        FlowUnits::Lpm => LPM_FACTOR,
+        FlowUnits::Mld => MLD_FACTOR,
+        FlowUnits::Cms => 1.0,
+        FlowUnits::Cmh => CMH_FACTOR,
+        FlowUnits::Cmd => CMD_FACTOR,
+    }
+}
+
+///
+/// Flow unit from its Epanet keyword (CFS, GPM, MGD, IMGD, AFD, LPS, LPM, MLD, CMH, CMD, CMS).
+///
+pub fn parse_flow_unit(keyword: &str) -> Option<FlowUnits> {
+    match keyword.trim().to_uppercase().as_str() {
+        "CFS" => Some(FlowUnits::Cfs),
+        "GPM" => Some(FlowUnits::Gpm),
+        "MGD" => Some(FlowUnits::Mgd),
+        "IMGD" => Some(FlowUnits::Imgd),
+        "AFD" => Some(FlowUnits::Afd),
+        "LPS" => Some(FlowUnits::Lps),
+        "LPM" => Some(FlowUnits::Lpm),
+        "MLD" => Some(FlowUnits::Mld),
+        "CMH" => Some(FlowUnits::Cmh),
+        "CMD" => Some(FlowUnits::Cmd),
+        "CMS" => Some(FlowUnits::Cms),
+        _ => None,
+    }
+}
+
+///
+/// Epanet keyword of the flow unit.
+///
+pub fn flow_unit_keyword(flow_unit: FlowUnits) -> &'static str {
+    match flow_unit {
+        FlowUnits::Cfs => "CFS",
+        FlowUnits::Gpm => "GPM",
+        FlowUnits::Mgd => "MGD",
+        FlowUnits::Imgd => "IMGD",
+        FlowUnits::Afd => "AFD",
+        FlowUnits::Lps => "LPS",
+        FlowUnits::Lpm => "LPM",
+        FlowUnits::Mld => "MLD",
+        FlowUnits::Cmh => "CMH",
+        FlowUnits::Cmd => "CMD",
+        FlowUnits::Cms => "CMS",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOW_UNITS: [FlowUnits; 11] = [
+        FlowUnits::Cfs,
+        FlowUnits::Gpm,
+        FlowUnits::Mgd,
+        FlowUnits::Imgd,
+        FlowUnits::Afd,
+        FlowUnits::Lps,
+        FlowUnits::Lpm,
+        FlowUnits::Mld,
+        FlowUnits::Cms,
+        FlowUnits::Cmh,
+        FlowUnits::Cmd,
+    ];
+
+    const QUANTITIES: [Quantity; 7] = [
+        Quantity::Flow,
+        Quantity::Length,
+        Quantity::Diameter,
+        Quantity::Pressure,
+        Quantity::Head,
+        Quantity::Volume,
+        Quantity::Velocity,
+    ];
+
+    #[test]
+    fn converts_back_and_forth() {
+        for flow_unit in FLOW_UNITS {
+            let units = Units::new(flow_unit);
+            for quantity in QUANTITIES {
+                let value = 123.456;
+                let back = units.from_si(quantity, units.to_si(quantity, value));
+                assert!(
+                    (back - value).abs() < 1e-9,
+                    "{:?} {:?} : {}",
+                    flow_unit,
+                    quantity,
+                    back
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn converts_the_flows() {
+        // 1 l/s
+        let expected = [
+            0.0353147, 15.850323, 0.0228245, 0.0190053, 0.0700457, 1.0, 60.0, 0.0864, 0.001, 3.6,
+            86.4,
+        ];
+        for (flow_unit, expected) in FLOW_UNITS.iter().zip(expected) {
+            let flow = Units::new(*flow_unit).from_si(Quantity::Flow, 0.001);
+            assert!(
+                (flow - expected).abs() < 1e-5 * expected,
+                "{:?} : {}",
+                flow_unit,
+                flow
+            );
+        }
+    }
+
+    #[test]
+    fn converts_the_us_pressures_and_heads() {
+        let units = Units::new(FlowUnits::Gpm);
+        assert_eq!(units.system, UnitSystem::Us);
+        // 100 ft of water column is 43.35 psi
+        let head = units.to_si(Quantity::Head, 100.0);
+        assert!((head - 30.48).abs() < 1e-12);
+        let pressure = units.from_si(Quantity::Pressure, head);
+        assert!((pressure - 43.35).abs() < 0.01);
+        assert_eq!(units.label(Quantity::Pressure), "psi");
+        assert_eq!(units.label(Quantity::Head), "ft");
+        assert!((units.to_si(Quantity::Diameter, 12.0) - 0.3048).abs() < 1e-12);
+    }
+
+    #[test]
+    fn parses_the_flow_unit_keywords() {
+        for flow_unit in FLOW_UNITS {
+            let keyword = flow_unit_keyword(flow_unit);
+            assert_eq!(parse_flow_unit(keyword), Some(flow_unit));
+            assert_eq!(parse_flow_unit(&keyword.to_lowercase()), Some(flow_unit));
+        }
+        assert_eq!(parse_flow_unit("GALLONS"), None);
+    }
+}